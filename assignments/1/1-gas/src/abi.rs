@@ -0,0 +1,232 @@
+//! Just enough Solidity ABI encoding to build calldata for the EVM
+//! interpreter: elementary types, `bytes` and `string`, no arrays or tuples.
+
+use std::fmt;
+
+use ethereum_types::U256;
+
+use crate::evm::keccak256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiError {
+    InvalidSignature(String),
+    UnsupportedType(String),
+    InvalidValue { ty: String, value: String },
+    ArgumentCount { expected: usize, got: usize },
+}
+
+impl fmt::Display for AbiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbiError::InvalidSignature(sig) => write!(f, "`{}` is not a function signature like `transfer(address,uint256)`", sig),
+            AbiError::UnsupportedType(ty) => write!(f, "ABI type `{}` is not supported", ty),
+            AbiError::InvalidValue { ty, value } => write!(f, "`{}` is not a valid {}", value, ty),
+            AbiError::ArgumentCount { expected, got } => {
+                write!(f, "expected {} arguments, got {}", expected, got)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AbiError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AbiType {
+    Uint(usize),
+    Int(usize),
+    Address,
+    Bool,
+    FixedBytes(usize),
+    Bytes,
+    String,
+}
+
+impl AbiType {
+    fn parse(ty: &str) -> Result<Self, AbiError> {
+        let unsupported = || AbiError::UnsupportedType(ty.to_string());
+        let bits = |digits: &str| -> Result<usize, AbiError> {
+            if digits.is_empty() {
+                return Ok(256);
+            }
+            match digits.parse::<usize>() {
+                Ok(bits) if bits > 0 && bits <= 256 && bits % 8 == 0 => Ok(bits),
+                _ => Err(unsupported()),
+            }
+        };
+
+        match ty {
+            "address" => Ok(AbiType::Address),
+            "bool" => Ok(AbiType::Bool),
+            "bytes" => Ok(AbiType::Bytes),
+            "string" => Ok(AbiType::String),
+            _ if ty.starts_with("uint") => Ok(AbiType::Uint(bits(&ty[4..])?)),
+            _ if ty.starts_with("int") => Ok(AbiType::Int(bits(&ty[3..])?)),
+            _ if ty.starts_with("bytes") => match ty[5..].parse::<usize>() {
+                Ok(size) if (1..=32).contains(&size) => Ok(AbiType::FixedBytes(size)),
+                _ => Err(unsupported()),
+            },
+            _ => Err(unsupported()),
+        }
+    }
+
+    /// Name used when hashing the signature, e.g. `uint` becomes `uint256`.
+    fn canonical(self) -> String {
+        match self {
+            AbiType::Uint(bits) => format!("uint{}", bits),
+            AbiType::Int(bits) => format!("int{}", bits),
+            AbiType::Address => String::from("address"),
+            AbiType::Bool => String::from("bool"),
+            AbiType::FixedBytes(size) => format!("bytes{}", size),
+            AbiType::Bytes => String::from("bytes"),
+            AbiType::String => String::from("string"),
+        }
+    }
+
+    fn is_dynamic(self) -> bool {
+        matches!(self, AbiType::Bytes | AbiType::String)
+    }
+}
+
+/// Splits `name(type,...)` into its name and parameter types.
+fn parse_signature(signature: &str) -> Result<(&str, Vec<AbiType>), AbiError> {
+    let invalid = || AbiError::InvalidSignature(signature.to_string());
+    let signature = signature.trim();
+    let open = signature.find('(').ok_or_else(invalid)?;
+    let params = signature[open + 1..].strip_suffix(')').ok_or_else(invalid)?;
+
+    let types = if params.trim().is_empty() {
+        Vec::new()
+    } else {
+        params
+            .split(',')
+            .map(|ty| AbiType::parse(ty.trim()))
+            .collect::<Result<_, _>>()?
+    };
+    Ok((&signature[..open], types))
+}
+
+/// First four bytes of the keccak hash of the canonical signature.
+pub fn selector(signature: &str) -> Result<[u8; 4], AbiError> {
+    let (name, types) = parse_signature(signature)?;
+    let canonical: Vec<String> = types.iter().map(|ty| ty.canonical()).collect();
+    let hash = keccak256(format!("{}({})", name, canonical.join(",")).as_bytes());
+    Ok([hash[0], hash[1], hash[2], hash[3]])
+}
+
+/// Encodes a call. If the signature has no name, as in `(address,uint256)`,
+/// only the arguments are encoded; that is what constructors take.
+pub fn encode_call(signature: &str, args: &[&str]) -> Result<Vec<u8>, AbiError> {
+    let (name, types) = parse_signature(signature)?;
+    if types.len() != args.len() {
+        return Err(AbiError::ArgumentCount {
+            expected: types.len(),
+            got: args.len(),
+        });
+    }
+
+    let mut encoded = Vec::new();
+    if !name.is_empty() {
+        encoded.extend_from_slice(&selector(signature)?);
+    }
+
+    let head_size = 32 * types.len();
+    let mut head = Vec::with_capacity(head_size);
+    let mut tail = Vec::new();
+    for (ty, arg) in types.iter().zip(args) {
+        if ty.is_dynamic() {
+            head.extend_from_slice(&word(U256::from(head_size + tail.len())));
+            tail.extend(encode_dynamic(*ty, arg)?);
+        } else {
+            head.extend_from_slice(&encode_static(*ty, arg)?);
+        }
+    }
+
+    encoded.extend(head);
+    encoded.extend(tail);
+    Ok(encoded)
+}
+
+fn word(value: U256) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    value.to_big_endian(&mut out);
+    out
+}
+
+fn parse_hex(value: &str) -> Option<Vec<u8>> {
+    hex::decode(value.strip_prefix("0x")?).ok()
+}
+
+fn parse_uint(value: &str) -> Option<U256> {
+    match value.strip_prefix("0x") {
+        Some(digits) => U256::from_str_radix(digits, 16).ok(),
+        None => U256::from_dec_str(value).ok(),
+    }
+}
+
+fn encode_static(ty: AbiType, value: &str) -> Result<[u8; 32], AbiError> {
+    let invalid = || AbiError::InvalidValue {
+        ty: ty.canonical(),
+        value: value.to_string(),
+    };
+
+    let encoded = match ty {
+        AbiType::Uint(bits) => {
+            let n = parse_uint(value).ok_or_else(invalid)?;
+            if n.bits() > bits {
+                return Err(invalid());
+            }
+            word(n)
+        }
+        AbiType::Int(bits) => {
+            let (negative, digits) = match value.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, value),
+            };
+            let magnitude = parse_uint(digits).ok_or_else(invalid)?;
+            // Largest magnitude is 2^(bits-1), and only for negative numbers.
+            let limit = U256::one() << (bits - 1);
+            if magnitude > limit || (!negative && magnitude == limit) {
+                return Err(invalid());
+            }
+            if negative {
+                word((!magnitude).overflowing_add(U256::one()).0)
+            } else {
+                word(magnitude)
+            }
+        }
+        AbiType::Address => {
+            let bytes = parse_hex(value).filter(|b| b.len() == 20).ok_or_else(invalid)?;
+            let mut out = [0u8; 32];
+            out[12..].copy_from_slice(&bytes);
+            out
+        }
+        AbiType::Bool => match value {
+            "true" => word(U256::one()),
+            "false" => word(U256::zero()),
+            _ => return Err(invalid()),
+        },
+        AbiType::FixedBytes(size) => {
+            let bytes = parse_hex(value).filter(|b| b.len() <= size).ok_or_else(invalid)?;
+            let mut out = [0u8; 32];
+            out[..bytes.len()].copy_from_slice(&bytes);
+            out
+        }
+        AbiType::Bytes | AbiType::String => unreachable!("dynamic types are encoded in the tail"),
+    };
+    Ok(encoded)
+}
+
+fn encode_dynamic(ty: AbiType, value: &str) -> Result<Vec<u8>, AbiError> {
+    let bytes = match ty {
+        AbiType::String => value.as_bytes().to_vec(),
+        _ => parse_hex(value).ok_or_else(|| AbiError::InvalidValue {
+            ty: ty.canonical(),
+            value: value.to_string(),
+        })?,
+    };
+
+    let mut out = word(U256::from(bytes.len())).to_vec();
+    out.extend_from_slice(&bytes);
+    out.resize(32 + bytes.len().div_ceil(32) * 32, 0);
+    Ok(out)
+}
@@ -0,0 +1,313 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use ethereum_types::{U256, U512};
+use serde::Deserialize;
+
+use crate::units::Wei;
+
+/// Ratio between a block's gas limit and its gas target.
+pub const ELASTICITY_MULTIPLIER: u64 = 2;
+/// The base fee can move by at most 1/8 (12.5%) from one block to the next.
+pub const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+
+/// Gas consumed by one block and the limit it was allowed to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct BlockUsage {
+    pub gas_used: u64,
+    pub gas_limit: u64,
+}
+
+/// One row of a simulation: the base fee a block was built with and the base
+/// fee its child will have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulatedBlock {
+    pub number: usize,
+    pub usage: BlockUsage,
+    pub base_fee_per_gas: Wei,
+    pub next_base_fee_per_gas: Wei,
+}
+
+impl SimulatedBlock {
+    /// Gas used as a percentage of the gas target (100% means "exactly on target").
+    pub fn target_utilisation(&self) -> f64 {
+        let target = self.usage.gas_limit / ELASTICITY_MULTIPLIER;
+        self.usage.gas_used as f64 * 100.0 / target as f64
+    }
+}
+
+#[derive(Debug)]
+pub enum SimulationError {
+    GasUsedAboveLimit { block: usize, gas_used: u64, gas_limit: u64 },
+    GasLimitTooLow { block: usize, gas_limit: u64 },
+    /// The base fee after `block` does not fit in 256 bits.
+    BaseFeeOverflow { block: usize },
+    Io(std::io::Error),
+    Parse(String),
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulationError::GasUsedAboveLimit { block, gas_used, gas_limit } => write!(
+                f,
+                "block {} uses {} gas, above its gas limit of {}",
+                block, gas_used, gas_limit
+            ),
+            SimulationError::GasLimitTooLow { block, gas_limit } => {
+                write!(f, "block {} has a gas limit of {}, which leaves no gas target", block, gas_limit)
+            }
+            SimulationError::BaseFeeOverflow { block } => {
+                write!(f, "the base fee after block {} does not fit in 256 bits", block)
+            }
+            SimulationError::Io(err) => write!(f, "could not read block series: {}", err),
+            SimulationError::Parse(msg) => write!(f, "could not parse block series: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SimulationError {}
+
+impl From<std::io::Error> for SimulationError {
+    fn from(err: std::io::Error) -> Self {
+        SimulationError::Io(err)
+    }
+}
+
+/// Why the base fee after a block cannot be computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseFeeError {
+    GasUsedAboveLimit { gas_used: u64, gas_limit: u64 },
+    GasLimitTooLow { gas_limit: u64 },
+    /// The new base fee does not fit in 256 bits.
+    Overflow,
+}
+
+impl fmt::Display for BaseFeeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BaseFeeError::GasUsedAboveLimit { gas_used, gas_limit } => {
+                write!(f, "{} gas used is above the gas limit of {}", gas_used, gas_limit)
+            }
+            BaseFeeError::GasLimitTooLow { gas_limit } => {
+                write!(f, "a gas limit of {} leaves no gas target", gas_limit)
+            }
+            BaseFeeError::Overflow => write!(f, "the base fee does not fit in 256 bits"),
+        }
+    }
+}
+
+impl std::error::Error for BaseFeeError {}
+
+/// Base fee of the child of a block, following the EIP-1559 update rule.
+///
+/// `parent_gas_limit` must be at least `ELASTICITY_MULTIPLIER`, otherwise the
+/// block has no gas target to compare against, and `parent_gas_used` must not
+/// exceed it.
+pub fn next_base_fee(parent_base_fee: Wei, parent_gas_used: u64, parent_gas_limit: u64) -> Result<Wei, BaseFeeError> {
+    if parent_gas_limit < ELASTICITY_MULTIPLIER {
+        return Err(BaseFeeError::GasLimitTooLow { gas_limit: parent_gas_limit });
+    }
+    if parent_gas_used > parent_gas_limit {
+        return Err(BaseFeeError::GasUsedAboveLimit { gas_used: parent_gas_used, gas_limit: parent_gas_limit });
+    }
+
+    let gas_target = parent_gas_limit / ELASTICITY_MULTIPLIER;
+    let base_fee = parent_base_fee.wei();
+    // The product is taken in 512 bits. With gas used at most the limit, the
+    // gas delta is at most about twice the target, so the change is at most a
+    // quarter of the base fee and always fits back in 256.
+    let change = |gas_delta: u64| {
+        let change = base_fee.full_mul(U256::from(gas_delta))
+            / U512::from(gas_target)
+            / U512::from(BASE_FEE_MAX_CHANGE_DENOMINATOR);
+        U256::try_from(change).expect("the change is below the base fee")
+    };
+
+    if parent_gas_used == gas_target {
+        Ok(parent_base_fee)
+    } else if parent_gas_used > gas_target {
+        let delta = change(parent_gas_used - gas_target).max(U256::one());
+        base_fee.checked_add(delta).map(Wei::from_wei).ok_or(BaseFeeError::Overflow)
+    } else {
+        Ok(Wei::from_wei(base_fee - change(gas_target - parent_gas_used)))
+    }
+}
+
+/// Replays a series of blocks, starting from `initial_base_fee` for the first one.
+pub fn simulate(initial_base_fee: Wei, series: &[BlockUsage]) -> Result<Vec<SimulatedBlock>, SimulationError> {
+    let mut base_fee = initial_base_fee;
+    let mut blocks = Vec::with_capacity(series.len());
+
+    for (number, usage) in series.iter().enumerate() {
+        let next = next_base_fee(base_fee, usage.gas_used, usage.gas_limit).map_err(|err| match err {
+            BaseFeeError::GasUsedAboveLimit { gas_used, gas_limit } => {
+                SimulationError::GasUsedAboveLimit { block: number, gas_used, gas_limit }
+            }
+            BaseFeeError::GasLimitTooLow { gas_limit } => SimulationError::GasLimitTooLow { block: number, gas_limit },
+            BaseFeeError::Overflow => SimulationError::BaseFeeOverflow { block: number },
+        })?;
+        blocks.push(SimulatedBlock {
+            number,
+            usage: *usage,
+            base_fee_per_gas: base_fee,
+            next_base_fee_per_gas: next,
+        });
+        base_fee = next;
+    }
+
+    Ok(blocks)
+}
+
+/// Reads a block series from a `.json` or `.csv` file.
+///
+/// JSON is an array of `{ "gas_used": .., "gas_limit": .. }` objects. CSV has
+/// one `gas_used,gas_limit` pair per line; a header line and blank lines are skipped.
+pub fn load_series(path: &Path) -> Result<Vec<BlockUsage>, SimulationError> {
+    let contents = fs::read_to_string(path)?;
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(&contents).map_err(|e| SimulationError::Parse(e.to_string())),
+        Some("csv") => parse_csv(&contents),
+        _ => Err(SimulationError::Parse(format!(
+            "{} must have a .json or .csv extension",
+            path.display()
+        ))),
+    }
+}
+
+fn parse_csv(contents: &str) -> Result<Vec<BlockUsage>, SimulationError> {
+    let mut series = Vec::new();
+
+    for (line_no, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != 2 {
+            return Err(SimulationError::Parse(format!(
+                "line {}: expected `gas_used,gas_limit`",
+                line_no + 1
+            )));
+        }
+
+        match (fields[0].parse(), fields[1].parse()) {
+            (Ok(gas_used), Ok(gas_limit)) => series.push(BlockUsage { gas_used, gas_limit }),
+            // Only the first line may be a header.
+            _ if line_no == 0 => continue,
+            _ => {
+                return Err(SimulationError::Parse(format!(
+                    "line {}: `{}` is not a pair of integers",
+                    line_no + 1,
+                    line
+                )));
+            }
+        }
+    }
+
+    Ok(series)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Gwei;
+
+    const GAS_LIMIT: u64 = 30_000_000;
+    const GAS_TARGET: u64 = GAS_LIMIT / ELASTICITY_MULTIPLIER;
+
+    fn gwei(amount: u64) -> Wei {
+        Gwei::new(amount).into()
+    }
+
+    #[test]
+    fn full_block_raises_the_base_fee_by_an_eighth() {
+        assert_eq!(next_base_fee(gwei(100), GAS_LIMIT, GAS_LIMIT), Ok(Wei::from_wei(112_500_000_000u64)));
+    }
+
+    #[test]
+    fn empty_block_lowers_the_base_fee_by_an_eighth() {
+        assert_eq!(next_base_fee(gwei(100), 0, GAS_LIMIT), Ok(Wei::from_wei(87_500_000_000u64)));
+    }
+
+    #[test]
+    fn block_at_target_keeps_the_base_fee() {
+        assert_eq!(next_base_fee(gwei(100), GAS_TARGET, GAS_LIMIT), Ok(gwei(100)));
+    }
+
+    #[test]
+    fn change_is_proportional_and_rounds_down() {
+        // Half way between target and limit: +1/16.
+        assert_eq!(
+            next_base_fee(gwei(100), GAS_TARGET * 3 / 2, GAS_LIMIT),
+            Ok(Wei::from_wei(106_250_000_000u64))
+        );
+        // 7 wei * 1/8 rounds down to zero, but an increase is at least 1 wei.
+        assert_eq!(next_base_fee(Wei::from_wei(7), GAS_LIMIT, GAS_LIMIT), Ok(Wei::from_wei(8)));
+        assert_eq!(next_base_fee(Wei::from_wei(7), 0, GAS_LIMIT), Ok(Wei::from_wei(7)));
+    }
+
+    #[test]
+    fn huge_base_fees_do_not_panic() {
+        let near_max = Wei::from_wei(U256::MAX - U256::from(1000));
+        assert_eq!(next_base_fee(near_max, GAS_LIMIT, GAS_LIMIT), Err(BaseFeeError::Overflow));
+        let lowered = next_base_fee(near_max, 0, GAS_LIMIT).unwrap();
+        assert_eq!(lowered.wei(), near_max.wei() - near_max.wei() / 8);
+
+        let full = BlockUsage { gas_used: GAS_LIMIT, gas_limit: GAS_LIMIT };
+        assert!(matches!(
+            simulate(near_max, &[full]),
+            Err(SimulationError::BaseFeeOverflow { block: 0 })
+        ));
+    }
+
+    #[test]
+    fn gas_limits_without_a_target_are_rejected() {
+        for gas_limit in [0, 1] {
+            assert_eq!(next_base_fee(gwei(100), 0, gas_limit), Err(BaseFeeError::GasLimitTooLow { gas_limit }));
+        }
+        assert_eq!(next_base_fee(gwei(100), 1, 1), Err(BaseFeeError::GasLimitTooLow { gas_limit: 1 }));
+        assert_eq!(next_base_fee(gwei(100), 2, 2), Ok(Wei::from_wei(112_500_000_000u64)));
+    }
+
+    #[test]
+    fn gas_used_above_the_limit_is_rejected() {
+        let over = Err(BaseFeeError::GasUsedAboveLimit { gas_used: GAS_LIMIT + 1, gas_limit: GAS_LIMIT });
+        assert_eq!(next_base_fee(gwei(100), GAS_LIMIT + 1, GAS_LIMIT), over);
+        // Far above the limit, the scaled change would not fit in 256 bits.
+        let near_max = Wei::from_wei(U256::MAX - U256::from(1000));
+        assert_eq!(
+            next_base_fee(near_max, u64::MAX, 2),
+            Err(BaseFeeError::GasUsedAboveLimit { gas_used: u64::MAX, gas_limit: 2 })
+        );
+    }
+
+    #[test]
+    fn simulate_chains_blocks_and_checks_them() {
+        let full = BlockUsage { gas_used: GAS_LIMIT, gas_limit: GAS_LIMIT };
+        let empty = BlockUsage { gas_used: 0, gas_limit: GAS_LIMIT };
+        let blocks = simulate(gwei(8), &[full, full, empty]).unwrap();
+        let fees: Vec<Wei> = blocks.iter().map(|block| block.next_base_fee_per_gas).collect();
+        let expected = [9_000_000_000u64, 10_125_000_000, 8_859_375_000].map(Wei::from_wei);
+        assert_eq!(fees, expected);
+        assert_eq!(blocks[1].base_fee_per_gas, fees[0]);
+        assert_eq!(blocks[0].target_utilisation(), 200.0);
+
+        let over = BlockUsage { gas_used: GAS_LIMIT + 1, gas_limit: GAS_LIMIT };
+        assert!(matches!(simulate(gwei(8), &[full, over]), Err(SimulationError::GasUsedAboveLimit { block: 1, .. })));
+        let no_target = BlockUsage { gas_used: 0, gas_limit: 1 };
+        assert!(matches!(simulate(gwei(8), &[no_target]), Err(SimulationError::GasLimitTooLow { block: 0, .. })));
+    }
+
+    #[test]
+    fn csv_skips_a_header_and_blank_lines() {
+        let series = parse_csv("gas_used,gas_limit\n\n15000000, 30000000\n0,30000000\n").unwrap();
+        let half = BlockUsage { gas_used: GAS_TARGET, gas_limit: GAS_LIMIT };
+        let empty = BlockUsage { gas_used: 0, gas_limit: GAS_LIMIT };
+        assert_eq!(series, [half, empty]);
+        assert!(matches!(parse_csv("1,2\nx,y\n"), Err(SimulationError::Parse(msg)) if msg.starts_with("line 2")));
+    }
+}
@@ -0,0 +1,351 @@
+//! EIP-4844 blob gas: the excess blob gas carried from block to block, the
+//! blob base fee derived from it, and what a transaction with blobs pays.
+
+use std::fmt;
+
+use ethereum_types::U256;
+
+use crate::intrinsic::Hardfork;
+use crate::units::Wei;
+
+/// Every blob is 128 KiB and consumes exactly this much blob gas.
+pub const GAS_PER_BLOB: u64 = 1 << 17;
+pub const MIN_BASE_FEE_PER_BLOB_GAS: u64 = 1;
+/// EIP-7918: a blob never costs less than this much execution gas.
+pub const BLOB_BASE_COST: u64 = 1 << 13;
+
+/// Blob limits and pricing of one fork.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobSchedule {
+    pub target_blobs_per_block: u64,
+    pub max_blobs_per_block: u64,
+    pub max_blobs_per_tx: u64,
+    /// Controls how fast the blob base fee moves; at most 1/8 per block
+    /// with a full or empty parent.
+    pub update_fraction: u64,
+    /// EIP-7918 reserve price tying the blob base fee to the execution base fee.
+    pub reserve_price: bool,
+}
+
+impl BlobSchedule {
+    /// Blob parameters of `fork`, or `None` before Cancun.
+    pub fn for_fork(fork: Hardfork) -> Option<Self> {
+        match fork {
+            Hardfork::Cancun => Some(Self {
+                target_blobs_per_block: 3,
+                max_blobs_per_block: 6,
+                max_blobs_per_tx: 6,
+                update_fraction: 3_338_477,
+                reserve_price: false,
+            }),
+            // EIP-7691 raised the blob throughput.
+            Hardfork::Prague => Some(Self {
+                target_blobs_per_block: 6,
+                max_blobs_per_block: 9,
+                max_blobs_per_tx: 9,
+                update_fraction: 5_007_716,
+                reserve_price: false,
+            }),
+            // EIP-7594 caps a single transaction at 6 blobs and EIP-7918 adds the reserve price.
+            Hardfork::Osaka => Some(Self {
+                target_blobs_per_block: 6,
+                max_blobs_per_block: 9,
+                max_blobs_per_tx: 6,
+                update_fraction: 5_007_716,
+                reserve_price: true,
+            }),
+            // EIP-7892 blob-parameter-only forks raise the throughput again.
+            Hardfork::Bpo1 => Some(Self {
+                target_blobs_per_block: 10,
+                max_blobs_per_block: 15,
+                max_blobs_per_tx: 6,
+                update_fraction: 8_346_193,
+                reserve_price: true,
+            }),
+            Hardfork::Bpo2 => Some(Self {
+                target_blobs_per_block: 14,
+                max_blobs_per_block: 21,
+                max_blobs_per_tx: 6,
+                update_fraction: 11_684_671,
+                reserve_price: true,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn target_blob_gas_per_block(&self) -> u64 {
+        self.target_blobs_per_block * GAS_PER_BLOB
+    }
+
+    pub fn max_blob_gas_per_block(&self) -> u64 {
+        self.max_blobs_per_block * GAS_PER_BLOB
+    }
+}
+
+/// The parent header fields the next block's blob base fee depends on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParentBlobHeader {
+    pub excess_blob_gas: u64,
+    pub blob_gas_used: u64,
+    /// Execution base fee, only read for the EIP-7918 reserve price.
+    pub base_fee_per_gas: Wei,
+}
+
+/// What the blobs of one transaction cost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobCost {
+    pub blobs: u64,
+    /// `blobs * GAS_PER_BLOB`
+    pub blob_gas: u64,
+    pub blob_base_fee: Wei,
+    /// `blob_gas * blob_base_fee`, burnt like the execution base fee.
+    pub blob_fee: Wei,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlobError {
+    Unsupported(Hardfork),
+    NoBlobs,
+    TooManyBlobs { blobs: u64, max: u64 },
+    BlobGasUsedAboveMax { blob_gas_used: u64, max: u64 },
+    Overflow,
+}
+
+impl fmt::Display for BlobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlobError::Unsupported(fork) => write!(f, "blobs need Cancun or later, not {}", fork),
+            BlobError::NoBlobs => write!(f, "a blob transaction carries at least one blob"),
+            BlobError::TooManyBlobs { blobs, max } => {
+                write!(f, "{} blobs is above the limit of {} per transaction", blobs, max)
+            }
+            BlobError::BlobGasUsedAboveMax { blob_gas_used, max } => write!(
+                f,
+                "parent blob gas used {} is above the maximum of {} per block",
+                blob_gas_used, max
+            ),
+            BlobError::Overflow => write!(f, "blob base fee does not fit in 256 bits"),
+        }
+    }
+}
+
+impl std::error::Error for BlobError {}
+
+/// Approximates `factor * e ** (numerator / denominator)` with a Taylor
+/// expansion, exactly as the EIP-4844 reference code does. Returns `None`
+/// if an intermediate value does not fit in 256 bits.
+pub fn fake_exponential(factor: U256, numerator: U256, denominator: U256) -> Option<U256> {
+    let mut output = U256::zero();
+    let mut accumulator = factor.checked_mul(denominator)?;
+    let mut i = U256::one();
+
+    while !accumulator.is_zero() {
+        output = output.checked_add(accumulator)?;
+        accumulator = accumulator.checked_mul(numerator)? / denominator.checked_mul(i)?;
+        i += U256::one();
+    }
+
+    Some(output / denominator)
+}
+
+/// Blob base fee of a block with the given excess blob gas.
+pub fn blob_base_fee(excess_blob_gas: u64, schedule: &BlobSchedule) -> Result<Wei, BlobError> {
+    fake_exponential(
+        U256::from(MIN_BASE_FEE_PER_BLOB_GAS),
+        U256::from(excess_blob_gas),
+        U256::from(schedule.update_fraction),
+    )
+    .map(Wei::from_wei)
+    .ok_or(BlobError::Overflow)
+}
+
+/// Excess blob gas of the child of `parent`. Blob gas used above the target
+/// accumulates, blob gas below it pays the excess back down.
+pub fn excess_blob_gas(parent: &ParentBlobHeader, schedule: &BlobSchedule) -> Result<u64, BlobError> {
+    let max = schedule.max_blob_gas_per_block();
+    if parent.blob_gas_used > max {
+        return Err(BlobError::BlobGasUsedAboveMax {
+            blob_gas_used: parent.blob_gas_used,
+            max,
+        });
+    }
+
+    let target = schedule.target_blob_gas_per_block();
+    let total = parent.excess_blob_gas.checked_add(parent.blob_gas_used).ok_or(BlobError::Overflow)?;
+    if total < target {
+        return Ok(0);
+    }
+
+    if schedule.reserve_price {
+        // While blobs are cheaper than BLOB_BASE_COST of execution gas, the
+        // excess only grows, so the blob fee catches up with execution costs.
+        let execution_floor = parent.base_fee_per_gas.wei().checked_mul(U256::from(BLOB_BASE_COST));
+        let blob_price = blob_base_fee(parent.excess_blob_gas, schedule)?
+            .wei()
+            .checked_mul(U256::from(GAS_PER_BLOB));
+        if execution_floor.ok_or(BlobError::Overflow)? > blob_price.ok_or(BlobError::Overflow)? {
+            let growth = parent.blob_gas_used * (schedule.max_blobs_per_block - schedule.target_blobs_per_block)
+                / schedule.max_blobs_per_block;
+            return parent.excess_blob_gas.checked_add(growth).ok_or(BlobError::Overflow);
+        }
+    }
+
+    Ok(total - target)
+}
+
+/// Prices `blobs` blobs in a block with the given excess blob gas.
+pub fn blob_cost(blobs: u64, excess_blob_gas: u64, schedule: &BlobSchedule) -> Result<BlobCost, BlobError> {
+    if blobs == 0 {
+        return Err(BlobError::NoBlobs);
+    }
+    if blobs > schedule.max_blobs_per_tx {
+        return Err(BlobError::TooManyBlobs {
+            blobs,
+            max: schedule.max_blobs_per_tx,
+        });
+    }
+
+    let blob_gas = blobs * GAS_PER_BLOB;
+    let blob_base_fee = blob_base_fee(excess_blob_gas, schedule)?;
+    Ok(BlobCost {
+        blobs,
+        blob_gas,
+        blob_base_fee,
+        blob_fee: blob_base_fee.checked_mul(blob_gas).ok_or(BlobError::Overflow)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(fork: Hardfork) -> BlobSchedule {
+        BlobSchedule::for_fork(fork).unwrap()
+    }
+
+    fn parent(excess_blob_gas: u64, blobs: u64, base_fee: u64) -> ParentBlobHeader {
+        ParentBlobHeader {
+            excess_blob_gas,
+            blob_gas_used: blobs * GAS_PER_BLOB,
+            base_fee_per_gas: Wei::from_wei(U256::from(base_fee)),
+        }
+    }
+
+    /// Follows a chain of blocks, each `(blobs, want excess, want blob base fee)`.
+    fn follow(fork: Hardfork, base_fee: u64, blocks: &[(u64, u64, u64)]) {
+        let schedule = schedule(fork);
+        let mut excess = 50_000_000;
+        for &(blobs, want_excess, want_fee) in blocks {
+            excess = excess_blob_gas(&parent(excess, blobs, base_fee), &schedule).unwrap();
+            assert_eq!(excess, want_excess, "{} after {} blobs", fork, blobs);
+            assert_eq!(blob_base_fee(excess, &schedule).unwrap().wei(), want_fee.into(), "{}", fork);
+        }
+    }
+
+    #[test]
+    fn fake_exponential_matches_the_eip_4844_vectors() {
+        // go-ethereum's TestFakeExponential.
+        let vectors: [(u64, u64, u64, u64); 15] = [
+            (1, 0, 1, 1),
+            (38493, 0, 1000, 38493),
+            (0, 1234, 2345, 0),
+            (1, 2, 1, 6),
+            (1, 4, 2, 6),
+            (1, 3, 1, 16),
+            (1, 6, 2, 18),
+            (1, 4, 1, 49),
+            (1, 8, 2, 50),
+            (10, 8, 2, 542),
+            (11, 8, 2, 596),
+            (1, 5, 1, 136),
+            (1, 5, 2, 11),
+            (2, 5, 2, 23),
+            (1, 50_000_000, 2_225_652, 5_709_098_764),
+        ];
+        for (factor, numerator, denominator, want) in vectors {
+            let got = fake_exponential(factor.into(), numerator.into(), denominator.into());
+            assert_eq!(got, Some(U256::from(want)), "{} * e^({} / {})", factor, numerator, denominator);
+        }
+        assert_eq!(fake_exponential(U256::MAX, 1.into(), 1.into()), None);
+    }
+
+    // The sequences below were checked against `BlobParams::next_block_excess_blob_gas_osaka`
+    // and `calc_blob_fee` from alloy-eips 1.8.3.
+
+    #[test]
+    fn excess_blob_gas_follows_usage_in_prague() {
+        let full = 9;
+        follow(
+            Hardfork::Prague,
+            1_000_000_000,
+            &[
+                (full, 50_393_216, 23_461),
+                (full, 50_786_432, 25_377),
+                (6, 50_786_432, 25_377),
+                (0, 50_000_000, 21_689),
+                (1, 49_344_640, 19_029),
+            ],
+        );
+        // Under the target with no excess left, the excess stays at zero.
+        assert_eq!(excess_blob_gas(&parent(GAS_PER_BLOB - 1, 5, 0), &schedule(Hardfork::Prague)), Ok(0));
+    }
+
+    #[test]
+    fn eip_7918_keeps_the_excess_from_falling_while_blobs_are_cheap() {
+        // At 1 gwei, 8192 gas of execution costs more than a blob, so from Osaka
+        // the excess only grows, by 3/9 of the blob gas used.
+        follow(
+            Hardfork::Osaka,
+            1_000_000_000,
+            &[
+                (9, 50_393_216, 23_461),
+                (9, 50_786_432, 25_377),
+                (6, 51_048_576, 26_741),
+                (0, 51_048_576, 26_741),
+                (1, 51_092_266, 26_976),
+            ],
+        );
+        // With a 1 wei execution base fee the reserve price never binds and Osaka acts like Prague.
+        follow(
+            Hardfork::Osaka,
+            1,
+            &[(9, 50_393_216, 23_461), (6, 50_393_216, 23_461), (0, 49_606_784, 20_051)],
+        );
+        // go-ethereum's TestCalcExcessBlobGasEIP7918: a block at the target.
+        let osaka = schedule(Hardfork::Osaka);
+        assert_eq!(excess_blob_gas(&parent(0, 6, 1_000_000_000), &osaka), Ok(6 * GAS_PER_BLOB * 3 / 9));
+        assert_eq!(excess_blob_gas(&parent(0, 6, 1), &osaka), Ok(0));
+    }
+
+    #[test]
+    fn bpo_forks_raise_the_blob_target() {
+        follow(
+            Hardfork::Bpo1,
+            1_000_000_000,
+            &[(15, 50_655_360, 432), (15, 51_310_720, 467), (10, 51_747_626, 492), (0, 51_747_626, 492)],
+        );
+        follow(
+            Hardfork::Bpo2,
+            1_000_000_000,
+            &[(21, 50_917_504, 78), (21, 51_835_008, 84), (14, 52_446_677, 88), (1, 52_490_367, 89)],
+        );
+        assert_eq!(
+            excess_blob_gas(&parent(0, 22, 0), &schedule(Hardfork::Bpo2)),
+            Err(BlobError::BlobGasUsedAboveMax { blob_gas_used: 22 * GAS_PER_BLOB, max: 21 * GAS_PER_BLOB })
+        );
+    }
+
+    #[test]
+    fn blob_cost_checks_the_per_transaction_limit() {
+        let cost = blob_cost(2, 50_000_000, &schedule(Hardfork::Prague)).unwrap();
+        assert_eq!(cost.blob_gas, 2 * GAS_PER_BLOB);
+        assert_eq!(cost.blob_fee.wei(), U256::from(2 * GAS_PER_BLOB * 21_689));
+
+        assert!(blob_cost(9, 0, &schedule(Hardfork::Prague)).is_ok());
+        for fork in [Hardfork::Osaka, Hardfork::Bpo1, Hardfork::Bpo2] {
+            assert_eq!(blob_cost(7, 0, &schedule(fork)), Err(BlobError::TooManyBlobs { blobs: 7, max: 6 }));
+        }
+        assert_eq!(blob_cost(0, 0, &schedule(Hardfork::Cancun)), Err(BlobError::NoBlobs));
+        assert_eq!(BlobSchedule::for_fork(Hardfork::Shanghai), None);
+    }
+}
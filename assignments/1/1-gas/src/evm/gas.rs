@@ -0,0 +1,60 @@
+//! Gas constants and dynamic cost formulas (Osaka schedule).
+
+use ethereum_types::U256;
+
+pub const JUMPDEST: u64 = 1;
+pub const BASE: u64 = 2;
+pub const VERY_LOW: u64 = 3;
+pub const LOW: u64 = 5;
+pub const MID: u64 = 8;
+pub const HIGH: u64 = 10;
+pub const BLOCKHASH: u64 = 20;
+
+pub const EXP: u64 = 10;
+pub const EXP_BYTE: u64 = 50;
+pub const KECCAK256: u64 = 30;
+pub const KECCAK256_WORD: u64 = 6;
+pub const COPY_WORD: u64 = 3;
+pub const MEMORY_WORD: u64 = 3;
+pub const QUADRATIC_DENOMINATOR: u64 = 512;
+
+pub const LOG: u64 = 375;
+pub const LOG_TOPIC: u64 = 375;
+pub const LOG_DATA_BYTE: u64 = 8;
+
+/// EIP-2929 access costs.
+pub const WARM_ACCESS: u64 = 100;
+pub const COLD_ACCOUNT_ACCESS: u64 = 2_600;
+pub const COLD_SLOAD: u64 = 2_100;
+
+pub const TRANSIENT: u64 = 100;
+
+pub const CALL_VALUE: u64 = 9_000;
+pub const CALL_STIPEND: u64 = 2_300;
+pub const NEW_ACCOUNT: u64 = 25_000;
+pub const SELFDESTRUCT: u64 = 5_000;
+
+pub const CREATE: u64 = 32_000;
+pub const INITCODE_WORD: u64 = 2;
+pub const CODE_DEPOSIT_BYTE: u64 = 200;
+pub const MAX_CODE_SIZE: usize = 24_576;
+pub const MAX_INITCODE_SIZE: usize = 2 * MAX_CODE_SIZE;
+
+pub const CALL_DEPTH_LIMIT: usize = 1024;
+pub const STACK_LIMIT: usize = 1024;
+
+/// Number of 32-byte words needed to hold `len` bytes.
+pub fn words(len: usize) -> u64 {
+    (len as u64).div_ceil(32)
+}
+
+/// Total cost of a memory of `words` words: linear part plus quadratic part.
+pub fn memory_cost(words: u64) -> u64 {
+    MEMORY_WORD * words + words * words / QUADRATIC_DENOMINATOR
+}
+
+/// EXP charges per byte of the exponent.
+pub fn exp_cost(exponent: U256) -> u64 {
+    EXP + EXP_BYTE * (exponent.bits() as u64).div_ceil(8)
+}
+
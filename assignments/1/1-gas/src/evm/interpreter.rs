@@ -0,0 +1,1425 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use ethereum_types::{U256, U512};
+use sha3::{Digest, Keccak256};
+
+use super::gas::{self, words};
+use super::opcode::*;
+use super::precompile;
+use super::{
+    Address, BlockEnv, EvmError, ExecutionReport, Halt, Log, OpcodeStats, Outcome, State, Transaction, TX_GAS_LIMIT_CAP,
+};
+use crate::intrinsic::{intrinsic_gas, Hardfork, TxPayload};
+use crate::sstore::{SstoreSchedule, StorageSlot, SSTORE_STIPEND};
+
+/// Everything that is rolled back when a call frame fails.
+#[derive(Debug, Clone, Default)]
+struct Substate {
+    state: State,
+    transient: HashMap<(Address, U256), U256>,
+    warm_addresses: HashSet<Address>,
+    warm_slots: HashSet<(Address, U256)>,
+    created: HashSet<Address>,
+    destroyed: HashSet<Address>,
+    refund: i64,
+    logs: Vec<Log>,
+}
+
+/// Executes transactions against an in-memory [`State`].
+#[derive(Debug, Clone)]
+pub struct Evm {
+    pub block: BlockEnv,
+    substate: Substate,
+    /// State as it was when the current transaction started, used for the
+    /// "original value" of SSTORE.
+    committed: State,
+    origin: Address,
+    gas_price: U256,
+    opcodes: BTreeMap<u8, OpcodeStats>,
+}
+
+#[derive(Debug)]
+struct Message {
+    caller: Address,
+    /// Account whose storage and balance the code runs against.
+    address: Address,
+    /// Account the code is loaded from; differs for CALLCODE and DELEGATECALL.
+    code_address: Address,
+    value: U256,
+    /// Whether `value` moves from `caller` to `address` (CALL only).
+    transfer: bool,
+    input: Vec<u8>,
+    gas: u64,
+    is_static: bool,
+    depth: usize,
+}
+
+#[derive(Debug)]
+struct FrameResult {
+    outcome: Outcome,
+    gas_left: u64,
+}
+
+#[derive(Debug)]
+pub(super) enum Exception {
+    Halt(Halt),
+    Fatal(EvmError),
+}
+
+impl From<Halt> for Exception {
+    fn from(halt: Halt) -> Self {
+        Exception::Halt(halt)
+    }
+}
+
+impl From<EvmError> for Exception {
+    fn from(err: EvmError) -> Self {
+        Exception::Fatal(err)
+    }
+}
+
+enum Control {
+    Continue,
+    Stop(Outcome),
+}
+
+struct Frame<'a> {
+    msg: &'a Message,
+    code: &'a [u8],
+    jumpdests: Vec<bool>,
+    pc: usize,
+    stack: Vec<U256>,
+    memory: Vec<u8>,
+    gas_left: u64,
+    /// Gas charged by the opcode currently executing.
+    step_gas: u64,
+    return_data: Vec<u8>,
+}
+
+impl<'a> Frame<'a> {
+    fn new(msg: &'a Message, code: &'a [u8]) -> Self {
+        let mut jumpdests = vec![false; code.len()];
+        let mut i = 0;
+        while i < code.len() {
+            if code[i] == JUMPDEST {
+                jumpdests[i] = true;
+            }
+            i += 1 + immediate_size(code[i]);
+        }
+
+        Self {
+            msg,
+            code,
+            jumpdests,
+            pc: 0,
+            stack: Vec::with_capacity(gas::STACK_LIMIT),
+            memory: Vec::new(),
+            gas_left: msg.gas,
+            step_gas: 0,
+            return_data: Vec::new(),
+        }
+    }
+
+    fn charge(&mut self, amount: u64) -> Result<(), Halt> {
+        if amount > self.gas_left {
+            return Err(Halt::OutOfGas);
+        }
+        self.gas_left -= amount;
+        self.step_gas += amount;
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<U256, Halt> {
+        self.stack.pop().ok_or(Halt::StackUnderflow)
+    }
+
+    fn push(&mut self, value: U256) -> Result<(), Halt> {
+        if self.stack.len() >= gas::STACK_LIMIT {
+            return Err(Halt::StackOverflow);
+        }
+        self.stack.push(value);
+        Ok(())
+    }
+
+    /// Grows memory to cover `offset..offset + len`, charging for the new
+    /// words, and returns the range as `usize`s.
+    fn expand_memory(&mut self, offset: U256, len: U256) -> Result<(usize, usize), Halt> {
+        if len.is_zero() {
+            return Ok((0, 0));
+        }
+        // Anything past 4 GiB would cost far more gas than a block holds.
+        let limit = U256::from(u32::MAX);
+        if offset > limit || len > limit {
+            return Err(Halt::OutOfGas);
+        }
+        let (offset, len) = (offset.as_usize(), len.as_usize());
+
+        let current_words = words(self.memory.len());
+        let new_words = words(offset + len);
+        if new_words > current_words {
+            self.charge(gas::memory_cost(new_words) - gas::memory_cost(current_words))?;
+            self.memory.resize(new_words as usize * 32, 0);
+        }
+        Ok((offset, len))
+    }
+
+    fn memory_slice(&self, offset: usize, len: usize) -> &[u8] {
+        &self.memory[offset..offset + len]
+    }
+}
+
+impl Evm {
+    pub fn new(state: State, block: BlockEnv) -> Self {
+        Self {
+            block,
+            substate: Substate {
+                state: state.clone(),
+                ..Substate::default()
+            },
+            committed: state,
+            origin: Address::zero(),
+            gas_price: U256::zero(),
+            opcodes: BTreeMap::new(),
+        }
+    }
+
+    pub fn state(&self) -> &State {
+        &self.substate.state
+    }
+
+    pub fn state_mut(&mut self) -> &mut State {
+        &mut self.substate.state
+    }
+
+    /// Executes one transaction and keeps its state changes.
+    pub fn transact(&mut self, tx: &Transaction) -> Result<ExecutionReport, EvmError> {
+        let payload = TxPayload {
+            data: &tx.data,
+            is_create: tx.to.is_none(),
+            ..TxPayload::default()
+        };
+        let intrinsic = intrinsic_gas(&payload, Hardfork::LATEST)?;
+        if tx.gas_limit > TX_GAS_LIMIT_CAP {
+            return Err(EvmError::GasLimitAboveCap { gas_limit: tx.gas_limit });
+        }
+        if tx.gas_limit < intrinsic.min_gas_limit() {
+            return Err(EvmError::GasLimitBelowIntrinsic {
+                gas_limit: tx.gas_limit,
+                intrinsic_gas: intrinsic.min_gas_limit(),
+            });
+        }
+        let balance = self.substate.state.balance(&tx.caller);
+        if balance < tx.value {
+            return Err(EvmError::InsufficientBalance { balance, value: tx.value });
+        }
+        let nonce = self.substate.state.nonce(&tx.caller);
+        if nonce == u64::MAX {
+            return Err(EvmError::NonceOverflow);
+        }
+
+        self.committed = self.substate.state.clone();
+        self.substate = Substate {
+            state: self.substate.state.clone(),
+            ..Substate::default()
+        };
+        self.origin = tx.caller;
+        self.gas_price = tx.gas_price;
+        self.opcodes.clear();
+
+        // EIP-2929 and EIP-3651: sender, recipient, coinbase and precompiles start warm.
+        let warm = &mut self.substate.warm_addresses;
+        warm.insert(tx.caller);
+        warm.insert(self.block.coinbase);
+        warm.extend(precompile::addresses());
+
+        let gas = tx.gas_limit - intrinsic.total();
+        self.substate.state.account_mut(&tx.caller).nonce += 1;
+
+        let executed = match tx.to {
+            Some(to) => {
+                self.substate.warm_addresses.insert(to);
+                let msg = Message {
+                    caller: tx.caller,
+                    address: to,
+                    code_address: to,
+                    value: tx.value,
+                    transfer: true,
+                    input: tx.data.clone(),
+                    gas,
+                    is_static: false,
+                    depth: 0,
+                };
+                self.call(msg).map(|result| (result, None))
+            }
+            None => {
+                let address = create_address(&tx.caller, nonce);
+                self.substate.warm_addresses.insert(address);
+                self.create(tx.caller, address, tx.value, &tx.data, gas, 0).map(|result| {
+                    let created = result.outcome.is_success().then_some(address);
+                    (result, created)
+                })
+            }
+        };
+        let (result, created_address) = match executed {
+            Ok(executed) => executed,
+            Err(err) => {
+                // Leave the state exactly as it was before the transaction.
+                self.substate.state = self.committed.clone();
+                return Err(err);
+            }
+        };
+
+        let gas_spent = tx.gas_limit - result.gas_left;
+        let refund = SstoreSchedule::for_fork(Hardfork::LATEST).capped_refund(gas_spent, self.substate.refund);
+        let gas_used = (gas_spent - refund).max(intrinsic.floor.unwrap_or(0));
+
+        // EIP-6780: only contracts created in this transaction can self-destruct.
+        for address in std::mem::take(&mut self.substate.destroyed) {
+            self.substate.state.remove_account(&address);
+        }
+
+        Ok(ExecutionReport {
+            outcome: result.outcome,
+            gas_used,
+            intrinsic_gas: intrinsic.total(),
+            execution_gas: gas - result.gas_left,
+            refund,
+            created_address,
+            logs: std::mem::take(&mut self.substate.logs),
+            opcodes: std::mem::take(&mut self.opcodes),
+        })
+    }
+
+    fn call(&mut self, msg: Message) -> Result<FrameResult, EvmError> {
+        let snapshot = self.substate.clone();
+
+        if msg.transfer && !self.substate.state.transfer(&msg.caller, &msg.address, msg.value) {
+            return Ok(FrameResult {
+                outcome: Outcome::Revert(Vec::new()),
+                gas_left: msg.gas,
+            });
+        }
+
+        let result = if precompile::is_precompile(&msg.code_address) {
+            match precompile::run(&msg.code_address, &msg.input, msg.gas) {
+                Ok((cost, output)) => FrameResult {
+                    outcome: Outcome::Success(output),
+                    gas_left: msg.gas - cost,
+                },
+                Err(Exception::Halt(halt)) => FrameResult {
+                    outcome: Outcome::Halt(halt),
+                    gas_left: 0,
+                },
+                Err(Exception::Fatal(err)) => return Err(err),
+            }
+        } else {
+            let code = self.substate.state.code(&msg.code_address).to_vec();
+            self.run_frame(&msg, &code)?
+        };
+
+        if !result.outcome.is_success() {
+            self.substate = snapshot;
+        }
+        Ok(result)
+    }
+
+    fn create(
+        &mut self,
+        caller: Address,
+        address: Address,
+        value: U256,
+        initcode: &[u8],
+        gas: u64,
+        depth: usize,
+    ) -> Result<FrameResult, EvmError> {
+        // EIP-684 and EIP-7610: code, a nonce or storage means the address is taken.
+        if let Some(account) = self.substate.state.account(&address)
+            && (!account.code.is_empty() || account.nonce != 0 || account.storage.values().any(|v| !v.is_zero()))
+        {
+            return Ok(FrameResult {
+                outcome: Outcome::Halt(Halt::CreateCollision),
+                gas_left: 0,
+            });
+        }
+
+        let snapshot = self.substate.clone();
+        self.substate.state.account_mut(&address).nonce = 1;
+        self.substate.state.transfer(&caller, &address, value);
+        self.substate.created.insert(address);
+
+        let msg = Message {
+            caller,
+            address,
+            code_address: address,
+            value,
+            transfer: false,
+            input: Vec::new(),
+            gas,
+            is_static: false,
+            depth,
+        };
+        let mut result = self.run_frame(&msg, initcode)?;
+
+        if let Outcome::Success(code) = &result.outcome {
+            let deposit = gas::CODE_DEPOSIT_BYTE * code.len() as u64;
+            let failure = if code.len() > gas::MAX_CODE_SIZE {
+                Some(Halt::CodeTooLarge)
+            } else if code.first() == Some(&0xef) {
+                Some(Halt::InvalidCodePrefix)
+            } else if deposit > result.gas_left {
+                Some(Halt::OutOfGas)
+            } else {
+                None
+            };
+
+            match failure {
+                Some(halt) => {
+                    result = FrameResult {
+                        outcome: Outcome::Halt(halt),
+                        gas_left: 0,
+                    };
+                }
+                None => {
+                    result.gas_left -= deposit;
+                    self.substate.state.account_mut(&address).code = code.clone();
+                }
+            }
+        }
+
+        if !result.outcome.is_success() {
+            self.substate = snapshot;
+        }
+        Ok(result)
+    }
+
+    fn run_frame(&mut self, msg: &Message, code: &[u8]) -> Result<FrameResult, EvmError> {
+        let mut frame = Frame::new(msg, code);
+
+        loop {
+            let op = code.get(frame.pc).copied().unwrap_or(STOP);
+            frame.pc += 1;
+            frame.step_gas = 0;
+
+            match self.step(&mut frame, op) {
+                Ok(Control::Continue) => self.record(op, frame.step_gas),
+                Ok(Control::Stop(outcome)) => {
+                    self.record(op, frame.step_gas);
+                    return Ok(FrameResult {
+                        outcome,
+                        gas_left: frame.gas_left,
+                    });
+                }
+                Err(Exception::Halt(halt)) => {
+                    self.record(op, frame.step_gas + frame.gas_left);
+                    return Ok(FrameResult {
+                        outcome: Outcome::Halt(halt),
+                        gas_left: 0,
+                    });
+                }
+                Err(Exception::Fatal(err)) => return Err(err),
+            }
+        }
+    }
+
+    fn record(&mut self, op: u8, gas: u64) {
+        let stats = self.opcodes.entry(op).or_default();
+        stats.count += 1;
+        stats.gas += gas;
+    }
+
+    /// Charges the EIP-2929 account access cost and marks the account warm.
+    fn access_account(&mut self, address: Address) -> u64 {
+        if self.substate.warm_addresses.insert(address) {
+            gas::COLD_ACCOUNT_ACCESS
+        } else {
+            gas::WARM_ACCESS
+        }
+    }
+
+    fn step(&mut self, frame: &mut Frame, op: u8) -> Result<Control, Exception> {
+        macro_rules! binary {
+            ($cost:expr, |$a:ident, $b:ident| $body:expr) => {{
+                frame.charge($cost)?;
+                let $a = frame.pop()?;
+                let $b = frame.pop()?;
+                frame.push($body)?;
+            }};
+        }
+        macro_rules! push_value {
+            ($value:expr) => {{
+                frame.charge(gas::BASE)?;
+                frame.push($value)?;
+            }};
+        }
+
+        match op {
+            STOP => return Ok(Control::Stop(Outcome::Success(Vec::new()))),
+
+            ADD => binary!(gas::VERY_LOW, |a, b| a.overflowing_add(b).0),
+            MUL => binary!(gas::LOW, |a, b| a.overflowing_mul(b).0),
+            SUB => binary!(gas::VERY_LOW, |a, b| a.overflowing_sub(b).0),
+            DIV => binary!(gas::LOW, |a, b| if b.is_zero() { b } else { a / b }),
+            SDIV => binary!(gas::LOW, |a, b| signed_div(a, b)),
+            MOD => binary!(gas::LOW, |a, b| if b.is_zero() { b } else { a % b }),
+            SMOD => binary!(gas::LOW, |a, b| signed_mod(a, b)),
+            ADDMOD | MULMOD => {
+                frame.charge(gas::MID)?;
+                let a = frame.pop()?;
+                let b = frame.pop()?;
+                let n = frame.pop()?;
+                let result = if n.is_zero() {
+                    n
+                } else {
+                    let wide = if op == ADDMOD {
+                        U512::from(a) + U512::from(b)
+                    } else {
+                        a.full_mul(b)
+                    };
+                    U256::try_from(wide % U512::from(n)).expect("remainder is smaller than the modulus")
+                };
+                frame.push(result)?;
+            }
+            EXP => {
+                let base = frame.pop()?;
+                let exponent = frame.pop()?;
+                frame.charge(gas::exp_cost(exponent))?;
+                frame.push(base.overflowing_pow(exponent).0)?;
+            }
+            SIGNEXTEND => binary!(gas::LOW, |size, value| sign_extend(size, value)),
+
+            LT => binary!(gas::VERY_LOW, |a, b| bool_word(a < b)),
+            GT => binary!(gas::VERY_LOW, |a, b| bool_word(a > b)),
+            SLT => binary!(gas::VERY_LOW, |a, b| bool_word(signed_lt(a, b))),
+            SGT => binary!(gas::VERY_LOW, |a, b| bool_word(signed_lt(b, a))),
+            EQ => binary!(gas::VERY_LOW, |a, b| bool_word(a == b)),
+            ISZERO => {
+                frame.charge(gas::VERY_LOW)?;
+                let a = frame.pop()?;
+                frame.push(bool_word(a.is_zero()))?;
+            }
+            AND => binary!(gas::VERY_LOW, |a, b| a & b),
+            OR => binary!(gas::VERY_LOW, |a, b| a | b),
+            XOR => binary!(gas::VERY_LOW, |a, b| a ^ b),
+            NOT => {
+                frame.charge(gas::VERY_LOW)?;
+                let a = frame.pop()?;
+                frame.push(!a)?;
+            }
+            BYTE => binary!(gas::VERY_LOW, |i, x| if i < U256::from(32) {
+                U256::from(x.byte(31 - i.as_usize()))
+            } else {
+                U256::zero()
+            }),
+            SHL => binary!(gas::VERY_LOW, |shift, value| if shift < U256::from(256) {
+                value << shift.as_usize()
+            } else {
+                U256::zero()
+            }),
+            SHR => binary!(gas::VERY_LOW, |shift, value| if shift < U256::from(256) {
+                value >> shift.as_usize()
+            } else {
+                U256::zero()
+            }),
+            SAR => binary!(gas::VERY_LOW, |shift, value| arithmetic_shr(shift, value)),
+            CLZ => {
+                frame.charge(gas::LOW)?;
+                let a = frame.pop()?;
+                frame.push(U256::from(a.leading_zeros()))?;
+            }
+
+            KECCAK256 => {
+                let offset = frame.pop()?;
+                let len = frame.pop()?;
+                let (offset, len) = frame.expand_memory(offset, len)?;
+                frame.charge(gas::KECCAK256 + gas::KECCAK256_WORD * words(len))?;
+                let hash = keccak256(frame.memory_slice(offset, len));
+                frame.push(U256::from_big_endian(&hash))?;
+            }
+
+            ADDRESS => push_value!(address_word(&frame.msg.address)),
+            BALANCE => {
+                let address = word_address(frame.pop()?);
+                let cost = self.access_account(address);
+                frame.charge(cost)?;
+                frame.push(self.substate.state.balance(&address))?;
+            }
+            ORIGIN => push_value!(address_word(&self.origin)),
+            CALLER => push_value!(address_word(&frame.msg.caller)),
+            CALLVALUE => push_value!(frame.msg.value),
+            CALLDATALOAD => {
+                frame.charge(gas::VERY_LOW)?;
+                let offset = frame.pop()?;
+                let word = padded_slice(&frame.msg.input, offset, 32);
+                frame.push(U256::from_big_endian(&word))?;
+            }
+            CALLDATASIZE => push_value!(U256::from(frame.msg.input.len())),
+            CALLDATACOPY | CODECOPY => {
+                let dest = frame.pop()?;
+                let src = frame.pop()?;
+                let len = frame.pop()?;
+                let (dest, len) = frame.expand_memory(dest, len)?;
+                frame.charge(gas::VERY_LOW + gas::COPY_WORD * words(len))?;
+                let msg = frame.msg;
+                let source = if op == CALLDATACOPY { &msg.input[..] } else { frame.code };
+                let bytes = padded_slice(source, src, len);
+                frame.memory[dest..dest + len].copy_from_slice(&bytes);
+            }
+            CODESIZE => push_value!(U256::from(frame.code.len())),
+            GASPRICE => push_value!(self.gas_price),
+            EXTCODESIZE => {
+                let address = word_address(frame.pop()?);
+                let cost = self.access_account(address);
+                frame.charge(cost)?;
+                frame.push(U256::from(self.substate.state.code(&address).len()))?;
+            }
+            EXTCODECOPY => {
+                let address = word_address(frame.pop()?);
+                let dest = frame.pop()?;
+                let src = frame.pop()?;
+                let len = frame.pop()?;
+                let (dest, len) = frame.expand_memory(dest, len)?;
+                let cost = self.access_account(address);
+                frame.charge(cost + gas::COPY_WORD * words(len))?;
+                let bytes = padded_slice(self.substate.state.code(&address), src, len);
+                frame.memory[dest..dest + len].copy_from_slice(&bytes);
+            }
+            RETURNDATASIZE => push_value!(U256::from(frame.return_data.len())),
+            RETURNDATACOPY => {
+                let dest = frame.pop()?;
+                let src = frame.pop()?;
+                let len = frame.pop()?;
+                let end = src.overflowing_add(len);
+                if end.1 || end.0 > U256::from(frame.return_data.len()) {
+                    return Err(Halt::ReturnDataOutOfBounds.into());
+                }
+                let (dest, len) = frame.expand_memory(dest, len)?;
+                frame.charge(gas::VERY_LOW + gas::COPY_WORD * words(len))?;
+                let src = src.as_usize();
+                frame.memory[dest..dest + len].copy_from_slice(&frame.return_data[src..src + len]);
+            }
+            EXTCODEHASH => {
+                let address = word_address(frame.pop()?);
+                let cost = self.access_account(address);
+                frame.charge(cost)?;
+                let hash = if self.substate.state.is_empty(&address) {
+                    U256::zero()
+                } else {
+                    U256::from_big_endian(&keccak256(self.substate.state.code(&address)))
+                };
+                frame.push(hash)?;
+            }
+
+            BLOCKHASH => {
+                frame.charge(gas::BLOCKHASH)?;
+                frame.pop()?;
+                // No block history is kept, so every lookup misses.
+                frame.push(U256::zero())?;
+            }
+            COINBASE => push_value!(address_word(&self.block.coinbase)),
+            TIMESTAMP => push_value!(U256::from(self.block.timestamp)),
+            NUMBER => push_value!(U256::from(self.block.number)),
+            PREVRANDAO => push_value!(U256::from_big_endian(self.block.prevrandao.as_bytes())),
+            GASLIMIT => push_value!(U256::from(self.block.gas_limit)),
+            CHAINID => push_value!(U256::from(self.block.chain_id)),
+            SELFBALANCE => {
+                frame.charge(gas::LOW)?;
+                frame.push(self.substate.state.balance(&frame.msg.address))?;
+            }
+            BASEFEE => push_value!(self.block.base_fee),
+            BLOBHASH => {
+                frame.charge(gas::VERY_LOW)?;
+                frame.pop()?;
+                // Transactions executed here never carry blobs.
+                frame.push(U256::zero())?;
+            }
+            BLOBBASEFEE => push_value!(self.block.blob_base_fee),
+
+            POP => {
+                frame.charge(gas::BASE)?;
+                frame.pop()?;
+            }
+            MLOAD => {
+                frame.charge(gas::VERY_LOW)?;
+                let offset = frame.pop()?;
+                let (offset, _) = frame.expand_memory(offset, U256::from(32))?;
+                let word = U256::from_big_endian(frame.memory_slice(offset, 32));
+                frame.push(word)?;
+            }
+            MSTORE => {
+                frame.charge(gas::VERY_LOW)?;
+                let offset = frame.pop()?;
+                let value = frame.pop()?;
+                let (offset, _) = frame.expand_memory(offset, U256::from(32))?;
+                value.to_big_endian(&mut frame.memory[offset..offset + 32]);
+            }
+            MSTORE8 => {
+                frame.charge(gas::VERY_LOW)?;
+                let offset = frame.pop()?;
+                let value = frame.pop()?;
+                let (offset, _) = frame.expand_memory(offset, U256::one())?;
+                frame.memory[offset] = value.byte(0);
+            }
+            SLOAD => {
+                let key = frame.pop()?;
+                let address = frame.msg.address;
+                let cost = if self.substate.warm_slots.insert((address, key)) {
+                    gas::COLD_SLOAD
+                } else {
+                    gas::WARM_ACCESS
+                };
+                frame.charge(cost)?;
+                frame.push(self.substate.state.storage(&address, &key))?;
+            }
+            SSTORE => {
+                if frame.msg.is_static {
+                    return Err(Halt::StateChangeInStaticCall.into());
+                }
+                if frame.gas_left <= SSTORE_STIPEND {
+                    return Err(Halt::OutOfGas.into());
+                }
+                let key = frame.pop()?;
+                let value = frame.pop()?;
+                let address = frame.msg.address;
+
+                let mut slot = StorageSlot {
+                    original: self.committed.storage(&address, &key),
+                    current: self.substate.state.storage(&address, &key),
+                    warm: !self.substate.warm_slots.insert((address, key)),
+                };
+                let cost = slot.write(value, &SstoreSchedule::for_fork(Hardfork::LATEST));
+                frame.charge(cost.gas)?;
+
+                self.substate.refund += cost.refund;
+                self.substate.state.set_storage(&address, key, value);
+            }
+            JUMP => {
+                frame.charge(gas::MID)?;
+                let dest = frame.pop()?;
+                frame.pc = jump_target(frame, dest)?;
+            }
+            JUMPI => {
+                frame.charge(gas::HIGH)?;
+                let dest = frame.pop()?;
+                let condition = frame.pop()?;
+                if !condition.is_zero() {
+                    frame.pc = jump_target(frame, dest)?;
+                }
+            }
+            PC => push_value!(U256::from(frame.pc - 1)),
+            MSIZE => push_value!(U256::from(frame.memory.len())),
+            GAS => {
+                frame.charge(gas::BASE)?;
+                frame.push(U256::from(frame.gas_left))?;
+            }
+            JUMPDEST => frame.charge(gas::JUMPDEST)?,
+            TLOAD => {
+                frame.charge(gas::TRANSIENT)?;
+                let key = frame.pop()?;
+                let value = self
+                    .substate
+                    .transient
+                    .get(&(frame.msg.address, key))
+                    .copied()
+                    .unwrap_or_default();
+                frame.push(value)?;
+            }
+            TSTORE => {
+                if frame.msg.is_static {
+                    return Err(Halt::StateChangeInStaticCall.into());
+                }
+                frame.charge(gas::TRANSIENT)?;
+                let key = frame.pop()?;
+                let value = frame.pop()?;
+                self.substate.transient.insert((frame.msg.address, key), value);
+            }
+            MCOPY => {
+                let dest = frame.pop()?;
+                let src = frame.pop()?;
+                let len = frame.pop()?;
+                let (src, _) = frame.expand_memory(src, len)?;
+                let (dest, len) = frame.expand_memory(dest, len)?;
+                frame.charge(gas::VERY_LOW + gas::COPY_WORD * words(len))?;
+                frame.memory.copy_within(src..src + len, dest);
+            }
+
+            PUSH0 => push_value!(U256::zero()),
+            PUSH1..=PUSH32 => {
+                frame.charge(gas::VERY_LOW)?;
+                let size = immediate_size(op);
+                let bytes = padded_slice(frame.code, U256::from(frame.pc), size);
+                frame.pc += size;
+                frame.push(U256::from_big_endian(&bytes))?;
+            }
+            DUP1..=DUP16 => {
+                frame.charge(gas::VERY_LOW)?;
+                let depth = (op - DUP1) as usize + 1;
+                if frame.stack.len() < depth {
+                    return Err(Halt::StackUnderflow.into());
+                }
+                let value = frame.stack[frame.stack.len() - depth];
+                frame.push(value)?;
+            }
+            SWAP1..=SWAP16 => {
+                frame.charge(gas::VERY_LOW)?;
+                let depth = (op - SWAP1) as usize + 1;
+                let len = frame.stack.len();
+                if len <= depth {
+                    return Err(Halt::StackUnderflow.into());
+                }
+                frame.stack.swap(len - 1, len - 1 - depth);
+            }
+            LOG0..=LOG4 => {
+                if frame.msg.is_static {
+                    return Err(Halt::StateChangeInStaticCall.into());
+                }
+                let offset = frame.pop()?;
+                let len = frame.pop()?;
+                let topic_count = (op - LOG0) as usize;
+                let mut topics = Vec::with_capacity(topic_count);
+                for _ in 0..topic_count {
+                    let mut topic = [0u8; 32];
+                    frame.pop()?.to_big_endian(&mut topic);
+                    topics.push(topic.into());
+                }
+                let (offset, len) = frame.expand_memory(offset, len)?;
+                frame.charge(gas::LOG + gas::LOG_TOPIC * topic_count as u64 + gas::LOG_DATA_BYTE * len as u64)?;
+                self.substate.logs.push(Log {
+                    address: frame.msg.address,
+                    topics,
+                    data: frame.memory_slice(offset, len).to_vec(),
+                });
+            }
+
+            CREATE | CREATE2 => return self.op_create(frame, op),
+            CALL | CALLCODE | DELEGATECALL | STATICCALL => return self.op_call(frame, op),
+            RETURN | REVERT => {
+                let offset = frame.pop()?;
+                let len = frame.pop()?;
+                let (offset, len) = frame.expand_memory(offset, len)?;
+                let output = frame.memory_slice(offset, len).to_vec();
+                let outcome = if op == RETURN {
+                    Outcome::Success(output)
+                } else {
+                    Outcome::Revert(output)
+                };
+                return Ok(Control::Stop(outcome));
+            }
+            SELFDESTRUCT => {
+                if frame.msg.is_static {
+                    return Err(Halt::StateChangeInStaticCall.into());
+                }
+                let beneficiary = word_address(frame.pop()?);
+                let address = frame.msg.address;
+                let balance = self.substate.state.balance(&address);
+
+                let mut cost = gas::SELFDESTRUCT;
+                if self.substate.warm_addresses.insert(beneficiary) {
+                    cost += gas::COLD_ACCOUNT_ACCESS;
+                }
+                if !balance.is_zero() && self.substate.state.is_empty(&beneficiary) {
+                    cost += gas::NEW_ACCOUNT;
+                }
+                frame.charge(cost)?;
+
+                self.substate.state.transfer(&address, &beneficiary, balance);
+                if self.substate.created.contains(&address) {
+                    self.substate.destroyed.insert(address);
+                }
+                return Ok(Control::Stop(Outcome::Success(Vec::new())));
+            }
+            _ => return Err(Halt::InvalidOpcode(op).into()),
+        }
+
+        Ok(Control::Continue)
+    }
+
+    fn op_call(&mut self, frame: &mut Frame, op: u8) -> Result<Control, Exception> {
+        let requested_gas = frame.pop()?;
+        let target = word_address(frame.pop()?);
+        let value = if op == CALL || op == CALLCODE {
+            frame.pop()?
+        } else {
+            U256::zero()
+        };
+        let in_offset = frame.pop()?;
+        let in_len = frame.pop()?;
+        let out_offset = frame.pop()?;
+        let out_len = frame.pop()?;
+
+        if op == CALL && !value.is_zero() && frame.msg.is_static {
+            return Err(Halt::StateChangeInStaticCall.into());
+        }
+
+        let (in_offset, in_len) = frame.expand_memory(in_offset, in_len)?;
+        let (out_offset, out_len) = frame.expand_memory(out_offset, out_len)?;
+
+        let mut cost = self.access_account(target);
+        if !value.is_zero() {
+            cost += gas::CALL_VALUE;
+            if op == CALL && self.substate.state.is_empty(&target) {
+                cost += gas::NEW_ACCOUNT;
+            }
+        }
+        frame.charge(cost)?;
+
+        // EIP-150: a call can forward at most 63/64 of the remaining gas.
+        let available = frame.gas_left - frame.gas_left / 64;
+        let gas = if requested_gas > U256::from(available) {
+            available
+        } else {
+            requested_gas.as_u64()
+        };
+        frame.gas_left -= gas;
+        frame.return_data.clear();
+
+        let self_address = frame.msg.address;
+        let stipend = if value.is_zero() { 0 } else { gas::CALL_STIPEND };
+        if frame.msg.depth >= gas::CALL_DEPTH_LIMIT || self.substate.state.balance(&self_address) < value {
+            // The call never starts, so its gas comes back, stipend included.
+            frame.gas_left += gas + stipend;
+            frame.push(U256::zero())?;
+            return Ok(Control::Continue);
+        }
+
+        let input = frame.memory_slice(in_offset, in_len).to_vec();
+        let depth = frame.msg.depth + 1;
+        let msg = match op {
+            CALL => Message {
+                caller: self_address,
+                address: target,
+                code_address: target,
+                value,
+                transfer: true,
+                input,
+                gas: gas + stipend,
+                is_static: frame.msg.is_static,
+                depth,
+            },
+            CALLCODE => Message {
+                caller: self_address,
+                address: self_address,
+                code_address: target,
+                value,
+                transfer: false,
+                input,
+                gas: gas + stipend,
+                is_static: frame.msg.is_static,
+                depth,
+            },
+            DELEGATECALL => Message {
+                caller: frame.msg.caller,
+                address: self_address,
+                code_address: target,
+                value: frame.msg.value,
+                transfer: false,
+                input,
+                gas,
+                is_static: frame.msg.is_static,
+                depth,
+            },
+            _ => Message {
+                caller: self_address,
+                address: target,
+                code_address: target,
+                value: U256::zero(),
+                transfer: false,
+                input,
+                gas,
+                is_static: true,
+                depth,
+            },
+        };
+
+        let result = self.call(msg)?;
+        frame.gas_left += result.gas_left;
+
+        let (success, output) = match result.outcome {
+            Outcome::Success(output) => (true, output),
+            Outcome::Revert(output) => (false, output),
+            Outcome::Halt(_) => (false, Vec::new()),
+        };
+        let copied = out_len.min(output.len());
+        frame.memory[out_offset..out_offset + copied].copy_from_slice(&output[..copied]);
+        frame.return_data = output;
+        frame.push(bool_word(success))?;
+        Ok(Control::Continue)
+    }
+
+    fn op_create(&mut self, frame: &mut Frame, op: u8) -> Result<Control, Exception> {
+        if frame.msg.is_static {
+            return Err(Halt::StateChangeInStaticCall.into());
+        }
+        let value = frame.pop()?;
+        let offset = frame.pop()?;
+        let len = frame.pop()?;
+        let salt = if op == CREATE2 { Some(frame.pop()?) } else { None };
+
+        if len > U256::from(gas::MAX_INITCODE_SIZE) {
+            return Err(Halt::InitcodeTooLarge.into());
+        }
+        let (offset, len) = frame.expand_memory(offset, len)?;
+        let mut cost = gas::CREATE + gas::INITCODE_WORD * words(len);
+        if salt.is_some() {
+            cost += gas::KECCAK256_WORD * words(len);
+        }
+        frame.charge(cost)?;
+
+        let initcode = frame.memory_slice(offset, len).to_vec();
+        frame.return_data.clear();
+
+        let creator = frame.msg.address;
+        let nonce = self.substate.state.nonce(&creator);
+        if frame.msg.depth >= gas::CALL_DEPTH_LIMIT
+            || self.substate.state.balance(&creator) < value
+            || nonce == u64::MAX
+        {
+            frame.push(U256::zero())?;
+            return Ok(Control::Continue);
+        }
+
+        let gas = frame.gas_left - frame.gas_left / 64;
+        frame.gas_left -= gas;
+        self.substate.state.account_mut(&creator).nonce += 1;
+
+        let address = match salt {
+            Some(salt) => create2_address(&creator, salt, &initcode),
+            None => create_address(&creator, nonce),
+        };
+        self.substate.warm_addresses.insert(address);
+
+        let result = self.create(creator, address, value, &initcode, gas, frame.msg.depth + 1)?;
+        frame.gas_left += result.gas_left;
+        match result.outcome {
+            Outcome::Success(_) => frame.push(address_word(&address))?,
+            Outcome::Revert(output) => {
+                frame.return_data = output;
+                frame.push(U256::zero())?;
+            }
+            Outcome::Halt(_) => frame.push(U256::zero())?,
+        }
+        Ok(Control::Continue)
+    }
+}
+
+fn jump_target(frame: &Frame, dest: U256) -> Result<usize, Halt> {
+    if dest >= U256::from(frame.code.len()) || !frame.jumpdests[dest.as_usize()] {
+        return Err(Halt::InvalidJump);
+    }
+    Ok(dest.as_usize())
+}
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+/// Address of a contract deployed with CREATE: `keccak(rlp([sender, nonce]))[12..]`.
+pub fn create_address(sender: &Address, nonce: u64) -> Address {
+    let nonce_bytes = nonce.to_be_bytes();
+    let nonce_bytes = &nonce_bytes[nonce.leading_zeros() as usize / 8..];
+
+    let mut payload = Vec::with_capacity(30);
+    payload.push(0x80 + 20);
+    payload.extend_from_slice(sender.as_bytes());
+    match nonce_bytes {
+        [byte] if *byte < 0x80 => payload.push(*byte),
+        bytes => {
+            payload.push(0x80 + bytes.len() as u8);
+            payload.extend_from_slice(bytes);
+        }
+    }
+
+    let mut encoded = vec![0xc0 + payload.len() as u8];
+    encoded.extend_from_slice(&payload);
+    Address::from_slice(&keccak256(&encoded)[12..])
+}
+
+/// Address of a contract deployed with CREATE2 (EIP-1014).
+pub fn create2_address(sender: &Address, salt: U256, initcode: &[u8]) -> Address {
+    let mut preimage = Vec::with_capacity(85);
+    preimage.push(0xff);
+    preimage.extend_from_slice(sender.as_bytes());
+    let mut salt_bytes = [0u8; 32];
+    salt.to_big_endian(&mut salt_bytes);
+    preimage.extend_from_slice(&salt_bytes);
+    preimage.extend_from_slice(&keccak256(initcode));
+    Address::from_slice(&keccak256(&preimage)[12..])
+}
+
+/// `len` bytes of `data` starting at `offset`, zero-padded past the end.
+fn padded_slice(data: &[u8], offset: U256, len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len];
+    if offset < U256::from(data.len()) {
+        let start = offset.as_usize();
+        let available = (data.len() - start).min(len);
+        out[..available].copy_from_slice(&data[start..start + available]);
+    }
+    out
+}
+
+fn address_word(address: &Address) -> U256 {
+    U256::from_big_endian(address.as_bytes())
+}
+
+fn word_address(word: U256) -> Address {
+    let mut bytes = [0u8; 32];
+    word.to_big_endian(&mut bytes);
+    Address::from_slice(&bytes[12..])
+}
+
+fn bool_word(value: bool) -> U256 {
+    if value { U256::one() } else { U256::zero() }
+}
+
+fn is_negative(value: U256) -> bool {
+    value.bit(255)
+}
+
+fn negate(value: U256) -> U256 {
+    (!value).overflowing_add(U256::one()).0
+}
+
+fn abs(value: U256) -> U256 {
+    if is_negative(value) { negate(value) } else { value }
+}
+
+fn signed_div(a: U256, b: U256) -> U256 {
+    if b.is_zero() {
+        return b;
+    }
+    let quotient = abs(a) / abs(b);
+    if is_negative(a) != is_negative(b) {
+        negate(quotient)
+    } else {
+        quotient
+    }
+}
+
+fn signed_mod(a: U256, b: U256) -> U256 {
+    if b.is_zero() {
+        return b;
+    }
+    let remainder = abs(a) % abs(b);
+    if is_negative(a) { negate(remainder) } else { remainder }
+}
+
+fn signed_lt(a: U256, b: U256) -> bool {
+    match (is_negative(a), is_negative(b)) {
+        (true, false) => true,
+        (false, true) => false,
+        _ => a < b,
+    }
+}
+
+fn sign_extend(size: U256, value: U256) -> U256 {
+    if size >= U256::from(31) {
+        return value;
+    }
+    let sign_bit = size.as_usize() * 8 + 7;
+    let mask = (U256::one() << sign_bit) - U256::one();
+    if value.bit(sign_bit) { value | !mask } else { value & mask }
+}
+
+fn arithmetic_shr(shift: U256, value: U256) -> U256 {
+    let negative = is_negative(value);
+    if shift >= U256::from(256) {
+        return if negative { U256::MAX } else { U256::zero() };
+    }
+    let shift = shift.as_usize();
+    if negative { !(!value >> shift) } else { value >> shift }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::encode_call;
+    use crate::evm::{opcode, TX_GAS_LIMIT_CAP};
+
+    const PUSH2: u8 = PUSH1 + 1;
+    const PUSH3: u8 = PUSH1 + 2;
+
+    const SENDER: u64 = 0xa11ce;
+    const CONTRACT: u64 = 0xc0de;
+    const CALLEE: u64 = 0xca11ee;
+
+    fn address(n: u64) -> Address {
+        Address::from_low_u64_be(n)
+    }
+
+    fn evm(contracts: &[(u64, Vec<u8>)]) -> Evm {
+        let mut state = State::new();
+        state.account_mut(&address(SENDER)).balance = U256::exp10(18);
+        for (n, code) in contracts {
+            state.account_mut(&address(*n)).code = code.clone();
+        }
+        Evm::new(state, BlockEnv::default())
+    }
+
+    fn tx(to: Option<u64>, data: Vec<u8>, gas_limit: u64) -> Transaction {
+        Transaction {
+            caller: address(SENDER),
+            to: to.map(address),
+            value: U256::zero(),
+            data,
+            gas_limit,
+            gas_price: U256::zero(),
+        }
+    }
+
+    fn call(evm: &mut Evm, gas_limit: u64) -> ExecutionReport {
+        evm.transact(&tx(Some(CONTRACT), Vec::new(), gas_limit)).unwrap()
+    }
+
+    fn word(value: u64) -> Vec<u8> {
+        let mut bytes = vec![0u8; 32];
+        U256::from(value).to_big_endian(&mut bytes);
+        bytes
+    }
+
+    fn returned_word(report: &ExecutionReport) -> u64 {
+        match &report.outcome {
+            Outcome::Success(output) => U256::from_big_endian(output).as_u64(),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    /// Code that returns the top of the stack as one word.
+    const RETURN_TOP: [u8; 6] = [PUSH0, MSTORE, PUSH1, 32, PUSH0, RETURN];
+
+    #[test]
+    fn opcodes_charge_their_class_cost() {
+        #[rustfmt::skip]
+        let code = vec![
+            CALLER, POP,
+            PUSH1, 2, PUSH1, 3, ADD,
+            PUSH1, 4, MUL,
+            PUSH1, 5, PUSH1, 6, ADDMOD,
+            PUSH2, 1, 0, PUSH1, 2, EXP,
+            PUSH1, 1, PUSH1, 26, JUMPI,
+            JUMPDEST,
+            PUSH0, SLOAD, PUSH0, SLOAD,
+            PUSH2, 0xbe, 0xef, BALANCE, PUSH2, 0xbe, 0xef, BALANCE,
+            PUSH1, 1, CLZ,
+            STOP,
+        ];
+        let mut evm = evm(&[(CONTRACT, code)]);
+        let report = call(&mut evm, 100_000);
+        assert!(report.outcome.is_success());
+
+        for (op, count, gas) in [
+            (CALLER, 1, 2),
+            (POP, 1, 2),
+            (PUSH0, 2, 4),
+            (PUSH1, 9, 27),
+            (ADD, 1, 3),
+            (MUL, 1, 5),
+            (ADDMOD, 1, 8),
+            (JUMPI, 1, 10),
+            (JUMPDEST, 1, 1),
+            (CLZ, 1, 5),
+            // 10 plus 50 per byte of the exponent 0x0100.
+            (EXP, 1, 110),
+            // Cold then warm: 2100 + 100 for the slot, 2600 + 100 for the account.
+            (SLOAD, 2, 2_200),
+            (BALANCE, 2, 2_700),
+        ] {
+            assert_eq!(report.opcodes[&op], OpcodeStats { count, gas }, "{}", opcode::name(op).unwrap());
+        }
+        let total: u64 = report.opcodes.values().map(|stats| stats.gas).sum();
+        assert_eq!(report.execution_gas, total);
+        assert_eq!(report.gas_used, 21_000 + total);
+    }
+
+    #[test]
+    fn clz_counts_leading_zero_bits() {
+        #[rustfmt::skip]
+        let code = vec![
+            PUSH1, 1, CLZ, PUSH0, MSTORE,
+            PUSH0, CLZ, PUSH1, 32, MSTORE,
+            PUSH1, 1, PUSH1, 255, SHL, CLZ, PUSH1, 64, MSTORE,
+            PUSH1, 96, PUSH0, RETURN,
+        ];
+        let mut evm = evm(&[(CONTRACT, code)]);
+        let expected = [word(255), word(256), word(0)].concat();
+        assert_eq!(call(&mut evm, 100_000).outcome, Outcome::Success(expected));
+    }
+
+    #[test]
+    fn memory_expansion_charges_for_new_words_only() {
+        assert_eq!(gas::memory_cost(1), 3);
+        assert_eq!(gas::memory_cost(512), 1_536 + 512);
+        assert_eq!(gas::memory_cost(2_048), 6_144 + 8_192);
+
+        #[rustfmt::skip]
+        let code = [
+            &[PUSH1, 7, PUSH0, MSTORE][..],
+            // Reading the word at 0x3fe0 grows memory to 512 words.
+            &[PUSH2, 0x3f, 0xe0, MLOAD, POP],
+            // Writing the byte at 0xffff grows it to 2048 words.
+            &[PUSH1, 1, PUSH2, 0xff, 0xff, MSTORE8],
+            &[MSIZE],
+            &RETURN_TOP,
+        ]
+        .concat();
+        let mut evm = evm(&[(CONTRACT, code)]);
+        let report = call(&mut evm, 100_000);
+
+        assert_eq!(returned_word(&report), 65_536);
+        assert_eq!(report.opcodes[&MLOAD].gas, 3 + (2_048 - 3));
+        assert_eq!(report.opcodes[&MSTORE8].gas, 3 + (14_336 - 2_048));
+        // The second MSTORE writes inside memory that already exists.
+        assert_eq!(report.opcodes[&MSTORE], OpcodeStats { count: 2, gas: 3 + 3 + 3 });
+    }
+
+    /// Code that calls CALLEE with `gas` and `value` and returns the word it returns.
+    fn caller_code(gas: &[u8], value: u8) -> Vec<u8> {
+        [
+            &[PUSH1, 32, PUSH0, PUSH0, PUSH0, PUSH1, value, PUSH3, 0xca, 0x11, 0xee][..],
+            gas,
+            &[CALL, POP, PUSH1, 32, PUSH0, RETURN],
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn calls_forward_at_most_63_64ths_of_the_gas_left() {
+        // The callee reports the gas it was given, less the 2 its GAS costs.
+        let callee = [&[GAS][..], &RETURN_TOP].concat();
+        let mut evm = evm(&[(CONTRACT, caller_code(&[GAS], 0)), (CALLEE, callee.clone())]);
+        let report = call(&mut evm, 100_000);
+
+        // 79000 left after the intrinsic gas, 17 spent pushing the arguments,
+        // then 3 for the return buffer and 2600 for the cold callee.
+        let available = 79_000 - 17 - 3 - 2_600;
+        assert_eq!(returned_word(&report), available - available / 64 - 2);
+
+        // Asking for no gas with value attached still gives the callee the stipend.
+        let mut evm = evm_with_balance(caller_code(&[PUSH0], 1), callee, U256::one());
+        assert_eq!(returned_word(&call(&mut evm, 100_000)), 2_300 - 2);
+    }
+
+    fn evm_with_balance(caller: Vec<u8>, callee: Vec<u8>, balance: U256) -> Evm {
+        let mut evm = evm(&[(CONTRACT, caller), (CALLEE, callee)]);
+        evm.state_mut().account_mut(&address(CONTRACT)).balance = balance;
+        evm
+    }
+
+    #[test]
+    fn a_call_that_cannot_start_returns_its_gas_and_stipend() {
+        // Sending 1 wei from an account that has none: the call fails before
+        // running, and the caller gets back everything but the CALL's own cost.
+        #[rustfmt::skip]
+        let code = [
+            &[PUSH0, PUSH0, PUSH0, PUSH0, PUSH1, 1, PUSH3, 0xca, 0x11, 0xee, PUSH0, CALL, POP, GAS][..],
+            &RETURN_TOP,
+        ]
+        .concat();
+        let mut evm = evm_with_balance(code, vec![STOP], U256::zero());
+        let report = call(&mut evm, 100_000);
+        let before_call = 79_000 - 2 * 4 - 3 * 2 - 2;
+        assert_eq!(returned_word(&report), before_call - (2_600 + 9_000) + 2_300 - 2 - 2);
+    }
+
+    #[test]
+    fn revert_and_halt_drop_refunds() {
+        let clear_slot = [PUSH0, PUSH0, SSTORE];
+        let outcomes = [
+            ([&clear_slot[..], &[STOP]].concat(), true),
+            ([&clear_slot[..], &[PUSH0, PUSH0, REVERT]].concat(), false),
+            ([&clear_slot[..], &[0xfe]].concat(), false),
+        ];
+        let mut reports = Vec::new();
+        for (code, success) in outcomes {
+            let mut evm = evm(&[(CONTRACT, code)]);
+            evm.state_mut().set_storage(&address(CONTRACT), U256::zero(), U256::from(5));
+            let report = call(&mut evm, 100_000);
+            assert_eq!(report.outcome.is_success(), success);
+            let kept = if success { 0 } else { 5 };
+            assert_eq!(evm.state().storage(&address(CONTRACT), &U256::zero()), U256::from(kept));
+            reports.push(report);
+        }
+
+        // Clearing a cold slot costs 2100 + 2900 and refunds 4800.
+        assert_eq!((reports[0].gas_used, reports[0].refund), (21_000 + 5_004 - 4_800, 4_800));
+        // REVERT keeps the unused gas but loses the refund.
+        assert_eq!((reports[1].gas_used, reports[1].refund), (21_000 + 5_008, 0));
+        // An exceptional halt consumes everything.
+        assert_eq!((reports[2].gas_used, reports[2].refund), (100_000, 0));
+        assert_eq!(reports[2].outcome, Outcome::Halt(Halt::InvalidOpcode(0xfe)));
+    }
+
+    #[test]
+    fn a_halting_callee_burns_only_what_it_was_given() {
+        #[rustfmt::skip]
+        let code = vec![PUSH0, PUSH0, PUSH0, PUSH0, PUSH0, PUSH3, 0xca, 0x11, 0xee, GAS, CALL, STOP];
+        let mut evm = evm(&[(CONTRACT, code), (CALLEE, vec![0xfe])]);
+        let report = call(&mut evm, 100_000);
+        assert!(report.outcome.is_success());
+
+        // The caller keeps the 1/64 it could not forward.
+        let available = 79_000 - 15 - 2_600;
+        assert_eq!(report.gas_used, 100_000 - available / 64);
+    }
+
+    #[test]
+    fn todo_deploy_and_calls_match_revm() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../Todo/ignition/deployments/chain-11155111/artifacts/TodoModule#TodoContract.json"
+        );
+        let artifact: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let initcode = hex::decode(artifact["bytecode"].as_str().unwrap().trim_start_matches("0x")).unwrap();
+
+        // Gas used as reported by revm 10.0.0 for the same transactions, block
+        // and sender. It implements Cancun, which prices these transactions the
+        // same way Osaka does: none of them touch the opcodes or precompiles
+        // added since, and all stay above the EIP-7623 calldata floor.
+        let mut evm = evm(&[]);
+        let deploy = evm.transact(&tx(None, initcode, TX_GAS_LIMIT_CAP)).unwrap();
+        assert_eq!(deploy.gas_used, 899_480);
+        let todo = deploy.created_address.unwrap();
+        assert_eq!(todo, "6b182f1488e8efeb2eb298155ed5bd7ff8a14042".parse().unwrap());
+
+        let mut send = |data: Vec<u8>| {
+            let tx = Transaction { to: Some(todo), ..tx(None, data, TX_GAS_LIMIT_CAP) };
+            evm.transact(&tx).unwrap()
+        };
+        let create = send(encode_call("createTodo(string,uint256)", &["buy milk", "1800000000"]).unwrap());
+        assert_eq!(create.outcome, Outcome::Success(word(1)));
+        assert_eq!((create.gas_used, create.logs.len()), (203_677, 1));
+
+        let complete = send(encode_call("completeTodo(uint256)", &["1"]).unwrap());
+        assert_eq!((complete.gas_used, complete.refund), (30_278, 4_800));
+    }
+
+    #[test]
+    fn create_collides_with_an_address_that_has_storage() {
+        // EIP-7610: storage alone is enough to make the address unusable.
+        let initcode = vec![PUSH0, PUSH0, RETURN];
+        let mut evm = evm(&[]);
+        let target = create_address(&address(SENDER), 0);
+        evm.state_mut().set_storage(&target, U256::zero(), U256::one());
+
+        let report = evm.transact(&tx(None, initcode.clone(), 100_000)).unwrap();
+        assert_eq!(report.outcome, Outcome::Halt(Halt::CreateCollision));
+        assert_eq!(report.gas_used, 100_000);
+        assert_eq!(evm.state().account(&target).unwrap().nonce, 0);
+
+        // The next nonce deploys to a fresh address.
+        assert!(evm.transact(&tx(None, initcode, 100_000)).unwrap().outcome.is_success());
+    }
+
+    #[test]
+    fn transactions_are_capped_and_unsupported_precompiles_rejected() {
+        let mut evm = evm(&[(CONTRACT, caller_code(&[GAS], 0))]);
+        let too_much = evm.transact(&tx(Some(CONTRACT), Vec::new(), TX_GAS_LIMIT_CAP + 1));
+        assert_eq!(too_much, Err(EvmError::GasLimitAboveCap { gas_limit: TX_GAS_LIMIT_CAP + 1 }));
+
+        // Calling the KZG precompile aborts the transaction and leaves the state alone.
+        let code = [&[PUSH0, PUSH0, PUSH0, PUSH0, PUSH0, PUSH1, 0x0a, GAS, CALL][..], &RETURN_TOP].concat();
+        let mut evm = evm_with_balance(code, Vec::new(), U256::zero());
+        let before = evm.state().clone();
+        let err = evm.transact(&tx(Some(CONTRACT), Vec::new(), 100_000)).unwrap_err();
+        assert!(matches!(err, EvmError::UnsupportedPrecompile { name: "KZG point evaluation", .. }));
+        assert_eq!(evm.state(), &before);
+    }
+}
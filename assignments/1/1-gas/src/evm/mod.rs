@@ -0,0 +1,188 @@
+//! A small EVM interpreter with an in-memory state, used to measure how much
+//! gas a contract call really costs. It follows the Osaka rules
+//! ([`Hardfork::LATEST`](crate::intrinsic::Hardfork::LATEST)), including
+//! memory expansion and EIP-2929 warm/cold access costs.
+//!
+//! Gas is metered but never paid for: balances only move through `value`.
+
+pub(crate) mod gas;
+mod interpreter;
+pub mod opcode;
+mod precompile;
+pub mod state;
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use ethereum_types::{H256, U256};
+
+use crate::intrinsic::IntrinsicGasError;
+
+pub use interpreter::{create2_address, create_address, keccak256, Evm};
+pub use state::{Account, Address, State};
+
+/// EIP-7825 (Osaka): the most gas a single transaction may ask for.
+pub const TX_GAS_LIMIT_CAP: u64 = 1 << 24;
+
+/// Block the transaction is executed in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockEnv {
+    pub number: u64,
+    pub timestamp: u64,
+    pub coinbase: Address,
+    pub gas_limit: u64,
+    pub base_fee: U256,
+    pub prevrandao: H256,
+    pub chain_id: u64,
+    pub blob_base_fee: U256,
+}
+
+impl Default for BlockEnv {
+    fn default() -> Self {
+        Self {
+            number: 10_271_183,
+            timestamp: 1_767_225_600,
+            coinbase: Address::zero(),
+            gas_limit: 36_000_000,
+            base_fee: U256::from(1_000_000_000u64),
+            prevrandao: H256::zero(),
+            chain_id: 11_155_111,
+            blob_base_fee: U256::one(),
+        }
+    }
+}
+
+/// A transaction to execute. `to: None` deploys `data` as initcode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub caller: Address,
+    pub to: Option<Address>,
+    pub value: U256,
+    pub data: Vec<u8>,
+    pub gas_limit: u64,
+    pub gas_price: U256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Log {
+    pub address: Address,
+    pub topics: Vec<H256>,
+    pub data: Vec<u8>,
+}
+
+/// Exceptional halts. They consume all gas given to the failing frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Halt {
+    OutOfGas,
+    StackUnderflow,
+    StackOverflow,
+    InvalidJump,
+    InvalidOpcode(u8),
+    StateChangeInStaticCall,
+    ReturnDataOutOfBounds,
+    InitcodeTooLarge,
+    CodeTooLarge,
+    InvalidCodePrefix,
+    CreateCollision,
+    /// A precompile was given input it cannot process, such as a point off the curve.
+    PrecompileFailure,
+}
+
+impl fmt::Display for Halt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Halt::OutOfGas => write!(f, "out of gas"),
+            Halt::StackUnderflow => write!(f, "stack underflow"),
+            Halt::StackOverflow => write!(f, "stack overflow"),
+            Halt::InvalidJump => write!(f, "invalid jump destination"),
+            Halt::InvalidOpcode(op) => write!(f, "invalid opcode 0x{:02x}", op),
+            Halt::StateChangeInStaticCall => write!(f, "state change in a static call"),
+            Halt::ReturnDataOutOfBounds => write!(f, "return data out of bounds"),
+            Halt::InitcodeTooLarge => write!(f, "initcode too large"),
+            Halt::CodeTooLarge => write!(f, "deployed code too large"),
+            Halt::InvalidCodePrefix => write!(f, "deployed code starts with 0xef"),
+            Halt::CreateCollision => write!(f, "contract address already in use"),
+            Halt::PrecompileFailure => write!(f, "precompile rejected its input"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Success(Vec<u8>),
+    Revert(Vec<u8>),
+    Halt(Halt),
+}
+
+impl Outcome {
+    pub fn is_success(&self) -> bool {
+        matches!(self, Outcome::Success(_))
+    }
+}
+
+/// How often an opcode ran and how much gas it charged in total. Gas handed
+/// to a sub-call is not counted against the CALL/CREATE opcode itself.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpcodeStats {
+    pub count: u64,
+    pub gas: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionReport {
+    pub outcome: Outcome,
+    /// Gas charged to the sender, after refunds and the EIP-7623 floor.
+    pub gas_used: u64,
+    pub intrinsic_gas: u64,
+    /// Gas spent executing bytecode, before refunds.
+    pub execution_gas: u64,
+    /// Refund actually granted, after the EIP-3529 cap.
+    pub refund: u64,
+    pub created_address: Option<Address>,
+    pub logs: Vec<Log>,
+    pub opcodes: BTreeMap<u8, OpcodeStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvmError {
+    Intrinsic(IntrinsicGasError),
+    GasLimitBelowIntrinsic { gas_limit: u64, intrinsic_gas: u64 },
+    GasLimitAboveCap { gas_limit: u64 },
+    InsufficientBalance { balance: U256, value: U256 },
+    NonceOverflow,
+    /// A call reached a precompile this EVM does not run.
+    UnsupportedPrecompile { address: Address, name: &'static str },
+}
+
+impl fmt::Display for EvmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvmError::Intrinsic(err) => write!(f, "{}", err),
+            EvmError::GasLimitBelowIntrinsic { gas_limit, intrinsic_gas } => write!(
+                f,
+                "gas limit {} is below the intrinsic gas of {}",
+                gas_limit, intrinsic_gas
+            ),
+            EvmError::GasLimitAboveCap { gas_limit } => write!(
+                f,
+                "gas limit {} is above the per-transaction cap of {}",
+                gas_limit, TX_GAS_LIMIT_CAP
+            ),
+            EvmError::InsufficientBalance { balance, value } => {
+                write!(f, "sender balance {} wei cannot cover a value of {} wei", balance, value)
+            }
+            EvmError::NonceOverflow => write!(f, "sender nonce is at its maximum"),
+            EvmError::UnsupportedPrecompile { address, name } => {
+                write!(f, "the {} precompile at {:?} is not supported", name, address)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EvmError {}
+
+impl From<IntrinsicGasError> for EvmError {
+    fn from(err: IntrinsicGasError) -> Self {
+        EvmError::Intrinsic(err)
+    }
+}
@@ -0,0 +1,535 @@
+//! Precompiled contracts, priced as in Osaka.
+//!
+//! The KZG point evaluation (0x0a) and BLS12-381 (0x0b-0x11) contracts are
+//! known, so they start warm like the rest, but not run: calling one stops
+//! the transaction with [`EvmError::UnsupportedPrecompile`].
+
+use ethereum_types::U256;
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use num_bigint::BigUint;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+use substrate_bn::{AffineG1, AffineG2, Fq, Fq2, Fr, Group, Gt, G1, G2};
+
+use super::gas::words;
+use super::interpreter::{keccak256, Exception};
+use super::{Address, EvmError, Halt};
+
+const ECRECOVER: u64 = 0x01;
+const SHA256: u64 = 0x02;
+const RIPEMD160: u64 = 0x03;
+const IDENTITY: u64 = 0x04;
+const MODEXP: u64 = 0x05;
+const BN254_ADD: u64 = 0x06;
+const BN254_MUL: u64 = 0x07;
+const BN254_PAIRING: u64 = 0x08;
+const BLAKE2F: u64 = 0x09;
+const POINT_EVALUATION: u64 = 0x0a;
+/// EIP-7951 (Osaka), after the BLS12-381 contracts of EIP-2537 at 0x0b-0x11.
+const P256VERIFY: u64 = 0x100;
+
+const ADDRESSES: [u64; 18] = [
+    0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10, 0x11, P256VERIFY,
+];
+
+/// EIP-7823: base, exponent and modulus are each at most 1024 bytes.
+const MODEXP_MAX_LEN: usize = 1024;
+const PAIRING_ELEMENT_LEN: usize = 192;
+const BLAKE2F_INPUT_LEN: usize = 213;
+
+pub fn is_precompile(address: &Address) -> bool {
+    addresses().any(|precompile| precompile == *address)
+}
+
+pub fn addresses() -> impl Iterator<Item = Address> {
+    ADDRESSES.into_iter().map(Address::from_low_u64_be)
+}
+
+/// Runs a precompile with `gas` available, returning the gas it costs and
+/// its output. Input the precompile rejects halts the call, like running out
+/// of gas does.
+pub(super) fn run(address: &Address, input: &[u8], gas: u64) -> Result<(u64, Vec<u8>), Exception> {
+    let id = address.to_low_u64_be();
+    let cost = match id {
+        ECRECOVER => 3_000,
+        SHA256 => 60 + 12 * words(input.len()),
+        RIPEMD160 => 600 + 120 * words(input.len()),
+        IDENTITY => 15 + 3 * words(input.len()),
+        MODEXP => modexp_cost(input)?,
+        BN254_ADD => 150,
+        BN254_MUL => 6_000,
+        BN254_PAIRING => 45_000 + 34_000 * (input.len() / PAIRING_ELEMENT_LEN) as u64,
+        BLAKE2F => blake2f_rounds(input)?.into(),
+        P256VERIFY => 6_900,
+        _ => {
+            let name = if id == POINT_EVALUATION { "KZG point evaluation" } else { "BLS12-381" };
+            return Err(EvmError::UnsupportedPrecompile { address: *address, name }.into());
+        }
+    };
+    if cost > gas {
+        return Err(Halt::OutOfGas.into());
+    }
+
+    let output = match id {
+        ECRECOVER => ecrecover(input),
+        SHA256 => Sha256::digest(input).to_vec(),
+        RIPEMD160 => left_pad(&Ripemd160::digest(input), 32),
+        IDENTITY => input.to_vec(),
+        MODEXP => modexp(input),
+        BN254_ADD => bn254_add(input)?,
+        BN254_MUL => bn254_mul(input)?,
+        BN254_PAIRING => bn254_pairing(input)?,
+        BLAKE2F => blake2f(input),
+        _ => p256_verify(input),
+    };
+    Ok((cost, output))
+}
+
+/// `data` zero-padded on the right to at least `len` bytes.
+fn right_pad(data: &[u8], len: usize) -> Vec<u8> {
+    let mut out = data.to_vec();
+    if out.len() < len {
+        out.resize(len, 0);
+    }
+    out
+}
+
+/// `data` zero-padded on the left to `len` bytes.
+fn left_pad(data: &[u8], len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len - data.len()];
+    out.extend_from_slice(data);
+    out
+}
+
+/// Address that signed `hash`, or no output if the signature is invalid.
+fn ecrecover(input: &[u8]) -> Vec<u8> {
+    let input = right_pad(input, 128);
+    let v = U256::from_big_endian(&input[32..64]);
+    if v != U256::from(27) && v != U256::from(28) {
+        return Vec::new();
+    }
+    let Ok(mut signature) = Signature::from_slice(&input[64..128]) else {
+        return Vec::new();
+    };
+    let mut recovery = v.low_u32() as u8 - 27;
+    // The precompile accepts high-s signatures; the library only recovers low-s ones.
+    if let Some(normalized) = signature.normalize_s() {
+        signature = normalized;
+        recovery ^= 1;
+    }
+    let recovery = RecoveryId::from_byte(recovery).expect("recovery id is 0 or 1");
+    match VerifyingKey::recover_from_prehash(&input[..32], &signature, recovery) {
+        Ok(key) => {
+            let point = key.to_encoded_point(false);
+            left_pad(&keccak256(&point.as_bytes()[1..])[12..], 32)
+        }
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Lengths of the base, exponent and modulus, which head the MODEXP input.
+fn modexp_lengths(input: &[u8]) -> Result<[usize; 3], Halt> {
+    let header = right_pad(&input[..input.len().min(96)], 96);
+    let mut lengths = [0; 3];
+    for (length, word) in lengths.iter_mut().zip(header.chunks(32)) {
+        let value = U256::from_big_endian(word);
+        if value > U256::from(MODEXP_MAX_LEN) {
+            return Err(Halt::PrecompileFailure);
+        }
+        *length = value.as_usize();
+    }
+    Ok(lengths)
+}
+
+/// EIP-7883 pricing: at least 500 gas, and twice the EIP-2565 cost for
+/// operands over 32 bytes and exponents over 32 bytes.
+fn modexp_cost(input: &[u8]) -> Result<u64, Halt> {
+    let [base_len, exp_len, mod_len] = modexp_lengths(input)?;
+    let max_len = base_len.max(mod_len) as u64;
+    let complexity = if max_len <= 32 { 16 } else { 2 * max_len.div_ceil(8).pow(2) };
+
+    // The exponent's first 32 bytes, and how many bits the rest adds.
+    let body = input.get(96..).unwrap_or_default();
+    let head_len = exp_len.min(32);
+    let head = right_pad(body.get(base_len..).unwrap_or_default(), head_len);
+    let head_bits = U256::from_big_endian(&head[..head_len]).bits() as u64;
+    let iterations = if exp_len <= 32 {
+        head_bits.saturating_sub(1)
+    } else {
+        16 * (exp_len as u64 - 32) + head_bits.saturating_sub(1)
+    };
+
+    Ok((complexity * iterations.max(1)).max(500))
+}
+
+/// `base ** exponent % modulus`, as many bytes long as the modulus.
+fn modexp(input: &[u8]) -> Vec<u8> {
+    let [base_len, exp_len, mod_len] = modexp_lengths(input).expect("checked when pricing");
+    let body = right_pad(input.get(96..).unwrap_or_default(), base_len + exp_len + mod_len);
+    let base = BigUint::from_bytes_be(&body[..base_len]);
+    let exponent = BigUint::from_bytes_be(&body[base_len..base_len + exp_len]);
+    let modulus = BigUint::from_bytes_be(&body[base_len + exp_len..base_len + exp_len + mod_len]);
+    if modulus == BigUint::ZERO {
+        return vec![0; mod_len];
+    }
+    left_pad(&base.modpow(&exponent, &modulus).to_bytes_be(), mod_len)
+}
+
+fn read_fq(word: &[u8]) -> Result<Fq, Halt> {
+    Fq::from_slice(word).map_err(|_| Halt::PrecompileFailure)
+}
+
+/// A G1 point from 64 bytes; all zeros is the point at infinity.
+fn read_g1(bytes: &[u8]) -> Result<G1, Halt> {
+    let (x, y) = (read_fq(&bytes[..32])?, read_fq(&bytes[32..64])?);
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1::zero());
+    }
+    AffineG1::new(x, y).map(G1::from).map_err(|_| Halt::PrecompileFailure)
+}
+
+/// A G2 point from 128 bytes, each coordinate as its imaginary part first.
+fn read_g2(bytes: &[u8]) -> Result<G2, Halt> {
+    let x = Fq2::new(read_fq(&bytes[32..64])?, read_fq(&bytes[..32])?);
+    let y = Fq2::new(read_fq(&bytes[96..128])?, read_fq(&bytes[64..96])?);
+    if x.is_zero() && y.is_zero() {
+        return Ok(G2::zero());
+    }
+    AffineG2::new(x, y).map(G2::from).map_err(|_| Halt::PrecompileFailure)
+}
+
+fn encode_g1(point: G1) -> Vec<u8> {
+    let mut out = vec![0u8; 64];
+    if let Some(point) = AffineG1::from_jacobian(point) {
+        point.x().to_big_endian(&mut out[..32]).expect("32 bytes");
+        point.y().to_big_endian(&mut out[32..]).expect("32 bytes");
+    }
+    out
+}
+
+fn bn254_add(input: &[u8]) -> Result<Vec<u8>, Halt> {
+    let input = right_pad(input, 128);
+    Ok(encode_g1(read_g1(&input[..64])? + read_g1(&input[64..128])?))
+}
+
+fn bn254_mul(input: &[u8]) -> Result<Vec<u8>, Halt> {
+    let input = right_pad(input, 96);
+    let scalar = Fr::from_slice(&input[64..96]).expect("32 bytes");
+    Ok(encode_g1(read_g1(&input[..64])? * scalar))
+}
+
+/// One word: 1 if the product of the pairings of each (G1, G2) pair is one.
+fn bn254_pairing(input: &[u8]) -> Result<Vec<u8>, Halt> {
+    if !input.len().is_multiple_of(PAIRING_ELEMENT_LEN) {
+        return Err(Halt::PrecompileFailure);
+    }
+    let pairs = input
+        .chunks(PAIRING_ELEMENT_LEN)
+        .map(|element| Ok((read_g1(&element[..64])?, read_g2(&element[64..])?)))
+        .collect::<Result<Vec<_>, Halt>>()?;
+    Ok(left_pad(&[u8::from(substrate_bn::pairing_batch(&pairs) == Gt::one())], 32))
+}
+
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// EIP-152 input: rounds (4 bytes, big-endian), state h (64), message m
+/// (128), offset counter t (16) and the final-block flag (1, either 0 or 1).
+fn blake2f_rounds(input: &[u8]) -> Result<u32, Halt> {
+    if input.len() != BLAKE2F_INPUT_LEN || input[212] > 1 {
+        return Err(Halt::PrecompileFailure);
+    }
+    Ok(u32::from_be_bytes(input[..4].try_into().expect("4 bytes")))
+}
+
+/// The BLAKE2b compression function F, run for the given number of rounds.
+fn blake2f(input: &[u8]) -> Vec<u8> {
+    let rounds = blake2f_rounds(input).expect("checked when pricing");
+    let le_words = |bytes: &[u8]| -> Vec<u64> {
+        bytes.chunks(8).map(|word| u64::from_le_bytes(word.try_into().expect("8 bytes"))).collect()
+    };
+    let mut h = le_words(&input[4..68]);
+    let m = le_words(&input[68..196]);
+    let t = le_words(&input[196..212]);
+
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(&h);
+    v[8..].copy_from_slice(&BLAKE2B_IV);
+    v[12] ^= t[0];
+    v[13] ^= t[1];
+    if input[212] == 1 {
+        v[14] = !v[14];
+    }
+
+    for round in 0..rounds as usize {
+        let s = &BLAKE2B_SIGMA[round % 10];
+        for (i, [a, b, c, d]) in [
+            [0, 4, 8, 12],
+            [1, 5, 9, 13],
+            [2, 6, 10, 14],
+            [3, 7, 11, 15],
+            [0, 5, 10, 15],
+            [1, 6, 11, 12],
+            [2, 7, 8, 13],
+            [3, 4, 9, 14],
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let (x, y) = (m[s[2 * i]], m[s[2 * i + 1]]);
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+            v[d] = (v[d] ^ v[a]).rotate_right(32);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(24);
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+            v[d] = (v[d] ^ v[a]).rotate_right(16);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(63);
+        }
+    }
+
+    for (i, word) in h.iter_mut().enumerate() {
+        *word ^= v[i] ^ v[i + 8];
+    }
+    h.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+/// One word set to 1 if the secp256r1 signature (hash, r, s, x, y) is
+/// valid, or no output otherwise.
+fn p256_verify(input: &[u8]) -> Vec<u8> {
+    use p256::ecdsa::signature::hazmat::PrehashVerifier;
+
+    if input.len() != 160 {
+        return Vec::new();
+    }
+    let Ok(signature) = p256::ecdsa::Signature::from_slice(&input[32..96]) else {
+        return Vec::new();
+    };
+    let mut point = [0x04; 65];
+    point[1..].copy_from_slice(&input[96..160]);
+    let Ok(key) = p256::ecdsa::VerifyingKey::from_sec1_bytes(&point) else {
+        return Vec::new();
+    };
+    match key.verify_prehash(&input[..32], &signature) {
+        Ok(()) => left_pad(&[1], 32),
+        Err(_) => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_hex(address: u64, input: &str, gas: u64) -> Result<(u64, String), Exception> {
+        let input = hex::decode(input).unwrap();
+        run(&Address::from_low_u64_be(address), &input, gas).map(|(cost, output)| (cost, hex::encode(output)))
+    }
+
+    fn call(address: u64, input: &str) -> (u64, String) {
+        run_hex(address, input, u64::MAX).unwrap()
+    }
+
+    fn word(value: usize) -> String {
+        format!("{:064x}", value)
+    }
+
+    #[test]
+    fn hashes_and_identity_charge_per_word() {
+        let sha256_abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert_eq!(call(SHA256, "616263"), (72, sha256_abc.into()));
+        let ripemd_empty = format!("{}9c1185a5c5e9fc54612808977ee8f548b2258d31", "0".repeat(24));
+        assert_eq!(call(RIPEMD160, ""), (600, ripemd_empty));
+        assert_eq!(call(IDENTITY, &"ab".repeat(33)), (21, "ab".repeat(33)));
+
+        assert!(matches!(run_hex(SHA256, "616263", 71), Err(Exception::Halt(Halt::OutOfGas))));
+    }
+
+    #[test]
+    fn ecrecover_returns_the_signer_or_nothing() {
+        // go-ethereum's ECRECOVER test vector.
+        let hash = "38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e";
+        let r = "38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e";
+        let s = "789d1dd423d25f0772d2748d60f7e4b81bb14d086eba8e8e8efb6dcff8a4ae02";
+        let signer = format!("{}ceaccac640adf55b2028469bd36ba501f28b699d", "0".repeat(24));
+        assert_eq!(call(ECRECOVER, &format!("{}{}{}{}", hash, word(27), r, s)), (3_000, signer));
+
+        // A bad `v` or `s` still costs 3000 gas but returns no address.
+        assert_eq!(call(ECRECOVER, &format!("{}{}{}{}", hash, word(29), r, s)), (3_000, String::new()));
+        assert_eq!(call(ECRECOVER, &format!("{}{}{}{}", hash, word(27), r, word(0))), (3_000, String::new()));
+    }
+
+    #[test]
+    fn modexp_is_priced_by_eip_7883() {
+        let p = "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f";
+        let p_minus_1 = "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e";
+
+        // EIP-198's examples: 3^(p-1) mod p, and 0^(p-1) mod p with an empty base.
+        // Operands of 32 bytes or less cost 16 per iteration: 16 * 255.
+        let fermat = format!("{}{}{}03{}{}", word(1), word(32), word(32), p_minus_1, p);
+        assert_eq!(call(MODEXP, &fermat), (4_080, word(1)));
+        let zero_base = format!("{}{}{}{}{}", word(0), word(32), word(32), p_minus_1, p);
+        assert_eq!(call(MODEXP, &zero_base), (4_080, word(0)));
+
+        // go-ethereum's nagydani-1 inputs: 64-byte operands cost 2 * 8^2 = 128
+        // per iteration, but never less than 500.
+        let base = "e09ad9675465c53a109fac66a445c91b292d2bb2c5268addb30cd82f80fcb003\
+                    3ff97c80a5fc6f39193ae969c6ede6710a6b7ac27078a06d90ef1c72e5c85fb5";
+        let modulus = "fc9e1f6beb81516545975218075ec2af118cd8798df6e08a147c60fd6095ac2b\
+                       b02c2908cf4dd7c81f11c289e4bce98f3553768f392a80ce22bf5c4f4a248c6b";
+        let square = format!("{}{}{}{}02{}", word(64), word(1), word(64), base, modulus);
+        let squared = "60008f1614cc01dcfb6bfb09c625cf90b47d4468db81b5f8b7a39d42f332eab9\
+                       b2da8f2d95311648a8f243f4bb13cfb3d8f7f2a3c014122ebb3ed41b02783adc";
+        assert_eq!(call(MODEXP, &square), (500, squared.into()));
+        let pow_0x10001 = format!("{}{}{}{}010001{}", word(64), word(3), word(64), base, modulus);
+        let powered = "c36d804180c35d4426b57b50c5bfcca5c01856d104564cd513b461d3c8b84091\
+                       28a5573e416d0ebe38f5f736766d9dc27143e4da981dfa4d67f7dc474cbee6d2";
+        assert_eq!(call(MODEXP, &pow_0x10001), (128 * 16, powered.into()));
+
+        // Each exponent byte past the first 32 adds 16 iterations: 16 * (16 * 8).
+        let long_exponent = format!("{}{}{}02{}{}05", word(1), word(40), word(1), word(1), "00".repeat(8));
+        assert_eq!(call(MODEXP, &long_exponent).0, 16 * 128);
+    }
+
+    #[test]
+    fn modexp_rejects_operands_over_1024_bytes() {
+        let input = format!("{}{}{}", word(1025), word(1), word(1));
+        assert!(matches!(
+            run_hex(MODEXP, &input, u64::MAX),
+            Err(Exception::Halt(Halt::PrecompileFailure))
+        ));
+        assert_eq!(call(MODEXP, &format!("{}{}{}", word(0), word(0), word(1024))).1, "00".repeat(1024));
+    }
+
+    // Inputs and outputs from go-ethereum's bn256 precompile tests.
+    const PAIRING: &str = "\
+        1c76476f4def4bb94541d57ebba1193381ffa7aa76ada664dd31c16024c43f59\
+        3034dd2920f673e204fee2811c678745fc819b55d3e9d294e45c9b03a76aef41\
+        209dd15ebff5d46c4bd888e51a93cf99a7329636c63514396b4a452003a35bf7\
+        04bf11ca01483bfa8b34b43561848d28905960114c8ac04049af4b6315a41678\
+        2bb8324af6cfc93537a2ad1a445cfd0ca2a71acd7ac41fadbf933c2a51be344d\
+        120a2a4cf30c1bf9845f20c6fe39e07ea2cce61f0c9bb048165fe5e4de877550\
+        111e129f1cf1097710d41c4ac70fcdfa5ba2023c6ff1cbeac322de49d1b6df7c\
+        2032c61a830e3c17286de9462bf242fca2883585b93870a73853face6a6bf411\
+        198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
+        1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
+        090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
+        12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa";
+
+    #[test]
+    fn bn254_add_mul_and_pairing() {
+        let add = "18b18acfb4c2c30276db5411368e7185b311dd124691610c5d3b74034e093dc9\
+                   063c909c4720840cb5134cb9f59fa749755796819658d32efc0d288198f37266\
+                   07c2b7f58a84bd6145f00c9c2bc0bb1a187f20ff2c92963a88019e7c6a014eed\
+                   06614e20c147e940f2d70da3f74c9a17df361706a4485c742bd6788478fa17d7";
+        let sum = "2243525c5efd4b9c3d3c45ac0ca3fe4dd85e830a4ce6b65fa1eeaee202839703\
+                   301d1d33be6da8e509df21cc35964723180eed7532537db9ae5e7d48f195c915";
+        assert_eq!(call(BN254_ADD, add), (150, sum.into()));
+        assert_eq!(call(BN254_ADD, ""), (150, "00".repeat(64)));
+
+        let mul = "2bd3e6d0f3b142924f5ca7b49ce5b9d54c4703d7ae5648e61d02268b1a0a9fb7\
+                   21611ce0a6af85915e2f1d70300909ce2e49dfad4a4619c8390cae66cefdb204\
+                   00000000000000000000000000000000000000000000000011138ce750fa15c2";
+        let product = "070a8d6a982153cae4be29d434e8faef8a47b274a053f5a4ee2a6c9c13c31e5c\
+                       031b8ce914eba3a9ffb989f9cdd5b0f01943074bf4f0f315690ec3cec6981afc";
+        assert_eq!(call(BN254_MUL, mul), (6_000, product.into()));
+
+        assert_eq!(call(BN254_PAIRING, PAIRING), (45_000 + 2 * 34_000, word(1)));
+        assert_eq!(call(BN254_PAIRING, ""), (45_000, word(1)));
+        assert_eq!(call(BN254_PAIRING, &PAIRING[..384]).1, word(0));
+    }
+
+    #[test]
+    fn bn254_rejects_points_off_the_curve() {
+        let off_curve = "11".repeat(64);
+        for (address, input) in [
+            (BN254_ADD, off_curve.clone()),
+            (BN254_MUL, off_curve.clone()),
+            (BN254_PAIRING, "11".repeat(192)),
+            (BN254_PAIRING, PAIRING[..382].to_string()),
+        ] {
+            assert!(
+                matches!(run_hex(address, &input, u64::MAX), Err(Exception::Halt(Halt::PrecompileFailure))),
+                "{:#x}",
+                address
+            );
+        }
+    }
+
+    #[test]
+    fn blake2f_runs_the_requested_rounds() {
+        // EIP-152 test vector 5: one final block of "abc" under 12 rounds is BLAKE2b-512("abc").
+        let input = "0000000c\
+                     48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+                     d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b\
+                     6162630000000000000000000000000000000000000000000000000000000000\
+                     0000000000000000000000000000000000000000000000000000000000000000\
+                     0000000000000000000000000000000000000000000000000000000000000000\
+                     0000000000000000000000000000000000000000000000000000000000000000\
+                     0300000000000000000000000000000001";
+        let digest = "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+                      7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923";
+        assert_eq!(call(BLAKE2F, input), (12, digest.into()));
+
+        let bad_flag = format!("{}02", &input[..424]);
+        for input in [&input[..424], &bad_flag] {
+            assert!(matches!(run_hex(BLAKE2F, input, u64::MAX), Err(Exception::Halt(Halt::PrecompileFailure))));
+        }
+    }
+
+    #[test]
+    fn p256_verify_follows_eip_7951() {
+        // From the daimo-eth/p256-verifier test vectors.
+        let valid = "4cee90eb86eaa050036147a12d49004b6b9c72bd725d39d4785011fe190f0b4d\
+                     a73bd4903f0ce3b639bbbf6e8e80d16931ff4bcf5993d58468e8fb19086e8cac\
+                     36dbcd03009df8c59286b162af3bd7fcc0450c9aa81be5d10d312af6c66b1d60\
+                     4aebd3099c618202fcfe16ae7770b0c49ab5eadf74b754204a3bb6060e44eff3\
+                     7618b065f9832de4ca6ca971a7a1adc826d0f7c00181a5fb2ddf79ae00b4e10e";
+        assert_eq!(call(P256VERIFY, valid), (6_900, word(1)));
+
+        let wrong_hash = format!("3{}", &valid[1..]);
+        assert_eq!(call(P256VERIFY, &wrong_hash), (6_900, String::new()));
+        assert_eq!(call(P256VERIFY, &valid[..318]), (6_900, String::new()));
+    }
+
+    #[test]
+    fn kzg_and_bls_precompiles_are_unsupported() {
+        for (address, name) in [(POINT_EVALUATION, "KZG point evaluation"), (0x0b, "BLS12-381"), (0x11, "BLS12-381")] {
+            let address = Address::from_low_u64_be(address);
+            match run(&address, &[], u64::MAX) {
+                Err(Exception::Fatal(err)) => assert_eq!(err, EvmError::UnsupportedPrecompile { address, name }),
+                other => panic!("{:?}: {:?}", address, other),
+            }
+        }
+        let err = EvmError::UnsupportedPrecompile {
+            address: Address::from_low_u64_be(POINT_EVALUATION),
+            name: "KZG point evaluation",
+        };
+        assert_eq!(
+            err.to_string(),
+            "the KZG point evaluation precompile at 0x000000000000000000000000000000000000000a is not supported"
+        );
+        assert!(is_precompile(&Address::from_low_u64_be(0x100)));
+        assert!(!is_precompile(&Address::from_low_u64_be(0x12)));
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use ethereum_types::{H160, U256};
+
+pub type Address = H160;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Account {
+    pub balance: U256,
+    pub nonce: u64,
+    pub code: Vec<u8>,
+    pub storage: HashMap<U256, U256>,
+}
+
+impl Account {
+    /// Empty in the EIP-161 sense: no code, no nonce and no balance.
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty() && self.nonce == 0 && self.balance.is_zero()
+    }
+}
+
+/// World state kept entirely in memory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct State {
+    accounts: HashMap<Address, Account>,
+}
+
+impl State {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn account(&self, address: &Address) -> Option<&Account> {
+        self.accounts.get(address)
+    }
+
+    /// Returns the account at `address`, creating an empty one if needed.
+    pub fn account_mut(&mut self, address: &Address) -> &mut Account {
+        self.accounts.entry(*address).or_default()
+    }
+
+    pub fn remove_account(&mut self, address: &Address) {
+        self.accounts.remove(address);
+    }
+
+    /// True if the account does not exist or is empty.
+    pub fn is_empty(&self, address: &Address) -> bool {
+        self.accounts.get(address).is_none_or(Account::is_empty)
+    }
+
+    pub fn balance(&self, address: &Address) -> U256 {
+        self.accounts.get(address).map(|a| a.balance).unwrap_or_default()
+    }
+
+    pub fn nonce(&self, address: &Address) -> u64 {
+        self.accounts.get(address).map(|a| a.nonce).unwrap_or_default()
+    }
+
+    pub fn code(&self, address: &Address) -> &[u8] {
+        self.accounts.get(address).map(|a| a.code.as_slice()).unwrap_or_default()
+    }
+
+    pub fn storage(&self, address: &Address, key: &U256) -> U256 {
+        self.accounts
+            .get(address)
+            .and_then(|a| a.storage.get(key).copied())
+            .unwrap_or_default()
+    }
+
+    pub fn set_storage(&mut self, address: &Address, key: U256, value: U256) {
+        let storage = &mut self.account_mut(address).storage;
+        if value.is_zero() {
+            storage.remove(&key);
+        } else {
+            storage.insert(key, value);
+        }
+    }
+
+    /// Moves `value` wei between accounts, returning `false` if `from` cannot afford it.
+    pub fn transfer(&mut self, from: &Address, to: &Address, value: U256) -> bool {
+        if self.balance(from) < value {
+            return false;
+        }
+        self.account_mut(from).balance -= value;
+        self.account_mut(to).balance += value;
+        true
+    }
+}
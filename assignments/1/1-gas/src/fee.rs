@@ -0,0 +1,171 @@
+use std::fmt;
+
+use crate::units::Wei;
+
+/// Inputs needed to price a type-2 (EIP-1559) transaction once it has been
+/// included in a block. Every fee value is per unit of gas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeParams {
+    pub gas_used: u64,
+    pub base_fee_per_gas: Wei,
+    pub max_fee_per_gas: Wei,
+    pub max_priority_fee_per_gas: Wei,
+}
+
+/// How the fee of a transaction is split up. All amounts are exact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeBreakdown {
+    /// Price actually paid per unit of gas: `min(max_fee, base_fee + priority_fee)`.
+    pub effective_gas_price: Wei,
+    /// Part of the effective price that goes to the validator.
+    pub priority_fee_per_gas: Wei,
+    /// `gas_used * effective_gas_price`
+    pub total_fee: Wei,
+    /// `gas_used * base_fee`, removed from circulation.
+    pub burnt_fee: Wei,
+    /// `gas_used * priority_fee_per_gas`, paid to the validator.
+    pub validator_tip: Wei,
+    /// Difference between what `max_fee_per_gas` allowed and what was paid.
+    pub refund: Wei,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeeError {
+    MaxFeeBelowBaseFee { max_fee_per_gas: Wei, base_fee_per_gas: Wei },
+    PriorityFeeAboveMaxFee { max_priority_fee_per_gas: Wei, max_fee_per_gas: Wei },
+    Overflow,
+}
+
+impl fmt::Display for FeeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeeError::MaxFeeBelowBaseFee { max_fee_per_gas, base_fee_per_gas } => write!(
+                f,
+                "max fee per gas ({}) is below the block base fee ({})",
+                max_fee_per_gas, base_fee_per_gas
+            ),
+            FeeError::PriorityFeeAboveMaxFee { max_priority_fee_per_gas, max_fee_per_gas } => write!(
+                f,
+                "max priority fee per gas ({}) is above max fee per gas ({})",
+                max_priority_fee_per_gas, max_fee_per_gas
+            ),
+            FeeError::Overflow => write!(f, "fee does not fit in 256 bits"),
+        }
+    }
+}
+
+impl std::error::Error for FeeError {}
+
+/// Prices a transaction the way EIP-1559 does.
+///
+/// The sender offers at most `max_fee_per_gas`. The base fee is always paid
+/// and burnt, the validator gets whatever is left up to
+/// `max_priority_fee_per_gas`, and the rest is refunded.
+pub fn compute_fee(params: FeeParams) -> Result<FeeBreakdown, FeeError> {
+    let FeeParams {
+        gas_used,
+        base_fee_per_gas,
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+    } = params;
+
+    if max_fee_per_gas < base_fee_per_gas {
+        return Err(FeeError::MaxFeeBelowBaseFee { max_fee_per_gas, base_fee_per_gas });
+    }
+    if max_priority_fee_per_gas > max_fee_per_gas {
+        return Err(FeeError::PriorityFeeAboveMaxFee { max_priority_fee_per_gas, max_fee_per_gas });
+    }
+
+    let priority_fee_per_gas = max_priority_fee_per_gas.min(max_fee_per_gas.saturating_sub(base_fee_per_gas));
+    let effective_gas_price = base_fee_per_gas
+        .checked_add(priority_fee_per_gas)
+        .ok_or(FeeError::Overflow)?;
+    let for_gas_used = |price: Wei| price.checked_mul(gas_used).ok_or(FeeError::Overflow);
+
+    Ok(FeeBreakdown {
+        effective_gas_price,
+        priority_fee_per_gas,
+        total_fee: for_gas_used(effective_gas_price)?,
+        burnt_fee: for_gas_used(base_fee_per_gas)?,
+        validator_tip: for_gas_used(priority_fee_per_gas)?,
+        refund: for_gas_used(max_fee_per_gas.saturating_sub(effective_gas_price))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Gwei;
+
+    fn params(gas_used: u64, base_fee: u64, max_fee: u64, max_priority_fee: u64) -> FeeParams {
+        FeeParams {
+            gas_used,
+            base_fee_per_gas: Gwei::new(base_fee).into(),
+            max_fee_per_gas: Gwei::new(max_fee).into(),
+            max_priority_fee_per_gas: Gwei::new(max_priority_fee).into(),
+        }
+    }
+
+    fn gwei(amount: u64) -> Wei {
+        Gwei::new(amount).into()
+    }
+
+    #[test]
+    fn full_priority_fee_when_max_fee_allows_it() {
+        let fee = compute_fee(params(21_000, 20, 30, 2)).unwrap();
+        assert_eq!(fee.effective_gas_price, gwei(22));
+        assert_eq!(fee.priority_fee_per_gas, gwei(2));
+        assert_eq!(fee.total_fee, gwei(462_000));
+        assert_eq!(fee.burnt_fee, gwei(420_000));
+        assert_eq!(fee.validator_tip, gwei(42_000));
+        assert_eq!(fee.refund, gwei(168_000));
+        assert_eq!(fee.burnt_fee.checked_add(fee.validator_tip), Some(fee.total_fee));
+    }
+
+    #[test]
+    fn priority_fee_is_capped_by_max_fee() {
+        let fee = compute_fee(params(50_000, 29, 30, 2)).unwrap();
+        assert_eq!(fee.effective_gas_price, gwei(30));
+        assert_eq!(fee.priority_fee_per_gas, gwei(1));
+        assert_eq!(fee.validator_tip, gwei(50_000));
+        assert_eq!(fee.refund, Wei::zero());
+
+        let at_base_fee = compute_fee(params(21_000, 30, 30, 2)).unwrap();
+        assert_eq!(at_base_fee.priority_fee_per_gas, Wei::zero());
+        assert_eq!(at_base_fee.total_fee, at_base_fee.burnt_fee);
+    }
+
+    #[test]
+    fn rejects_max_fee_below_base_fee() {
+        assert_eq!(
+            compute_fee(params(21_000, 31, 30, 2)),
+            Err(FeeError::MaxFeeBelowBaseFee {
+                max_fee_per_gas: gwei(30),
+                base_fee_per_gas: gwei(31),
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_priority_fee_above_max_fee() {
+        assert_eq!(
+            compute_fee(params(21_000, 20, 30, 31)),
+            Err(FeeError::PriorityFeeAboveMaxFee {
+                max_priority_fee_per_gas: gwei(31),
+                max_fee_per_gas: gwei(30),
+            })
+        );
+    }
+
+    #[test]
+    fn reports_overflow() {
+        let huge = Wei::from_wei(ethereum_types::U256::MAX / 2);
+        let params = FeeParams {
+            gas_used: 21_000,
+            base_fee_per_gas: huge,
+            max_fee_per_gas: huge,
+            max_priority_fee_per_gas: Wei::zero(),
+        };
+        assert_eq!(compute_fee(params), Err(FeeError::Overflow));
+    }
+}
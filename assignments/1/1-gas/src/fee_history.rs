@@ -0,0 +1,313 @@
+//! Fee recommendations from `eth_feeHistory`.
+//!
+//! The node reports the base fee of a range of blocks, how full they were and
+//! the priority fees paid at a few percentiles. The 10th, 50th and 90th
+//! percentile rewards become the slow, standard and fast tips.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use ethereum_types::U256;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::base_fee::next_base_fee;
+use crate::units::Wei;
+
+/// Reward percentiles asked for over RPC, one per `Speed`.
+pub const REWARD_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+/// `maxFeePerGas` covers the base fee after this many full blocks in a row
+/// (about 2x the next base fee), so a transaction stays valid while it waits.
+pub const BASE_FEE_HEADROOM_BLOCKS: usize = 6;
+
+/// Result of `eth_feeHistory`, with every quantity decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeeHistory {
+    pub oldest_block: u64,
+    /// One entry per block plus the base fee of the block after the newest one.
+    pub base_fee_per_gas: Vec<Wei>,
+    pub gas_used_ratio: Vec<f64>,
+    /// Per block, the priority fee paid at each requested percentile.
+    pub reward: Vec<Vec<Wei>>,
+}
+
+impl FeeHistory {
+    /// Base fee of the block after the newest one in the history.
+    pub fn next_base_fee(&self) -> Option<Wei> {
+        self.base_fee_per_gas.last().copied()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Speed {
+    Slow,
+    Standard,
+    Fast,
+}
+
+impl Speed {
+    pub const ALL: [Speed; 3] = [Speed::Slow, Speed::Standard, Speed::Fast];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Speed::Slow => "slow",
+            Speed::Standard => "standard",
+            Speed::Fast => "fast",
+        }
+    }
+
+    /// Column of `FeeHistory::reward` this speed reads.
+    fn reward_index(self) -> usize {
+        match self {
+            Speed::Slow => 0,
+            Speed::Standard => 1,
+            Speed::Fast => 2,
+        }
+    }
+}
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// Values to put in a type-2 transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeEstimate {
+    pub speed: Speed,
+    pub max_priority_fee_per_gas: Wei,
+    pub max_fee_per_gas: Wei,
+}
+
+#[derive(Debug)]
+pub enum FeeHistoryError {
+    Io(std::io::Error),
+    Http(String),
+    Rpc(String),
+    Parse(String),
+    InvalidQuantity(String),
+    /// Each reward row must hold the slow, standard and fast percentiles.
+    RewardPercentiles { expected: usize, got: usize },
+    Empty,
+    Overflow,
+}
+
+impl fmt::Display for FeeHistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeeHistoryError::Io(err) => write!(f, "could not read fee history: {}", err),
+            FeeHistoryError::Http(msg) => write!(f, "RPC request failed: {}", msg),
+            FeeHistoryError::Rpc(msg) => write!(f, "node returned an error: {}", msg),
+            FeeHistoryError::Parse(msg) => write!(f, "could not parse fee history: {}", msg),
+            FeeHistoryError::InvalidQuantity(value) => write!(f, "`{}` is not a hex quantity", value),
+            FeeHistoryError::RewardPercentiles { expected, got } => write!(
+                f,
+                "expected {} reward percentiles per block, got {}",
+                expected, got
+            ),
+            FeeHistoryError::Empty => write!(f, "fee history has no blocks with rewards"),
+            FeeHistoryError::Overflow => write!(f, "fee does not fit in 256 bits"),
+        }
+    }
+}
+
+impl std::error::Error for FeeHistoryError {}
+
+impl From<std::io::Error> for FeeHistoryError {
+    fn from(err: std::io::Error) -> Self {
+        FeeHistoryError::Io(err)
+    }
+}
+
+/// `eth_feeHistory` as it comes over the wire, with hex-encoded quantities.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawFeeHistory {
+    oldest_block: String,
+    base_fee_per_gas: Vec<String>,
+    gas_used_ratio: Vec<f64>,
+    #[serde(default)]
+    reward: Vec<Vec<String>>,
+}
+
+fn quantity(value: &str) -> Result<U256, FeeHistoryError> {
+    let invalid = || FeeHistoryError::InvalidQuantity(value.to_string());
+    let digits = value.strip_prefix("0x").ok_or_else(invalid)?;
+    U256::from_str_radix(digits, 16).map_err(|_| invalid())
+}
+
+/// Decodes either a bare `eth_feeHistory` result or the whole JSON-RPC
+/// response it came in.
+pub fn parse_fee_history(json: &str) -> Result<FeeHistory, FeeHistoryError> {
+    let value: Value = serde_json::from_str(json).map_err(|e| FeeHistoryError::Parse(e.to_string()))?;
+    from_value(value)
+}
+
+fn from_value(mut value: Value) -> Result<FeeHistory, FeeHistoryError> {
+    if let Some(error) = value.get("error") {
+        let message = error.get("message").and_then(Value::as_str).map(String::from);
+        return Err(FeeHistoryError::Rpc(message.unwrap_or_else(|| error.to_string())));
+    }
+    if let Some(result) = value.get_mut("result") {
+        value = result.take();
+    }
+
+    let raw: RawFeeHistory = serde_json::from_value(value).map_err(|e| FeeHistoryError::Parse(e.to_string()))?;
+    let oldest_block = quantity(&raw.oldest_block)?;
+    if oldest_block > U256::from(u64::MAX) {
+        return Err(FeeHistoryError::InvalidQuantity(raw.oldest_block));
+    }
+    let wei = |value: &String| quantity(value).map(Wei::from_wei);
+
+    Ok(FeeHistory {
+        oldest_block: oldest_block.as_u64(),
+        base_fee_per_gas: raw.base_fee_per_gas.iter().map(wei).collect::<Result<_, _>>()?,
+        gas_used_ratio: raw.gas_used_ratio,
+        reward: raw
+            .reward
+            .iter()
+            .map(|row| row.iter().map(wei).collect::<Result<_, _>>())
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Reads a saved `eth_feeHistory` response.
+pub fn load_fee_history(path: &Path) -> Result<FeeHistory, FeeHistoryError> {
+    parse_fee_history(&fs::read_to_string(path)?)
+}
+
+/// Calls `eth_feeHistory` on a plain-HTTP node such as `http://localhost:8545`
+/// for the latest `block_count` blocks, asking for `REWARD_PERCENTILES`.
+pub fn fetch_fee_history(url: &str, block_count: u64) -> Result<FeeHistory, FeeHistoryError> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "eth_feeHistory",
+        "params": [format!("0x{:x}", block_count), "latest", REWARD_PERCENTILES],
+    });
+
+    let response = ureq::post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&request.to_string())
+        .map_err(|e| FeeHistoryError::Http(e.to_string()))?
+        .into_string()?;
+    parse_fee_history(&response)
+}
+
+/// Recommends a tip and max fee for every `Speed`.
+///
+/// The tip is the median, over the history, of the reward percentile that
+/// belongs to the speed. Empty blocks are skipped since they report zero
+/// rewards whatever the demand was. The max fee adds the tip to the base fee
+/// reached after `BASE_FEE_HEADROOM_BLOCKS` full blocks.
+pub fn estimate_fees(history: &FeeHistory) -> Result<Vec<FeeEstimate>, FeeHistoryError> {
+    let upcoming_base_fee = history.next_base_fee().ok_or(FeeHistoryError::Empty)?;
+
+    let rows: Vec<&Vec<Wei>> = history
+        .reward
+        .iter()
+        .zip(&history.gas_used_ratio)
+        .filter(|(_, ratio)| **ratio > 0.0)
+        .map(|(row, _)| row)
+        .collect();
+    if rows.is_empty() {
+        return Err(FeeHistoryError::Empty);
+    }
+    if let Some(row) = rows.iter().find(|row| row.len() != REWARD_PERCENTILES.len()) {
+        return Err(FeeHistoryError::RewardPercentiles {
+            expected: REWARD_PERCENTILES.len(),
+            got: row.len(),
+        });
+    }
+
+    // Any gas limit works here: a full block moves the base fee by the same 1/8.
+    let max_base_fee = (0..BASE_FEE_HEADROOM_BLOCKS)
+        .try_fold(upcoming_base_fee, |base_fee, _| next_base_fee(base_fee, 2, 2).ok())
+        .ok_or(FeeHistoryError::Overflow)?;
+
+    Speed::ALL
+        .into_iter()
+        .map(|speed| {
+            let mut tips: Vec<Wei> = rows.iter().map(|row| row[speed.reward_index()]).collect();
+            tips.sort();
+            let tip = tips[tips.len() / 2];
+            Ok(FeeEstimate {
+                speed,
+                max_priority_fee_per_gas: tip,
+                max_fee_per_gas: max_base_fee.checked_add(tip).ok_or(FeeHistoryError::Overflow)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(name: &str) -> FeeHistory {
+        load_fee_history(&Path::new(env!("CARGO_MANIFEST_DIR")).join("data").join(name)).unwrap()
+    }
+
+    fn wei(wei: u64) -> Wei {
+        Wei::from_wei(U256::from(wei))
+    }
+
+    #[test]
+    fn estimates_from_the_saved_history() {
+        let history = load("fee_history.json");
+        assert_eq!(history.oldest_block, 0x948964);
+        assert_eq!(history.next_base_fee(), Some(wei(0x54132d71)));
+
+        // The next base fee, 1_410_542_961 wei, after six full blocks at +1/8 each.
+        let max_base_fee = 2_859_574_741;
+        let estimates = estimate_fees(&history).unwrap();
+        let expected = [(Speed::Slow, 20_000_000), (Speed::Standard, 110_000_000), (Speed::Fast, 5_000_000_000)];
+        for (estimate, (speed, tip)) in estimates.iter().zip(expected) {
+            assert_eq!(estimate.speed, speed);
+            assert_eq!(estimate.max_priority_fee_per_gas, wei(tip), "{}", speed);
+            assert_eq!(estimate.max_fee_per_gas, wei(max_base_fee + tip), "{}", speed);
+        }
+    }
+
+    #[test]
+    fn empty_blocks_do_not_pull_the_median_down() {
+        // The first three blocks; the third is empty and reports zero rewards.
+        let mut history = load("fee_history.json");
+        history.gas_used_ratio.truncate(3);
+        history.reward.truncate(3);
+        assert_eq!(history.reward[2], vec![Wei::zero(); 3]);
+
+        let estimates = estimate_fees(&history).unwrap();
+        let tips: Vec<Wei> = estimates.iter().map(|e| e.max_priority_fee_per_gas).collect();
+        assert_eq!(tips, [wei(30_000_000), wei(100_000_000), wei(6_000_000_000)]);
+
+        history.gas_used_ratio[..2].fill(0.0);
+        assert!(matches!(estimate_fees(&history), Err(FeeHistoryError::Empty)));
+    }
+
+    #[test]
+    fn captured_response_with_one_percentile_is_rejected() {
+        // A node's reply to a request for the 50th percentile only, taken from
+        // alloy-rpc-types-eth's `test_fee_history_serde_2`.
+        let history = load("fee_history_one_percentile.json");
+        assert_eq!(history.oldest_block, 0x59f94f);
+        assert_eq!(history.base_fee_per_gas.len(), history.gas_used_ratio.len() + 1);
+        assert_eq!(history.next_base_fee(), Some(wei(0x5da59cf89)));
+        assert_eq!(history.gas_used_ratio[2], 0.0);
+        assert_eq!(history.reward[2], vec![Wei::zero()]);
+        assert!(matches!(
+            estimate_fees(&history),
+            Err(FeeHistoryError::RewardPercentiles { expected: 3, got: 1 })
+        ));
+    }
+
+    #[test]
+    fn rpc_errors_and_bad_quantities() {
+        let error = parse_fee_history(r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"boom"}}"#);
+        assert!(matches!(error, Err(FeeHistoryError::Rpc(message)) if message == "boom"));
+        let bad = parse_fee_history(r#"{"oldestBlock":"12","baseFeePerGas":[],"gasUsedRatio":[]}"#);
+        assert!(matches!(bad, Err(FeeHistoryError::InvalidQuantity(value)) if value == "12"));
+    }
+}
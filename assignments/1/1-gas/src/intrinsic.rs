@@ -0,0 +1,353 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Every transaction pays this before a single opcode runs.
+pub const TX_BASE_GAS: u64 = 21_000;
+/// Extra charge for a contract-creation transaction (Homestead onwards).
+pub const TX_CREATE_GAS: u64 = 32_000;
+pub const TX_DATA_ZERO_GAS: u64 = 4;
+/// Cost of a non-zero calldata byte before EIP-2028 (Istanbul).
+pub const TX_DATA_NON_ZERO_GAS_FRONTIER: u64 = 68;
+/// Cost of a non-zero calldata byte after EIP-2028 (Istanbul).
+pub const TX_DATA_NON_ZERO_GAS: u64 = 16;
+/// EIP-3860: cost per 32-byte word of initcode.
+pub const INITCODE_WORD_GAS: u64 = 2;
+/// EIP-3860: largest initcode a creation transaction may carry.
+pub const MAX_INITCODE_SIZE: usize = 2 * 24_576;
+/// EIP-2930 access list costs.
+pub const ACCESS_LIST_ADDRESS_GAS: u64 = 2_400;
+pub const ACCESS_LIST_STORAGE_KEY_GAS: u64 = 1_900;
+/// EIP-7702: charged for every authorization in a set-code transaction.
+pub const PER_EMPTY_ACCOUNT_GAS: u64 = 25_000;
+/// EIP-7623: calldata is counted in tokens, a non-zero byte being 4 tokens.
+pub const TOKENS_PER_NON_ZERO_BYTE: u64 = 4;
+pub const TOTAL_COST_FLOOR_PER_TOKEN: u64 = 10;
+
+/// Forks that changed how transactions are priced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Hardfork {
+    Frontier,
+    Homestead,
+    Istanbul,
+    Berlin,
+    London,
+    Shanghai,
+    Cancun,
+    Prague,
+    Osaka,
+    /// Blob-parameter-only forks (EIP-7892): priced like Osaka apart from blobs.
+    Bpo1,
+    Bpo2,
+}
+
+impl Hardfork {
+    pub const ALL: [Hardfork; 11] = [
+        Hardfork::Frontier,
+        Hardfork::Homestead,
+        Hardfork::Istanbul,
+        Hardfork::Berlin,
+        Hardfork::London,
+        Hardfork::Shanghai,
+        Hardfork::Cancun,
+        Hardfork::Prague,
+        Hardfork::Osaka,
+        Hardfork::Bpo1,
+        Hardfork::Bpo2,
+    ];
+
+    /// The newest fork this crate prices, used when no fork is given.
+    pub const LATEST: Hardfork = Hardfork::Osaka;
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Hardfork::Frontier => "frontier",
+            Hardfork::Homestead => "homestead",
+            Hardfork::Istanbul => "istanbul",
+            Hardfork::Berlin => "berlin",
+            Hardfork::London => "london",
+            Hardfork::Shanghai => "shanghai",
+            Hardfork::Cancun => "cancun",
+            Hardfork::Prague => "prague",
+            Hardfork::Osaka => "osaka",
+            Hardfork::Bpo1 => "bpo1",
+            Hardfork::Bpo2 => "bpo2",
+        }
+    }
+}
+
+impl fmt::Display for Hardfork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl FromStr for Hardfork {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_ascii_lowercase();
+        Hardfork::ALL
+            .into_iter()
+            .find(|fork| fork.name() == lower)
+            .ok_or_else(|| format!("unknown hardfork `{}`", s))
+    }
+}
+
+/// The parts of a transaction that intrinsic gas depends on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TxPayload<'a> {
+    /// Calldata, or initcode for a contract creation.
+    pub data: &'a [u8],
+    pub is_create: bool,
+    pub access_list_addresses: u64,
+    pub access_list_storage_keys: u64,
+    pub authorizations: u64,
+}
+
+/// Intrinsic gas split by where it comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntrinsicGas {
+    pub base: u64,
+    pub calldata: u64,
+    pub create: u64,
+    pub initcode: u64,
+    pub access_list: u64,
+    pub authorizations: u64,
+    /// EIP-7623 calldata floor, from Prague onwards.
+    pub floor: Option<u64>,
+}
+
+impl IntrinsicGas {
+    /// Gas charged up front, before execution.
+    pub fn total(&self) -> u64 {
+        self.base + self.calldata + self.create + self.initcode + self.access_list + self.authorizations
+    }
+
+    /// The lowest gas limit the transaction can be sent with.
+    pub fn min_gas_limit(&self) -> u64 {
+        self.total().max(self.floor.unwrap_or(0))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntrinsicGasError {
+    AccessListUnsupported(Hardfork),
+    AuthorizationsUnsupported(Hardfork),
+    AuthorizationsOnCreate,
+    InitcodeTooLarge { size: usize },
+}
+
+impl fmt::Display for IntrinsicGasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntrinsicGasError::AccessListUnsupported(fork) => {
+                write!(f, "access lists need Berlin or later, not {}", fork)
+            }
+            IntrinsicGasError::AuthorizationsUnsupported(fork) => {
+                write!(f, "authorization lists need Prague or later, not {}", fork)
+            }
+            IntrinsicGasError::AuthorizationsOnCreate => {
+                write!(f, "a set-code transaction cannot create a contract")
+            }
+            IntrinsicGasError::InitcodeTooLarge { size } => write!(
+                f,
+                "initcode is {} bytes, above the {} byte limit of EIP-3860",
+                size, MAX_INITCODE_SIZE
+            ),
+        }
+    }
+}
+
+impl std::error::Error for IntrinsicGasError {}
+
+/// Counts zero and non-zero bytes of `data`.
+pub fn count_bytes(data: &[u8]) -> (u64, u64) {
+    let zeros = data.iter().filter(|b| **b == 0).count() as u64;
+    (zeros, data.len() as u64 - zeros)
+}
+
+/// Computes the gas a transaction pays before execution under the rules of `fork`.
+pub fn intrinsic_gas(payload: &TxPayload, fork: Hardfork) -> Result<IntrinsicGas, IntrinsicGasError> {
+    let has_access_list = payload.access_list_addresses > 0 || payload.access_list_storage_keys > 0;
+    if has_access_list && fork < Hardfork::Berlin {
+        return Err(IntrinsicGasError::AccessListUnsupported(fork));
+    }
+    if payload.authorizations > 0 {
+        if fork < Hardfork::Prague {
+            return Err(IntrinsicGasError::AuthorizationsUnsupported(fork));
+        }
+        if payload.is_create {
+            return Err(IntrinsicGasError::AuthorizationsOnCreate);
+        }
+    }
+    if payload.is_create && fork >= Hardfork::Shanghai && payload.data.len() > MAX_INITCODE_SIZE {
+        return Err(IntrinsicGasError::InitcodeTooLarge { size: payload.data.len() });
+    }
+
+    let (zeros, non_zeros) = count_bytes(payload.data);
+    let non_zero_gas = if fork >= Hardfork::Istanbul {
+        TX_DATA_NON_ZERO_GAS
+    } else {
+        TX_DATA_NON_ZERO_GAS_FRONTIER
+    };
+
+    // Frontier charged creations like ordinary calls; Homestead added the surcharge.
+    let create = if payload.is_create && fork >= Hardfork::Homestead {
+        TX_CREATE_GAS
+    } else {
+        0
+    };
+    let initcode = if payload.is_create && fork >= Hardfork::Shanghai {
+        INITCODE_WORD_GAS * (payload.data.len() as u64).div_ceil(32)
+    } else {
+        0
+    };
+
+    let floor = (fork >= Hardfork::Prague).then(|| {
+        let tokens = zeros + non_zeros * TOKENS_PER_NON_ZERO_BYTE;
+        TX_BASE_GAS + tokens * TOTAL_COST_FLOOR_PER_TOKEN
+    });
+
+    Ok(IntrinsicGas {
+        base: TX_BASE_GAS,
+        calldata: zeros * TX_DATA_ZERO_GAS + non_zeros * non_zero_gas,
+        create,
+        initcode,
+        access_list: payload.access_list_addresses * ACCESS_LIST_ADDRESS_GAS
+            + payload.access_list_storage_keys * ACCESS_LIST_STORAGE_KEY_GAS,
+        authorizations: payload.authorizations * PER_EMPTY_ACCOUNT_GAS,
+        floor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(data: &[u8]) -> TxPayload<'_> {
+        TxPayload { data, ..TxPayload::default() }
+    }
+
+    fn create(initcode: &[u8]) -> TxPayload<'_> {
+        TxPayload { data: initcode, is_create: true, ..TxPayload::default() }
+    }
+
+    /// `transfer(0x1111..11, 1)`: 43 zero bytes and 25 non-zero ones.
+    fn erc20_transfer() -> Vec<u8> {
+        let mut data = hex::decode("a9059cbb").unwrap();
+        data.extend([0; 12]);
+        data.extend([0x11; 20]);
+        data.extend([0; 31]);
+        data.push(1);
+        data
+    }
+
+    #[test]
+    fn plain_transfer_costs_the_base_gas() {
+        for fork in Hardfork::ALL {
+            let gas = intrinsic_gas(&call(&[]), fork).unwrap();
+            assert_eq!(gas.total(), TX_BASE_GAS, "{}", fork);
+            assert_eq!(gas.min_gas_limit(), TX_BASE_GAS, "{}", fork);
+        }
+    }
+
+    #[test]
+    fn zero_byte_calldata() {
+        let data = [0u8; 100];
+        for fork in [Hardfork::Frontier, Hardfork::Istanbul, Hardfork::Shanghai, Hardfork::Prague] {
+            assert_eq!(intrinsic_gas(&call(&data), fork).unwrap().total(), 21_400, "{}", fork);
+        }
+        // EIP-7623: 100 tokens at 10 gas each lift the limit above 21 400.
+        let prague = intrinsic_gas(&call(&data), Hardfork::Prague).unwrap();
+        assert_eq!(prague.floor, Some(22_000));
+        assert_eq!(prague.min_gas_limit(), 22_000);
+        assert_eq!(intrinsic_gas(&call(&data), Hardfork::Shanghai).unwrap().floor, None);
+    }
+
+    #[test]
+    fn non_zero_byte_calldata() {
+        let data = [0xffu8; 100];
+        // EIP-2028 cut a non-zero byte from 68 to 16 gas in Istanbul.
+        assert_eq!(intrinsic_gas(&call(&data), Hardfork::Frontier).unwrap().total(), 27_800);
+        assert_eq!(intrinsic_gas(&call(&data), Hardfork::Istanbul).unwrap().total(), 22_600);
+        assert_eq!(intrinsic_gas(&call(&data), Hardfork::Shanghai).unwrap().total(), 22_600);
+        let prague = intrinsic_gas(&call(&data), Hardfork::Prague).unwrap();
+        assert_eq!(prague.total(), 22_600);
+        assert_eq!(prague.floor, Some(25_000));
+        assert_eq!(prague.min_gas_limit(), 25_000);
+    }
+
+    #[test]
+    fn mixed_calldata() {
+        let data = erc20_transfer();
+        assert_eq!(count_bytes(&data), (43, 25));
+        assert_eq!(intrinsic_gas(&call(&data), Hardfork::Frontier).unwrap().total(), 22_872);
+        assert_eq!(intrinsic_gas(&call(&data), Hardfork::Istanbul).unwrap().total(), 21_572);
+        assert_eq!(intrinsic_gas(&call(&data), Hardfork::Shanghai).unwrap().total(), 21_572);
+        let prague = intrinsic_gas(&call(&data), Hardfork::Prague).unwrap();
+        assert_eq!(prague.floor, Some(22_430));
+        assert_eq!(prague.min_gas_limit(), 22_430);
+        assert_eq!(intrinsic_gas(&call(&data), Hardfork::Osaka).unwrap(), prague);
+    }
+
+    #[test]
+    fn contract_creation_and_initcode_words() {
+        let initcode = [0xffu8; 100];
+        assert_eq!(intrinsic_gas(&create(&initcode), Hardfork::Frontier).unwrap().total(), 27_800);
+        assert_eq!(intrinsic_gas(&create(&initcode), Hardfork::Istanbul).unwrap().total(), 54_600);
+        // EIP-3860: 100 bytes are 4 words at 2 gas.
+        let shanghai = intrinsic_gas(&create(&initcode), Hardfork::Shanghai).unwrap();
+        assert_eq!(shanghai.initcode, 8);
+        assert_eq!(shanghai.total(), 54_608);
+        let prague = intrinsic_gas(&create(&initcode), Hardfork::Prague).unwrap();
+        assert_eq!((prague.total(), prague.min_gas_limit()), (54_608, 54_608));
+
+        let largest = vec![0u8; MAX_INITCODE_SIZE];
+        assert_eq!(intrinsic_gas(&create(&largest), Hardfork::Shanghai).unwrap().initcode, 3_072);
+        let too_large = vec![0u8; MAX_INITCODE_SIZE + 1];
+        assert_eq!(
+            intrinsic_gas(&create(&too_large), Hardfork::Shanghai),
+            Err(IntrinsicGasError::InitcodeTooLarge { size: MAX_INITCODE_SIZE + 1 })
+        );
+        assert!(intrinsic_gas(&create(&too_large), Hardfork::London).is_ok());
+    }
+
+    #[test]
+    fn access_list_entries() {
+        let payload = TxPayload {
+            access_list_addresses: 2,
+            access_list_storage_keys: 3,
+            ..TxPayload::default()
+        };
+        let gas = intrinsic_gas(&payload, Hardfork::Berlin).unwrap();
+        assert_eq!(gas.access_list, 2 * 2_400 + 3 * 1_900);
+        assert_eq!(gas.total(), 31_500);
+        assert_eq!(
+            intrinsic_gas(&payload, Hardfork::Istanbul),
+            Err(IntrinsicGasError::AccessListUnsupported(Hardfork::Istanbul))
+        );
+    }
+
+    #[test]
+    fn authorization_entries() {
+        let payload = TxPayload { authorizations: 2, ..TxPayload::default() };
+        let gas = intrinsic_gas(&payload, Hardfork::Prague).unwrap();
+        assert_eq!(gas.authorizations, 50_000);
+        assert_eq!(gas.total(), 71_000);
+        assert_eq!(
+            intrinsic_gas(&payload, Hardfork::Cancun),
+            Err(IntrinsicGasError::AuthorizationsUnsupported(Hardfork::Cancun))
+        );
+        let on_create = TxPayload { is_create: true, ..payload };
+        assert_eq!(intrinsic_gas(&on_create, Hardfork::Prague), Err(IntrinsicGasError::AuthorizationsOnCreate));
+    }
+
+    #[test]
+    fn fork_names_round_trip() {
+        for fork in Hardfork::ALL {
+            assert_eq!(fork.name().parse(), Ok(fork));
+        }
+        assert_eq!("PRAGUE".parse(), Ok(Hardfork::Prague));
+        assert!("paris".parse::<Hardfork>().is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// An on-chain interaction recorded by Hardhat Ignition in `journal.jsonl`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalInteraction {
+    pub future_id: String,
+    /// `None` for contract deployments.
+    pub to: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl JournalInteraction {
+    pub fn is_deployment(&self) -> bool {
+        self.to.is_none()
+    }
+}
+
+#[derive(Debug)]
+pub enum JournalError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for JournalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalError::Io(err) => write!(f, "could not read journal: {}", err),
+            JournalError::Parse { line, message } => write!(f, "journal line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for JournalError {}
+
+impl From<std::io::Error> for JournalError {
+    fn from(err: std::io::Error) -> Self {
+        JournalError::Io(err)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    #[serde(rename = "type")]
+    kind: String,
+    future_id: Option<String>,
+    network_interaction: Option<NetworkInteraction>,
+}
+
+#[derive(Deserialize)]
+struct NetworkInteraction {
+    #[serde(rename = "type")]
+    kind: String,
+    to: Option<String>,
+    data: Option<String>,
+}
+
+/// Collects every on-chain interaction request from an Ignition journal.
+pub fn read_interactions(path: &Path) -> Result<Vec<JournalInteraction>, JournalError> {
+    let contents = fs::read_to_string(path)?;
+    let mut interactions = Vec::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line_no = index + 1;
+
+        let entry: Entry = serde_json::from_str(line).map_err(|e| JournalError::Parse {
+            line: line_no,
+            message: e.to_string(),
+        })?;
+        if entry.kind != "NETWORK_INTERACTION_REQUEST" {
+            continue;
+        }
+        let Some(interaction) = entry.network_interaction else {
+            continue;
+        };
+        if interaction.kind != "ONCHAIN_INTERACTION" {
+            continue;
+        }
+
+        let raw = interaction.data.unwrap_or_default();
+        let data = hex::decode(raw.trim_start_matches("0x")).map_err(|e| JournalError::Parse {
+            line: line_no,
+            message: format!("invalid hex data: {}", e),
+        })?;
+
+        interactions.push(JournalInteraction {
+            future_id: entry.future_id.unwrap_or_default(),
+            to: interaction.to,
+            data,
+        });
+    }
+
+    Ok(interactions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intrinsic::{intrinsic_gas, Hardfork, TxPayload};
+
+    /// The Sepolia deployment journal of the `Todo` Ignition project.
+    fn todo_journal() -> Vec<JournalInteraction> {
+        read_interactions(&Path::new(env!("CARGO_MANIFEST_DIR")).join("data").join("journal.jsonl")).unwrap()
+    }
+
+    fn write_journal(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("gas_journal_{}_{}.jsonl", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn finds_the_deployment_in_a_journal() {
+        let interactions = todo_journal();
+        assert_eq!(interactions.len(), 1);
+
+        let deployment = &interactions[0];
+        assert_eq!(deployment.future_id, "TodoModule#TodoContract");
+        assert!(deployment.is_deployment());
+        assert_eq!(deployment.data.len(), 3942);
+        assert_eq!(deployment.data[..4], [0x60, 0x80, 0x60, 0x40]);
+    }
+
+    #[test]
+    fn deployment_intrinsic_gas() {
+        let deployment = &todo_journal()[0];
+        let payload = TxPayload { data: &deployment.data, is_create: true, ..TxPayload::default() };
+
+        // 38 zero bytes and 3904 non-zero ones, 124 words of initcode.
+        assert_eq!(intrinsic_gas(&payload, Hardfork::Istanbul).unwrap().total(), 115_616);
+        let prague = intrinsic_gas(&payload, Hardfork::Prague).unwrap();
+        assert_eq!(prague.total(), 115_864);
+        assert_eq!(prague.floor, Some(177_540));
+        assert_eq!(prague.min_gas_limit(), 177_540);
+    }
+
+    #[test]
+    fn keeps_calls_and_skips_other_entries() {
+        let path = write_journal(
+            "calls",
+            concat!(
+                "{\"type\":\"DEPLOYMENT_INITIALIZE\"}\n",
+                "\n",
+                "{\"type\":\"NETWORK_INTERACTION_REQUEST\",\"futureId\":\"M#call\",",
+                "\"networkInteraction\":{\"type\":\"ONCHAIN_INTERACTION\",\"to\":\"0x42\",\"data\":\"0xa9059cbb\"}}\n",
+                "{\"type\":\"NETWORK_INTERACTION_REQUEST\",\"futureId\":\"M#read\",",
+                "\"networkInteraction\":{\"type\":\"STATIC_CALL\",\"to\":\"0x42\",\"data\":\"0x\"}}\n",
+            ),
+        );
+        let interactions = read_interactions(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            interactions,
+            [JournalInteraction {
+                future_id: String::from("M#call"),
+                to: Some(String::from("0x42")),
+                data: vec![0xa9, 0x05, 0x9c, 0xbb],
+            }]
+        );
+        assert!(!interactions[0].is_deployment());
+    }
+
+    #[test]
+    fn reports_the_malformed_line() {
+        let path = write_journal("malformed", "{\"type\":\"DEPLOYMENT_INITIALIZE\"}\n{\"type\":\n");
+        let err = read_interactions(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(err, JournalError::Parse { line: 2, .. }), "{}", err);
+
+        let path = write_journal(
+            "bad_hex",
+            concat!(
+                "{\"type\":\"NETWORK_INTERACTION_REQUEST\",",
+                "\"networkInteraction\":{\"type\":\"ONCHAIN_INTERACTION\",\"data\":\"0xzz\"}}\n",
+            ),
+        );
+        let err = read_interactions(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(&err, JournalError::Parse { line: 1, message } if message.starts_with("invalid hex data")));
+
+        assert!(matches!(read_interactions(Path::new("no/such/journal.jsonl")), Err(JournalError::Io(_))));
+    }
+}
@@ -0,0 +1,309 @@
+//! L1 data fee charged by OP-stack rollups.
+//!
+//! Besides L2 execution gas, every transaction pays for posting its signed
+//! bytes to L1. The formula changed twice: Bedrock priced calldata gas with a
+//! fixed overhead and scalar, Ecotone mixed in the blob base fee, and Fjord
+//! replaced calldata gas with an estimate of the FastLZ-compressed size.
+
+use std::fmt;
+
+use ethereum_types::U256;
+
+use crate::intrinsic::{count_bytes, TX_DATA_NON_ZERO_GAS, TX_DATA_ZERO_GAS};
+use crate::units::Wei;
+
+/// Fjord size regression: `intercept + fastlz_coef * fastlz_size`, scaled by 1e6.
+pub const FJORD_COST_INTERCEPT: i64 = -42_585_600;
+pub const FJORD_FASTLZ_COEF: u64 = 836_500;
+/// Fjord never estimates a transaction below this many bytes.
+pub const FJORD_MIN_TRANSACTION_SIZE: u64 = 100;
+
+/// Scalars are fixed-point with 6 decimals.
+const SCALAR_DECIMALS: u64 = 1_000_000;
+
+/// The L1 cost function in force, with the scalars the system config sets.
+/// `Bedrock` is the function as Regolith left it, without the pre-Regolith
+/// 68-byte signature allowance, since OP Mainnet started on Regolith.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum L1FeeModel {
+    Bedrock { overhead: u64, scalar: u64 },
+    Ecotone { base_fee_scalar: u32, blob_base_fee_scalar: u32 },
+    Fjord { base_fee_scalar: u32, blob_base_fee_scalar: u32 },
+}
+
+impl L1FeeModel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            L1FeeModel::Bedrock { .. } => "bedrock",
+            L1FeeModel::Ecotone { .. } => "ecotone",
+            L1FeeModel::Fjord { .. } => "fjord",
+        }
+    }
+}
+
+impl fmt::Display for L1FeeModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// L1 prices the rollup reads from the L1 block attributes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct L1Prices {
+    pub base_fee: Wei,
+    /// Only used from Ecotone onwards.
+    pub blob_base_fee: Wei,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct L1DataFee {
+    pub model: L1FeeModel,
+    /// Calldata gas of the signed transaction: 4 per zero byte, 16 per other byte.
+    pub data_gas: u64,
+    /// Size in bytes the fee is based on: calldata gas / 16 for Ecotone, the
+    /// regression estimate for Fjord. Bedrock prices gas, not bytes.
+    pub estimated_size: Option<u64>,
+    pub fee: Wei,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct L1FeeOverflow;
+
+impl fmt::Display for L1FeeOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "L1 data fee does not fit in 256 bits")
+    }
+}
+
+impl std::error::Error for L1FeeOverflow {}
+
+/// L1 data fee of a signed, RLP-encoded transaction.
+pub fn l1_data_fee(signed_tx: &[u8], model: L1FeeModel, prices: &L1Prices) -> Result<L1DataFee, L1FeeOverflow> {
+    let (zeros, non_zeros) = count_bytes(signed_tx);
+    let data_gas = zeros * TX_DATA_ZERO_GAS + non_zeros * TX_DATA_NON_ZERO_GAS;
+    let decimals = U256::from(SCALAR_DECIMALS);
+
+    let (estimated_size, fee) = match model {
+        L1FeeModel::Bedrock { overhead, scalar } => {
+            let fee = U256::from(data_gas + overhead)
+                .checked_mul(prices.base_fee.wei())
+                .and_then(|fee| fee.checked_mul(U256::from(scalar)))
+                .ok_or(L1FeeOverflow)?;
+            (None, fee / decimals)
+        }
+        L1FeeModel::Ecotone { base_fee_scalar, blob_base_fee_scalar } => {
+            let price = weighted_gas_price(prices, base_fee_scalar, blob_base_fee_scalar)?;
+            let fee = U256::from(data_gas).checked_mul(price).ok_or(L1FeeOverflow)?;
+            (Some(data_gas / TX_DATA_NON_ZERO_GAS), fee / (decimals * U256::from(TX_DATA_NON_ZERO_GAS)))
+        }
+        L1FeeModel::Fjord { base_fee_scalar, blob_base_fee_scalar } => {
+            let price = weighted_gas_price(prices, base_fee_scalar, blob_base_fee_scalar)?;
+            let scaled_size = fjord_scaled_size(flz_compress_len(signed_tx));
+            let fee = U256::from(scaled_size).checked_mul(price).ok_or(L1FeeOverflow)?;
+            (Some(scaled_size / SCALAR_DECIMALS), fee / (decimals * decimals))
+        }
+    };
+
+    Ok(L1DataFee {
+        model,
+        data_gas,
+        estimated_size,
+        fee: Wei::from_wei(fee),
+    })
+}
+
+/// `16 * base_fee_scalar * l1_base_fee + blob_base_fee_scalar * l1_blob_base_fee`,
+/// still scaled by 1e6.
+fn weighted_gas_price(prices: &L1Prices, base_fee_scalar: u32, blob_base_fee_scalar: u32) -> Result<U256, L1FeeOverflow> {
+    let base = prices
+        .base_fee
+        .wei()
+        .checked_mul(U256::from(u64::from(base_fee_scalar) * TX_DATA_NON_ZERO_GAS));
+    let blob = prices.blob_base_fee.wei().checked_mul(U256::from(blob_base_fee_scalar));
+    base.zip(blob)
+        .and_then(|(base, blob)| base.checked_add(blob))
+        .ok_or(L1FeeOverflow)
+}
+
+/// Fjord's linear estimate of the compressed size, scaled by 1e6.
+fn fjord_scaled_size(fastlz_size: u32) -> u64 {
+    let estimate = FJORD_COST_INTERCEPT + (FJORD_FASTLZ_COEF * u64::from(fastlz_size)) as i64;
+    (estimate.max(0) as u64).max(FJORD_MIN_TRANSACTION_SIZE * SCALAR_DECIMALS)
+}
+
+/// Length of `data` after FastLZ level-1 compression, computed the way the
+/// rollup node does (a port of op-geth's `FlzCompressLen`).
+pub fn flz_compress_len(data: &[u8]) -> u32 {
+    let len = data.len() as u32;
+    let mut n = 0u32;
+    let mut table = vec![0u32; 8192];
+
+    let u24 = |i: u32| {
+        let i = i as usize;
+        u32::from(data[i]) | (u32::from(data[i + 1]) << 8) | (u32::from(data[i + 2]) << 16)
+    };
+    let hash = |v: u32| (2_654_435_769u32.wrapping_mul(v) >> 19) & 0x1fff;
+    let literals = |n: &mut u32, r: u32| {
+        let rest = r % 0x20;
+        *n += 0x21 * (r / 0x20);
+        if rest != 0 {
+            *n += rest + 1;
+        }
+    };
+
+    let mut anchor = 0u32;
+    let ip_limit = len.saturating_sub(13);
+    let mut ip = anchor + 2;
+    while ip < ip_limit {
+        let mut reference;
+        loop {
+            let seq = u24(ip);
+            let h = hash(seq) as usize;
+            reference = table[h];
+            table[h] = ip;
+            let distance = ip - reference;
+            if ip >= ip_limit {
+                break;
+            }
+            ip += 1;
+            if distance <= 0x1fff && seq == u24(reference) {
+                break;
+            }
+        }
+        if ip >= ip_limit {
+            break;
+        }
+
+        ip -= 1;
+        if ip > anchor {
+            literals(&mut n, ip - anchor);
+        }
+
+        // Match length, counted the same (slightly generous) way as the reference.
+        let (p, q, end) = (reference + 3, ip + 3, ip_limit + 9);
+        let mut l = 0;
+        let mut e = end - q;
+        while l < e {
+            if data[(p + l) as usize] != data[(q + l) as usize] {
+                e = 0;
+            }
+            l += 1;
+        }
+
+        let m = l - 1;
+        n += 3 * (m / 262);
+        n += if m % 262 >= 6 { 3 } else { 2 };
+
+        // Hash the two positions after the match so later matches can find them.
+        ip += l;
+        for _ in 0..2 {
+            table[hash(u24(ip)) as usize] = ip;
+            ip += 1;
+        }
+        anchor = ip;
+    }
+    literals(&mut n, len - anchor);
+    n
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// op-geth's `emptyTx`: an unsigned legacy transaction to 0x095e...2d87, 30 non-zero bytes.
+    const EMPTY_TX: &str = "dd80808094095e7baea6a6c7c4c2dfeb977efac326af552d878080808080";
+    /// An OP Mainnet contract call, from op-geth's `TestFlzCompressLen`.
+    const CONTRACT_CALL: &str = include_str!("../data/l1_fee/contract_call.hex");
+    /// Base transaction 0x5dadeb52...0769.
+    const BASE_TX: &str = include_str!("../data/l1_fee/base_0x5dadeb52.hex");
+
+    fn tx(hex: &str) -> Vec<u8> {
+        hex::decode(hex.trim()).unwrap()
+    }
+
+    fn prices(base_fee: u64, blob_base_fee: u64) -> L1Prices {
+        L1Prices {
+            base_fee: Wei::from_wei(U256::from(base_fee)),
+            blob_base_fee: Wei::from_wei(U256::from(blob_base_fee)),
+        }
+    }
+
+    fn fee(signed_tx: &[u8], model: L1FeeModel, prices: &L1Prices) -> u64 {
+        l1_data_fee(signed_tx, model, prices).unwrap().fee.wei().as_u64()
+    }
+
+    #[test]
+    fn flz_compress_len_matches_the_reference() {
+        // op-geth's TestFlzCompressLen and revm's test_flz_compress_len.
+        assert_eq!(flz_compress_len(&[]), 0);
+        assert_eq!(flz_compress_len(&[1]), 2);
+        assert_eq!(flz_compress_len(&[0; 1000]), 21);
+        assert_eq!(flz_compress_len(&[42; 1000]), 21);
+        assert_eq!(flz_compress_len(&tx("facade")), 4);
+        assert_eq!(flz_compress_len(&tx(EMPTY_TX)), 31);
+        assert_eq!(flz_compress_len(&tx(CONTRACT_CALL)), 202);
+        assert_eq!(flz_compress_len(&tx(BASE_TX)), 471);
+
+        // Without repeats every byte is a literal, so the length only grows.
+        let bytes: Vec<u8> = (0..=255).collect();
+        let lengths: Vec<u32> = (1..=bytes.len()).map(|n| flz_compress_len(&bytes[..n])).collect();
+        assert!(lengths.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn op_geth_cost_functions_on_the_empty_tx() {
+        // rollup_cost_test.go: L1 base fee 1 gwei, overhead 50, scalar 7, blob
+        // base fee 0.01 gwei, base fee scalar 2 and blob base fee scalar 3.
+        let prices = prices(1_000_000_000, 10_000_000);
+        let empty_tx = tx(EMPTY_TX);
+
+        let bedrock = L1FeeModel::Bedrock { overhead: 50, scalar: 7_000_000 };
+        let bedrock = l1_data_fee(&empty_tx, bedrock, &prices).unwrap();
+        assert_eq!(bedrock.data_gas, 480);
+        assert_eq!(bedrock.fee.wei(), U256::from(3_710_000_000_000u64));
+
+        let ecotone = L1FeeModel::Ecotone { base_fee_scalar: 2, blob_base_fee_scalar: 3 };
+        assert_eq!(fee(&empty_tx, ecotone, &prices), 960_900);
+
+        // FastLZ shrinks nothing here, so Fjord charges for its 100-byte minimum.
+        let fjord = L1FeeModel::Fjord { base_fee_scalar: 2, blob_base_fee_scalar: 3 };
+        let fjord = l1_data_fee(&empty_tx, fjord, &prices).unwrap();
+        assert_eq!(fjord.estimated_size, Some(FJORD_MIN_TRANSACTION_SIZE));
+        assert_eq!(fjord.fee.wei(), U256::from(3_203_000));
+    }
+
+    #[test]
+    fn revm_cost_functions() {
+        // revm's test_calculate_tx_l1_cost{,_ecotone,_fjord}: every price and scalar is 1000.
+        let prices = prices(1_000, 1_000);
+        let bedrock = L1FeeModel::Bedrock { overhead: 1_000, scalar: 1_000 };
+        let ecotone = L1FeeModel::Ecotone { base_fee_scalar: 1_000, blob_base_fee_scalar: 1_000 };
+        let fjord = L1FeeModel::Fjord { base_fee_scalar: 1_000, blob_base_fee_scalar: 1_000 };
+
+        assert_eq!(fee(&tx("facade"), bedrock, &prices), 1_048);
+        assert_eq!(fee(&tx("facade"), ecotone, &prices), 51);
+        assert_eq!(fee(&tx("facade"), fjord, &prices), 1_700);
+        // 836500 * 202 - 42585600 = 126387400 scaled bytes.
+        let call = l1_data_fee(&tx(CONTRACT_CALL), fjord, &prices).unwrap();
+        assert_eq!(call.estimated_size, Some(126));
+        assert_eq!(call.fee.wei(), U256::from(2_148));
+    }
+
+    #[test]
+    fn fjord_size_estimate_has_a_floor() {
+        // 836500 * 170 - 42585600 is just under 100 bytes, 171 just over.
+        for fastlz_size in [0, 100, 150, 170] {
+            assert_eq!(fjord_scaled_size(fastlz_size), 100_000_000, "{}", fastlz_size);
+        }
+        assert_eq!(fjord_scaled_size(171), 100_455_900);
+        assert_eq!(fjord_scaled_size(200), 124_714_400);
+
+        // op-geth's TestNewL1CostFuncFjord: a FastLZ size of 235 at
+        // l1BaseFee 2e6, blob base fee 3e6, scalars 20 and 15.
+        let price = weighted_gas_price(&prices(2_000_000, 3_000_000), 20, 15).unwrap();
+        let scaled_size = fjord_scaled_size(235);
+        assert_eq!(scaled_size * 16 / SCALAR_DECIMALS, 2_463);
+        let fee = U256::from(scaled_size) * price / U256::from(SCALAR_DECIMALS * SCALAR_DECIMALS);
+        assert_eq!(fee, U256::from(105_484));
+    }
+}
@@ -0,0 +1,12 @@
+pub mod abi;
+pub mod base_fee;
+pub mod blob;
+pub mod evm;
+pub mod fee;
+pub mod fee_history;
+pub mod intrinsic;
+pub mod journal;
+pub mod l1_fee;
+pub mod sstore;
+pub mod tx_type;
+pub mod units;
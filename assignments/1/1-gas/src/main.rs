@@ -1,14 +1,796 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use ethereum_types::U256;
+use gas::abi::encode_call;
+use gas::base_fee::{load_series, simulate};
+use gas::blob::{blob_cost, excess_blob_gas, BlobSchedule, ParentBlobHeader};
+use gas::evm::{opcode, Address, BlockEnv, Evm, ExecutionReport, Outcome, State, Transaction, TX_GAS_LIMIT_CAP};
+use gas::fee::{compute_fee, FeeParams};
+use gas::fee_history::{estimate_fees, fetch_fee_history, load_fee_history};
+use gas::intrinsic::{
+    count_bytes, intrinsic_gas, Hardfork, TxPayload, ACCESS_LIST_ADDRESS_GAS, ACCESS_LIST_STORAGE_KEY_GAS, TX_BASE_GAS,
+};
+use gas::journal::read_interactions;
+use gas::l1_fee::{l1_data_fee, L1FeeModel, L1Prices};
+use gas::sstore::{SstoreSchedule, StorageSlot};
+use gas::tx_type::{price_call, CallProfile, Market, TxType};
+use gas::units::{Ether, Gwei, Wei};
+
 fn main() {
-    let work = 1690;
-    let price_per_work = 20;
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        None => run_fee(&["21000", "20 gwei", "30 gwei", "2 gwei"]),
+        Some("fee") => run_fee(&args[1..]),
+        Some("simulate") => run_simulate(&args[1..]),
+        Some("estimate") => run_estimate(args[1..].to_vec()),
+        Some("intrinsic") => run_intrinsic(args[1..].to_vec()),
+        Some("blob") => run_blob(args[1..].to_vec()),
+        Some("sstore") => run_sstore(args[1..].to_vec()),
+        Some("compare") => run_compare(args[1..].to_vec()),
+        Some("l1-fee") => run_l1_fee(args[1..].to_vec()),
+        Some("evm") => run_evm(args[1..].to_vec()),
+        Some("abi-encode") => run_abi_encode(&args[1..]),
+        Some(other) => Err(format!("unknown command `{}`", other)),
+    };
+
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        eprintln!();
+        print_usage();
+        process::exit(1);
+    }
+}
+
+fn print_usage() {
+    eprintln!("Usage:");
+    eprintln!("  gas fee <gas_used> <base_fee> <max_fee> <max_priority_fee>");
+    eprintln!("  gas simulate <parent_base_fee> <blocks.csv|blocks.json>");
+    eprintln!("  gas estimate <fee_history.json|http://rpc-url> [--blocks <n>]");
+    eprintln!("  gas intrinsic <0xdata|journal.jsonl> [--create] [--fork <name>]");
+    eprintln!("                [--addresses <n>] [--storage-keys <n>] [--authorizations <n>]");
+    eprintln!("  gas blob <blobs> [--fork <name>] [--excess-blob-gas <n>] [--blob-gas-used <n>]");
+    eprintln!("           [--base-fee <amount>] [--gas-used <n>]");
+    eprintln!("  gas sstore <original> <value>... [--fork <name>] [--warm] [--gas-used <n>]");
+    eprintln!("  gas compare <0xcalldata> <execution_gas> [--slots <n>] [--external-addresses <n>]");
+    eprintln!("              [--external-slots <n>] [--written-slots <n>] [--authorizations <n>]");
+    eprintln!("              [--fork <name>] [--base-fee <amount>] [--priority-fee <amount>] [--max-fee <amount>]");
+    eprintln!("              [--gas-price <amount>]");
+    eprintln!("  gas l1-fee <0xsigned_tx> [--l1-base-fee <amount>] [--blob-base-fee <amount>]");
+    eprintln!("             [--overhead <n>] [--scalar <n>] [--base-fee-scalar <n>] [--blob-base-fee-scalar <n>]");
+    eprintln!("             [--gas-used <n>] [--l2-gas-price <amount>]");
+    eprintln!("  gas evm <artifact.json|0xruntime_code> [--deploy-args <0xdata>] [--call <0xdata>]...");
+    eprintln!("          [--from <address>] [--value <amount>] [--gas-limit <n>] [--timestamp <n>]");
+    eprintln!("  gas abi-encode <signature> [args...]");
+    eprintln!();
+    eprintln!("Amounts are in wei unless a unit is given, e.g. \"1.5 gwei\" or \"0.01 ether\".");
+}
+
+fn run_fee<S: AsRef<str>>(args: &[S]) -> Result<(), String> {
+    if args.len() != 4 {
+        return Err(format!("`fee` expects 4 arguments, got {}", args.len()));
+    }
+
+    let params = FeeParams {
+        gas_used: parse_arg(args[0].as_ref(), "gas_used")?,
+        base_fee_per_gas: parse_wei(args[1].as_ref(), "base_fee")?,
+        max_fee_per_gas: parse_wei(args[2].as_ref(), "max_fee")?,
+        max_priority_fee_per_gas: parse_wei(args[3].as_ref(), "max_priority_fee")?,
+    };
+
+    let fee = compute_fee(params).map_err(|e| e.to_string())?;
+
+    println!();
+    println!("Gas used:              {}", params.gas_used);
+    println!("Effective gas price:   {}", Gwei::from(fee.effective_gas_price));
+    println!("Priority fee per gas:  {}", Gwei::from(fee.priority_fee_per_gas));
+    println!();
+    println!("Total fee:             {}", Ether::from(fee.total_fee));
+    println!("Burnt (base fee):      {}", Ether::from(fee.burnt_fee));
+    println!("Validator tip:         {}", Ether::from(fee.validator_tip));
+    println!("Refund of unused max:  {}", Ether::from(fee.refund));
+    println!();
+    Ok(())
+}
+
+fn run_simulate(args: &[String]) -> Result<(), String> {
+    if args.len() != 2 {
+        return Err(format!("`simulate` expects 2 arguments, got {}", args.len()));
+    }
 
-    let gas_fee = get_gas_fee(work, price_per_work);
+    let parent_base_fee = parse_wei(&args[0], "parent_base_fee")?;
+    let series = load_series(Path::new(&args[1])).map_err(|e| e.to_string())?;
+    let blocks = simulate(parent_base_fee, &series).map_err(|e| e.to_string())?;
 
-    println!("");
-    println!("Your gas fee is: {}", gas_fee);
-    println!("")
+    println!();
+    println!(
+        "{:>5} | {:>12} | {:>12} | {:>8} | {:>22} | {:>22} | {:>7}",
+        "Block", "Gas used", "Gas limit", "Target", "Base fee", "Next base fee", "Change"
+    );
+    println!("{}", "-".repeat(107));
+    for block in &blocks {
+        let (base, next) = (block.base_fee_per_gas.wei(), block.next_base_fee_per_gas.wei());
+        let change = if base.is_zero() {
+            0.0
+        } else {
+            (next.low_u128() as f64 / base.low_u128() as f64 - 1.0) * 100.0
+        };
+        println!(
+            "{:>5} | {:>12} | {:>12} | {:>7.1}% | {:>22} | {:>22} | {:>+6.2}%",
+            block.number,
+            block.usage.gas_used,
+            block.usage.gas_limit,
+            block.target_utilisation(),
+            Gwei::from(block.base_fee_per_gas),
+            Gwei::from(block.next_base_fee_per_gas),
+            change
+        );
+    }
+    println!();
+    Ok(())
 }
 
-fn get_gas_fee(computation: u32, price_per_compute: u32) -> u32 {
-    computation * price_per_compute
-}
\ No newline at end of file
+fn run_estimate(mut args: Vec<String>) -> Result<(), String> {
+    let blocks = take_option(&mut args, "--blocks")?
+        .map(|n| parse_arg(&n, "--blocks"))
+        .transpose()?
+        .unwrap_or(20);
+
+    if args.len() != 1 {
+        return Err(format!("`estimate` expects 1 input, got {}", args.len()));
+    }
+    let input = &args[0];
+
+    if input.starts_with("https://") {
+        return Err(String::from("only plain-HTTP endpoints are supported, e.g. a local node at http://localhost:8545"));
+    }
+    let history = if input.starts_with("http://") {
+        fetch_fee_history(input, blocks)
+    } else {
+        load_fee_history(Path::new(input))
+    }
+    .map_err(|e| e.to_string())?;
+    let estimates = estimate_fees(&history).map_err(|e| e.to_string())?;
+
+    let newest = history.oldest_block + history.gas_used_ratio.len().saturating_sub(1) as u64;
+    println!();
+    println!("Blocks:          {} to {}", history.oldest_block, newest);
+    if let Some(base_fee) = history.next_base_fee() {
+        println!("Next base fee:   {}", Gwei::from(base_fee));
+    }
+    println!();
+    println!("{:>8} | {:>24} | {:>24}", "Speed", "maxPriorityFeePerGas", "maxFeePerGas");
+    println!("{}", "-".repeat(62));
+    for estimate in estimates {
+        println!(
+            "{:>8} | {:>24} | {:>24}",
+            estimate.speed,
+            Gwei::from(estimate.max_priority_fee_per_gas),
+            Gwei::from(estimate.max_fee_per_gas)
+        );
+    }
+    println!();
+    Ok(())
+}
+
+fn run_intrinsic(mut args: Vec<String>) -> Result<(), String> {
+    let is_create = take_flag(&mut args, "--create");
+    let fork = take_option(&mut args, "--fork")?
+        .map(|name| name.parse::<Hardfork>())
+        .transpose()?;
+    let addresses = take_option(&mut args, "--addresses")?
+        .map(|n| parse_arg(&n, "--addresses"))
+        .transpose()?
+        .unwrap_or(0);
+    let storage_keys = take_option(&mut args, "--storage-keys")?
+        .map(|n| parse_arg(&n, "--storage-keys"))
+        .transpose()?
+        .unwrap_or(0);
+    let authorizations = take_option(&mut args, "--authorizations")?
+        .map(|n| parse_arg(&n, "--authorizations"))
+        .transpose()?
+        .unwrap_or(0);
+
+    if args.len() != 1 {
+        return Err(format!("`intrinsic` expects 1 input, got {}", args.len()));
+    }
+    let input = &args[0];
+
+    // Either a single payload given as hex, or every interaction in an Ignition journal.
+    let payloads: Vec<(String, Vec<u8>, bool)> = if input.ends_with(".jsonl") {
+        read_interactions(Path::new(input))
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|tx| {
+                let label = if tx.is_deployment() {
+                    format!("{} (deployment)", tx.future_id)
+                } else {
+                    tx.future_id.clone()
+                };
+                let is_create = tx.is_deployment();
+                (label, tx.data, is_create)
+            })
+            .collect()
+    } else {
+        let data = parse_hex(input)?;
+        vec![(String::from("payload"), data, is_create)]
+    };
+
+    for (label, data, is_create) in &payloads {
+        let payload = TxPayload {
+            data,
+            is_create: *is_create,
+            access_list_addresses: addresses,
+            access_list_storage_keys: storage_keys,
+            authorizations,
+        };
+        let (zeros, non_zeros) = count_bytes(data);
+
+        println!();
+        println!("{}", label);
+        println!("{} bytes of data ({} zero, {} non-zero)", data.len(), zeros, non_zeros);
+
+        match fork {
+            Some(fork) => {
+                let gas = intrinsic_gas(&payload, fork).map_err(|e| e.to_string())?;
+                println!();
+                println!("Hardfork:        {}", fork);
+                println!("Base:            {}", gas.base);
+                println!("Calldata:        {}", gas.calldata);
+                println!("Create:          {}", gas.create);
+                println!("Initcode words:  {}", gas.initcode);
+                println!("Access list:     {}", gas.access_list);
+                println!("Authorizations:  {}", gas.authorizations);
+                println!("Intrinsic gas:   {}", gas.total());
+                if let Some(floor) = gas.floor {
+                    println!("Calldata floor:  {}", floor);
+                }
+                println!("Min gas limit:   {}", gas.min_gas_limit());
+            }
+            None => {
+                println!();
+                println!("{:>10} | {:>13} | {:>14} | {:>13}", "Hardfork", "Intrinsic gas", "Calldata floor", "Min gas limit");
+                println!("{}", "-".repeat(60));
+                for fork in Hardfork::ALL {
+                    match intrinsic_gas(&payload, fork) {
+                        Ok(gas) => println!(
+                            "{:>10} | {:>13} | {:>14} | {:>13}",
+                            fork,
+                            gas.total(),
+                            gas.floor.map(|f| f.to_string()).unwrap_or_else(|| String::from("-")),
+                            gas.min_gas_limit()
+                        ),
+                        Err(err) => println!("{:>10} | {}", fork, err),
+                    }
+                }
+            }
+        }
+    }
+    println!();
+    Ok(())
+}
+
+fn run_blob(mut args: Vec<String>) -> Result<(), String> {
+    let fork = take_option(&mut args, "--fork")?
+        .map(|name| name.parse::<Hardfork>())
+        .transpose()?
+        .unwrap_or(Hardfork::LATEST);
+    let parent = ParentBlobHeader {
+        excess_blob_gas: take_option(&mut args, "--excess-blob-gas")?
+            .map(|n| parse_arg(&n, "--excess-blob-gas"))
+            .transpose()?
+            .unwrap_or(0),
+        blob_gas_used: take_option(&mut args, "--blob-gas-used")?
+            .map(|n| parse_arg(&n, "--blob-gas-used"))
+            .transpose()?
+            .unwrap_or(0),
+        base_fee_per_gas: take_option(&mut args, "--base-fee")?
+            .map(|amount| parse_wei(&amount, "--base-fee"))
+            .transpose()?
+            .unwrap_or(Gwei::new(1).into()),
+    };
+    let gas_used: u64 = take_option(&mut args, "--gas-used")?
+        .map(|n| parse_arg(&n, "--gas-used"))
+        .transpose()?
+        .unwrap_or(21_000);
+
+    if args.len() != 1 {
+        return Err(format!("`blob` expects 1 argument, got {}", args.len()));
+    }
+    let blobs = parse_arg(&args[0], "blobs")?;
+
+    let schedule = BlobSchedule::for_fork(fork).ok_or_else(|| format!("blobs need Cancun or later, not {}", fork))?;
+    let excess = excess_blob_gas(&parent, &schedule).map_err(|e| e.to_string())?;
+    let cost = blob_cost(blobs, excess, &schedule).map_err(|e| e.to_string())?;
+    // The parent base fee stands in for this block's, which also depends on its execution gas.
+    let execution_fee = parent
+        .base_fee_per_gas
+        .checked_mul(gas_used)
+        .ok_or_else(|| String::from("execution fee does not fit in 256 bits"))?;
+    let total = execution_fee
+        .checked_add(cost.blob_fee)
+        .ok_or_else(|| String::from("total fee does not fit in 256 bits"))?;
+
+    println!();
+    println!("Hardfork:          {}", fork);
+    println!(
+        "Blobs per block:   target {}, max {} ({} per transaction)",
+        schedule.target_blobs_per_block, schedule.max_blobs_per_block, schedule.max_blobs_per_tx
+    );
+    println!("Excess blob gas:   {}", excess);
+    println!("Blob base fee:     {}", Gwei::from(cost.blob_base_fee));
+    println!();
+    println!("Blobs:             {}", cost.blobs);
+    println!("Blob gas:          {}", cost.blob_gas);
+    println!("Blob fee:          {}", Ether::from(cost.blob_fee));
+    println!("Execution fee:     {} ({} gas at {})", Ether::from(execution_fee), gas_used, Gwei::from(parent.base_fee_per_gas));
+    println!("Total burnt:       {}", Ether::from(total));
+    println!();
+    Ok(())
+}
+
+fn run_sstore(mut args: Vec<String>) -> Result<(), String> {
+    let fork = take_option(&mut args, "--fork")?
+        .map(|name| name.parse::<Hardfork>())
+        .transpose()?
+        .unwrap_or(Hardfork::LATEST);
+    let warm = take_flag(&mut args, "--warm");
+    let gas_used: Option<u64> = take_option(&mut args, "--gas-used")?
+        .map(|n| parse_arg(&n, "--gas-used"))
+        .transpose()?;
+
+    if args.len() < 2 {
+        return Err(String::from("`sstore` expects the original value and at least one value to write"));
+    }
+    let values = args.iter().map(|value| parse_u256(value)).collect::<Result<Vec<_>, _>>()?;
+
+    let schedule = SstoreSchedule::for_fork(fork);
+    let mut slot = StorageSlot { warm, ..StorageSlot::new(values[0]) };
+    let mut total_gas = 0;
+    let mut total_refund = 0;
+
+    println!();
+    println!("Hardfork: {}", fork);
+    println!();
+    println!("{:>5} | {:>12} | {:>12} | {:>12} | {:>7} | {:>8}", "Write", "Original", "Current", "New", "Gas", "Refund");
+    println!("{}", "-".repeat(71));
+    for (index, &new) in values[1..].iter().enumerate() {
+        let current = slot.current;
+        let cost = slot.write(new, &schedule);
+        total_gas += cost.gas;
+        total_refund += cost.refund;
+        println!(
+            "{:>5} | {:>12} | {:>12} | {:>12} | {:>7} | {:>+8}",
+            index + 1,
+            short_u256(slot.original),
+            short_u256(current),
+            short_u256(new),
+            cost.gas,
+            cost.refund
+        );
+    }
+
+    // Without a real transaction, assume a plain call that only does these writes.
+    let gas_used = gas_used.unwrap_or(TX_BASE_GAS + total_gas);
+    let refund = schedule.capped_refund(gas_used, total_refund);
+    println!();
+    println!("SSTORE gas:        {}", total_gas);
+    println!("Refund earned:     {}", total_refund);
+    println!("Refund cap:        {} (gas used {} / {})", gas_used / schedule.max_refund_quotient, gas_used, schedule.max_refund_quotient);
+    println!("Refund paid:       {}", refund);
+    println!("Gas after refund:  {}", gas_used - refund);
+    println!();
+    Ok(())
+}
+
+fn run_compare(mut args: Vec<String>) -> Result<(), String> {
+    let fork = take_option(&mut args, "--fork")?
+        .map(|name| name.parse::<Hardfork>())
+        .transpose()?
+        .unwrap_or(Hardfork::LATEST);
+    let mut count = |name: &str, default: u64| -> Result<u64, String> {
+        take_option(&mut args, name)?
+            .map(|n| parse_arg(&n, name))
+            .transpose()
+            .map(|n| n.unwrap_or(default))
+    };
+    let target_slots = count("--slots", 0)?;
+    let external_addresses = count("--external-addresses", 0)?;
+    let external_slots = count("--external-slots", 0)?;
+    let written_slots = count("--written-slots", 0)?;
+    let authorizations = count("--authorizations", 1)?;
+
+    let mut amount = |name: &str| -> Result<Option<Wei>, String> {
+        take_option(&mut args, name)?
+            .map(|value| parse_wei(&value, name))
+            .transpose()
+    };
+    let base_fee_per_gas = amount("--base-fee")?.unwrap_or(Gwei::new(1).into());
+    let max_priority_fee_per_gas = amount("--priority-fee")?.unwrap_or(Gwei::new(1).into());
+    let max_fee = amount("--max-fee")?;
+    let gas_price = amount("--gas-price")?;
+
+    if args.len() != 2 {
+        return Err(format!("`compare` expects calldata and execution gas, got {} arguments", args.len()));
+    }
+    let data = parse_hex(&args[0])?;
+    let call = CallProfile {
+        data: &data,
+        execution_gas: parse_arg(&args[1], "execution_gas")?,
+        target_slots,
+        external_addresses,
+        external_slots,
+        written_slots,
+        authorizations,
+    };
+
+    let overflow = || String::from("fee does not fit in 256 bits");
+    // Same defaults as most wallets: pay the tip on top of the base fee, and
+    // let the max fee absorb the base fee doubling.
+    let market = Market {
+        fork,
+        base_fee_per_gas,
+        gas_price: match gas_price {
+            Some(price) => price,
+            None => base_fee_per_gas.checked_add(max_priority_fee_per_gas).ok_or_else(overflow)?,
+        },
+        max_fee_per_gas: match max_fee {
+            Some(max_fee) => max_fee,
+            None => base_fee_per_gas
+                .checked_mul(2u64)
+                .and_then(|fee| fee.checked_add(max_priority_fee_per_gas))
+                .ok_or_else(overflow)?,
+        },
+        max_priority_fee_per_gas,
+    };
+
+    println!();
+    println!("Hardfork:        {}", market.fork);
+    println!("Base fee:        {}", Gwei::from(market.base_fee_per_gas));
+    println!("Gas price:       {}   (types 0 and 1)", Gwei::from(market.gas_price));
+    println!(
+        "Max fee / tip:   {} / {}   (types 2 and 4)",
+        Gwei::from(market.max_fee_per_gas),
+        Gwei::from(market.max_priority_fee_per_gas)
+    );
+
+    let (addresses, keys) = call.access_list();
+    let list_cost = addresses * ACCESS_LIST_ADDRESS_GAS + keys * ACCESS_LIST_STORAGE_KEY_GAS;
+    let savings = call.access_list_savings();
+    println!();
+    println!("Access list:     {} addresses, {} storage keys", addresses, keys);
+    println!("                 costs {} gas up front, saves {} gas of execution", list_cost, savings);
+
+    let rows = [
+        (TxType::Legacy, false),
+        (TxType::AccessList, true),
+        (TxType::DynamicFee, false),
+        (TxType::DynamicFee, true),
+        (TxType::SetCode, true),
+    ];
+    println!();
+    println!(
+        "{:>20} | {:>11} | {:>9} | {:>9} | {:>8} | {:>14} | {:>24}",
+        "Type", "Access list", "Intrinsic", "Execution", "Gas used", "Price per gas", "Total fee"
+    );
+    println!("{}", "-".repeat(115));
+    for (tx_type, with_access_list) in rows {
+        // The second type-2 row only differs from the first when it can carry a list.
+        if with_access_list && tx_type == TxType::DynamicFee && (addresses == 0 || fork < tx_type.introduced_in()) {
+            continue;
+        }
+        match price_call(&call, &market, tx_type, with_access_list) {
+            Ok(cost) => println!(
+                "{:>20} | {:>11} | {:>9} | {:>9} | {:>8} | {:>14} | {:>24}",
+                cost.tx_type,
+                if cost.with_access_list { "yes" } else { "no" },
+                cost.intrinsic_gas,
+                cost.execution_gas,
+                cost.gas_used,
+                Gwei::from(cost.effective_gas_price),
+                Ether::from(cost.total_fee)
+            ),
+            Err(err) => println!("{:>20} | {}", tx_type, err),
+        }
+    }
+
+    println!();
+    println!("Fields that set the fee:");
+    for tx_type in [TxType::Legacy, TxType::AccessList, TxType::DynamicFee, TxType::SetCode] {
+        println!("  {:<20} {}", tx_type, tx_type.fee_fields().join(", "));
+    }
+    println!();
+    Ok(())
+}
+
+/// OP Mainnet system config values, used when no scalar is given.
+const DEFAULT_L1_OVERHEAD: u64 = 188;
+const DEFAULT_L1_SCALAR: u64 = 684_000;
+const DEFAULT_BASE_FEE_SCALAR: u32 = 5_227;
+const DEFAULT_BLOB_BASE_FEE_SCALAR: u32 = 1_014_213;
+
+fn run_l1_fee(mut args: Vec<String>) -> Result<(), String> {
+    let mut amount = |name: &str, default: Wei| -> Result<Wei, String> {
+        take_option(&mut args, name)?
+            .map(|value| parse_wei(&value, name))
+            .transpose()
+            .map(|value| value.unwrap_or(default))
+    };
+    let prices = L1Prices {
+        base_fee: amount("--l1-base-fee", Gwei::new(10).into())?,
+        blob_base_fee: amount("--blob-base-fee", Gwei::new(1).into())?,
+    };
+    let l2_gas_price = amount("--l2-gas-price", Wei::new(1_000_000))?;
+
+    let overhead = take_option(&mut args, "--overhead")?
+        .map(|n| parse_arg(&n, "--overhead"))
+        .transpose()?
+        .unwrap_or(DEFAULT_L1_OVERHEAD);
+    let scalar = take_option(&mut args, "--scalar")?
+        .map(|n| parse_arg(&n, "--scalar"))
+        .transpose()?
+        .unwrap_or(DEFAULT_L1_SCALAR);
+    let base_fee_scalar = take_option(&mut args, "--base-fee-scalar")?
+        .map(|n| parse_arg(&n, "--base-fee-scalar"))
+        .transpose()?
+        .unwrap_or(DEFAULT_BASE_FEE_SCALAR);
+    let blob_base_fee_scalar = take_option(&mut args, "--blob-base-fee-scalar")?
+        .map(|n| parse_arg(&n, "--blob-base-fee-scalar"))
+        .transpose()?
+        .unwrap_or(DEFAULT_BLOB_BASE_FEE_SCALAR);
+    let gas_used: Option<u64> = take_option(&mut args, "--gas-used")?
+        .map(|n| parse_arg(&n, "--gas-used"))
+        .transpose()?;
+
+    if args.len() != 1 {
+        return Err(format!("`l1-fee` expects 1 signed transaction, got {}", args.len()));
+    }
+    let signed_tx = parse_hex(&args[0])?;
+
+    let models = [
+        L1FeeModel::Bedrock { overhead, scalar },
+        L1FeeModel::Ecotone { base_fee_scalar, blob_base_fee_scalar },
+        L1FeeModel::Fjord { base_fee_scalar, blob_base_fee_scalar },
+    ];
+    let overflow = || String::from("fee does not fit in 256 bits");
+    let l2_fee = gas_used
+        .map(|gas_used| l2_gas_price.checked_mul(gas_used).ok_or_else(overflow))
+        .transpose()?;
+
+    println!();
+    println!("Signed tx:       {} bytes", signed_tx.len());
+    println!("L1 base fee:     {}", Gwei::from(prices.base_fee));
+    println!("Blob base fee:   {}", Gwei::from(prices.blob_base_fee));
+    if let (Some(gas_used), Some(l2_fee)) = (gas_used, l2_fee) {
+        println!("L2 execution:    {} ({} gas at {})", Ether::from(l2_fee), gas_used, Gwei::from(l2_gas_price));
+    }
+    println!();
+    println!(
+        "{:>8} | {:>8} | {:>14} | {:>26} | {:>26}",
+        "Model", "Data gas", "Estimated size", "L1 data fee", "Total with L2 execution"
+    );
+    println!("{}", "-".repeat(94));
+    for model in models {
+        let fee = l1_data_fee(&signed_tx, model, &prices).map_err(|e| e.to_string())?;
+        let size = fee.estimated_size.map(|size| format!("{} bytes", size)).unwrap_or_else(|| String::from("-"));
+        let total = match l2_fee {
+            Some(l2_fee) => Ether::from(fee.fee.checked_add(l2_fee).ok_or_else(overflow)?).to_string(),
+            None => String::from("-"),
+        };
+        println!("{:>8} | {:>8} | {:>14} | {:>26} | {:>26}", model, fee.data_gas, size, Ether::from(fee.fee), total);
+    }
+    println!();
+    Ok(())
+}
+
+/// Prints small values in decimal and large ones, like hashes, in shortened hex.
+fn short_u256(value: U256) -> String {
+    if value.bits() <= 32 {
+        value.to_string()
+    } else {
+        let hex = format!("{:x}", value);
+        format!("0x{}..{}", &hex[..4], &hex[hex.len() - 4..])
+    }
+}
+
+/// Address the `evm` command sends transactions from.
+const DEFAULT_SENDER: u64 = 0xa11ce;
+/// Address runtime code is installed at when no artifact is given.
+const DEFAULT_CONTRACT: u64 = 0xc0de;
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Artifact {
+    contract_name: String,
+    bytecode: String,
+}
+
+fn run_evm(mut args: Vec<String>) -> Result<(), String> {
+    let deploy_args = take_option(&mut args, "--deploy-args")?
+        .map(|data| parse_hex(&data))
+        .transpose()?
+        .unwrap_or_default();
+    let mut calls = Vec::new();
+    while let Some(data) = take_option(&mut args, "--call")? {
+        calls.push(parse_hex(&data)?);
+    }
+    let sender = take_option(&mut args, "--from")?
+        .map(|address| parse_address(&address))
+        .transpose()?
+        .unwrap_or_else(|| Address::from_low_u64_be(DEFAULT_SENDER));
+    let value = take_option(&mut args, "--value")?
+        .map(|value| parse_wei(&value, "--value"))
+        .transpose()?
+        .unwrap_or_default()
+        .wei();
+    let gas_limit = take_option(&mut args, "--gas-limit")?
+        .map(|n| parse_arg(&n, "--gas-limit"))
+        .transpose()?
+        .unwrap_or(TX_GAS_LIMIT_CAP);
+    let mut block = BlockEnv::default();
+    if let Some(timestamp) = take_option(&mut args, "--timestamp")? {
+        block.timestamp = parse_arg(&timestamp, "--timestamp")?;
+    }
+
+    if args.len() != 1 {
+        return Err(format!("`evm` expects 1 input, got {}", args.len()));
+    }
+    let input = &args[0];
+
+    let mut state = State::new();
+    // Gas is never paid for, but `--value` needs something to draw from.
+    state.account_mut(&sender).balance = Ether::new(1_000).wei();
+    let mut evm = Evm::new(state, block);
+
+    let contract = if input.ends_with(".json") {
+        let raw = fs::read_to_string(input).map_err(|e| format!("could not read {}: {}", input, e))?;
+        let artifact: Artifact = serde_json::from_str(&raw).map_err(|e| format!("invalid artifact {}: {}", input, e))?;
+        let mut initcode = parse_hex(&artifact.bytecode)?;
+        initcode.extend(deploy_args);
+
+        let deploy = Transaction {
+            caller: sender,
+            to: None,
+            value: U256::zero(),
+            data: initcode,
+            gas_limit,
+            gas_price: evm.block.base_fee,
+        };
+        let report = evm.transact(&deploy).map_err(|e| e.to_string())?;
+        print_report(&format!("Deploy {}", artifact.contract_name), &report);
+        report
+            .created_address
+            .ok_or_else(|| String::from("deployment failed, nothing to call"))?
+    } else {
+        let address = Address::from_low_u64_be(DEFAULT_CONTRACT);
+        evm.state_mut().account_mut(&address).code = parse_hex(input)?;
+        address
+    };
+
+    for (index, data) in calls.into_iter().enumerate() {
+        let tx = Transaction {
+            caller: sender,
+            to: Some(contract),
+            value,
+            data,
+            gas_limit,
+            gas_price: evm.block.base_fee,
+        };
+        let report = evm.transact(&tx).map_err(|e| e.to_string())?;
+        print_report(&format!("Call #{}", index + 1), &report);
+    }
+    Ok(())
+}
+
+fn print_report(title: &str, report: &ExecutionReport) {
+    println!();
+    println!("{}", title);
+    println!("{}", "=".repeat(title.len()));
+    match &report.outcome {
+        Outcome::Success(code) if report.created_address.is_some() => {
+            println!("Result:          success ({} bytes of code deployed)", code.len())
+        }
+        Outcome::Success(output) => println!("Result:          success (0x{})", hex::encode(output)),
+        Outcome::Revert(output) => println!("Result:          reverted ({})", revert_reason(output)),
+        Outcome::Halt(halt) => println!("Result:          halted ({})", halt),
+    }
+    if let Some(address) = report.created_address {
+        println!("Contract:        {:?}", address);
+    }
+    println!("Intrinsic gas:   {}", report.intrinsic_gas);
+    println!("Execution gas:   {}", report.execution_gas);
+    println!("Refund:          {}", report.refund);
+    println!("Gas used:        {}", report.gas_used);
+    println!("Logs emitted:    {}", report.logs.len());
+
+    let mut opcodes: Vec<_> = report.opcodes.iter().collect();
+    opcodes.sort_by(|a, b| b.1.gas.cmp(&a.1.gas).then(a.0.cmp(b.0)));
+
+    println!();
+    println!("{:>14} | {:>7} | {:>9}", "Opcode", "Count", "Gas");
+    println!("{}", "-".repeat(36));
+    for (op, stats) in opcodes {
+        let name = opcode::name(*op).map(String::from).unwrap_or_else(|| format!("0x{:02x}", op));
+        println!("{:>14} | {:>7} | {:>9}", name, stats.count, stats.gas);
+    }
+}
+
+/// Decodes a Solidity `Error(string)` revert, or falls back to hex.
+fn revert_reason(output: &[u8]) -> String {
+    const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+    if output.len() >= 68 && output[..4] == ERROR_SELECTOR {
+        let len = U256::from_big_endian(&output[36..68]);
+        if len <= U256::from(output.len() - 68) {
+            return String::from_utf8_lossy(&output[68..68 + len.as_usize()]).into_owned();
+        }
+    }
+    format!("0x{}", hex::encode(output))
+}
+
+fn run_abi_encode(args: &[String]) -> Result<(), String> {
+    let (signature, values) = args
+        .split_first()
+        .ok_or_else(|| String::from("`abi-encode` expects a signature"))?;
+    let values: Vec<&str> = values.iter().map(String::as_str).collect();
+    let encoded = encode_call(signature, &values).map_err(|e| e.to_string())?;
+    println!("0x{}", hex::encode(encoded));
+    Ok(())
+}
+
+fn parse_wei(value: &str, name: &str) -> Result<Wei, String> {
+    value
+        .parse()
+        .map_err(|e| format!("invalid amount `{}` for {}: {}", value, name, e))
+}
+
+fn parse_u256(value: &str) -> Result<U256, String> {
+    match value.strip_prefix("0x") {
+        Some(digits) => U256::from_str_radix(digits, 16).ok(),
+        None => U256::from_dec_str(value).ok(),
+    }
+    .ok_or_else(|| format!("`{}` is not a 256-bit number", value))
+}
+
+fn parse_hex(value: &str) -> Result<Vec<u8>, String> {
+    hex::decode(value.trim_start_matches("0x")).map_err(|e| format!("invalid hex `{}`: {}", value, e))
+}
+
+fn parse_address(value: &str) -> Result<Address, String> {
+    match parse_hex(value) {
+        Ok(bytes) if bytes.len() == 20 => Ok(Address::from_slice(&bytes)),
+        _ => Err(format!("`{}` is not a 20-byte address", value)),
+    }
+}
+
+/// Removes `name` from `args`, returning whether it was present.
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|arg| arg == name) {
+        Some(index) => {
+            args.remove(index);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Removes `name <value>` from `args`, returning the value.
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, String> {
+    let Some(index) = args.iter().position(|arg| arg == name) else {
+        return Ok(None);
+    };
+    if index + 1 >= args.len() {
+        return Err(format!("{} expects a value", name));
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Ok(Some(value))
+}
+
+fn parse_arg<T: std::str::FromStr>(value: &str, name: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{}` for {}", value, name))
+}
@@ -0,0 +1,262 @@
+//! SSTORE pricing and refunds.
+//!
+//! What a storage write costs depends on the value the slot had when the
+//! transaction started (`original`), its value now (`current`) and the value
+//! being written (`new`). Istanbul (EIP-2200) introduced this net metering,
+//! Berlin (EIP-2929) added the cold access surcharge and London (EIP-3529)
+//! cut the clearing refund and capped refunds at 1/5 of the gas used.
+
+use std::fmt;
+
+use ethereum_types::U256;
+
+use crate::intrinsic::Hardfork;
+
+/// EIP-2200: SSTORE fails outright when no more than this is left.
+pub const SSTORE_STIPEND: u64 = 2_300;
+
+/// SSTORE costs and refunds of one fork.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SstoreSchedule {
+    /// Net metering (EIP-2200) instead of the flat Frontier rules.
+    pub net_metering: bool,
+    /// Charged for a write that leaves the slot clean or already dirty.
+    pub warm_read: u64,
+    /// Writing a non-zero value to a slot that was originally zero.
+    pub set: u64,
+    /// Changing a slot that was originally non-zero.
+    pub reset: u64,
+    /// Surcharge for the first access to a slot in a transaction (EIP-2929).
+    pub cold_sload: u64,
+    /// Refund for clearing a slot that was originally non-zero.
+    pub clears_refund: u64,
+    /// Refunds pay back at most `gas_used / max_refund_quotient`.
+    pub max_refund_quotient: u64,
+}
+
+impl SstoreSchedule {
+    pub fn for_fork(fork: Hardfork) -> Self {
+        match fork {
+            Hardfork::Frontier | Hardfork::Homestead => Self {
+                net_metering: false,
+                warm_read: 0,
+                set: 20_000,
+                reset: 5_000,
+                cold_sload: 0,
+                clears_refund: 15_000,
+                max_refund_quotient: 2,
+            },
+            Hardfork::Istanbul => Self {
+                net_metering: true,
+                warm_read: 800,
+                set: 20_000,
+                reset: 5_000,
+                cold_sload: 0,
+                clears_refund: 15_000,
+                max_refund_quotient: 2,
+            },
+            // The cold surcharge is split off the reset cost, so a cold reset still costs 5000.
+            Hardfork::Berlin => Self {
+                net_metering: true,
+                warm_read: 100,
+                set: 20_000,
+                reset: 2_900,
+                cold_sload: 2_100,
+                clears_refund: 15_000,
+                max_refund_quotient: 2,
+            },
+            _ => Self {
+                net_metering: true,
+                warm_read: 100,
+                set: 20_000,
+                reset: 2_900,
+                cold_sload: 2_100,
+                clears_refund: 4_800,
+                max_refund_quotient: 5,
+            },
+        }
+    }
+
+    /// Refund actually paid back for a transaction that spent `gas_spent`.
+    pub fn capped_refund(&self, gas_spent: u64, refund: i64) -> u64 {
+        (refund.max(0) as u64).min(gas_spent / self.max_refund_quotient)
+    }
+}
+
+/// Charge and refund change of one SSTORE. The refund can be negative when
+/// a write takes back a refund granted earlier in the same transaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SstoreCost {
+    pub gas: u64,
+    pub refund: i64,
+}
+
+impl fmt::Display for SstoreCost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} gas, refund {:+}", self.gas, self.refund)
+    }
+}
+
+/// A storage slot as seen by one transaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageSlot {
+    /// Value at the start of the transaction.
+    pub original: U256,
+    pub current: U256,
+    /// Whether the slot was already accessed in this transaction.
+    pub warm: bool,
+}
+
+impl StorageSlot {
+    /// A slot not touched yet in this transaction.
+    pub fn new(original: U256) -> Self {
+        Self {
+            original,
+            current: original,
+            warm: false,
+        }
+    }
+
+    /// Prices writing `new`, then stores it and marks the slot warm.
+    pub fn write(&mut self, new: U256, schedule: &SstoreSchedule) -> SstoreCost {
+        let mut cost = if schedule.net_metering {
+            net_metered_cost(self.original, self.current, new, schedule)
+        } else {
+            flat_cost(self.current, new, schedule)
+        };
+        if !self.warm {
+            cost.gas += schedule.cold_sload;
+        }
+
+        self.current = new;
+        self.warm = true;
+        cost
+    }
+}
+
+/// Frontier rules: only the current value matters.
+fn flat_cost(current: U256, new: U256, schedule: &SstoreSchedule) -> SstoreCost {
+    let gas = if current.is_zero() && !new.is_zero() {
+        schedule.set
+    } else {
+        schedule.reset
+    };
+    let refund = if !current.is_zero() && new.is_zero() {
+        schedule.clears_refund as i64
+    } else {
+        0
+    };
+    SstoreCost { gas, refund }
+}
+
+fn net_metered_cost(original: U256, current: U256, new: U256, schedule: &SstoreSchedule) -> SstoreCost {
+    if current == new {
+        return SstoreCost {
+            gas: schedule.warm_read,
+            refund: 0,
+        };
+    }
+
+    let clears = schedule.clears_refund as i64;
+    if original == current {
+        if original.is_zero() {
+            return SstoreCost {
+                gas: schedule.set,
+                refund: 0,
+            };
+        }
+        return SstoreCost {
+            gas: schedule.reset,
+            refund: if new.is_zero() { clears } else { 0 },
+        };
+    }
+
+    // The slot is already dirty: only the warm read is charged, and earlier
+    // refunds are adjusted to match the new final value.
+    let mut refund = 0;
+    if !original.is_zero() {
+        if current.is_zero() {
+            refund -= clears;
+        } else if new.is_zero() {
+            refund += clears;
+        }
+    }
+    if original == new {
+        let charged = if original.is_zero() { schedule.set } else { schedule.reset };
+        refund += (charged - schedule.warm_read) as i64;
+    }
+    SstoreCost {
+        gas: schedule.warm_read,
+        refund,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `PUSH1 value PUSH1 0 SSTORE` for every value on a warm slot and
+    /// returns the gas used, pushes included, and the refund.
+    fn run(original: u64, values: &[u64], fork: Hardfork) -> (u64, i64) {
+        let schedule = SstoreSchedule::for_fork(fork);
+        let mut slot = StorageSlot::new(original.into());
+        slot.warm = true;
+        values.iter().fold((0, 0), |(gas, refund), &value| {
+            let cost = slot.write(value.into(), &schedule);
+            (gas + 6 + cost.gas, refund + cost.refund)
+        })
+    }
+
+    #[test]
+    fn london_matches_the_eip_3529_table() {
+        // (code, original value, gas used, refund) from the EIP's test cases.
+        let cases: [(&str, u64, u64, i64); 17] = [
+            ("0x60006000556000600055", 0, 212, 0),
+            ("0x60006000556001600055", 0, 20112, 0),
+            ("0x60016000556000600055", 0, 20112, 19900),
+            ("0x60016000556002600055", 0, 20112, 0),
+            ("0x60016000556001600055", 0, 20112, 0),
+            ("0x60006000556000600055", 1, 3012, 4800),
+            ("0x60006000556001600055", 1, 3012, 2800),
+            ("0x60006000556002600055", 1, 3012, 0),
+            ("0x60026000556000600055", 1, 3012, 4800),
+            ("0x60026000556003600055", 1, 3012, 0),
+            ("0x60026000556001600055", 1, 3012, 2800),
+            ("0x60026000556002600055", 1, 3012, 0),
+            ("0x60016000556000600055", 1, 3012, 4800),
+            ("0x60016000556002600055", 1, 3012, 0),
+            ("0x60016000556001600055", 1, 212, 0),
+            ("0x600160005560006000556001600055", 0, 40118, 19900),
+            ("0x600060005560016000556000600055", 1, 5918, 7600),
+        ];
+        for (code, original, gas, refund) in cases {
+            // Every write is `60 <value> 60 00 55`.
+            let values: Vec<u64> = hex::decode(&code[2..]).unwrap().chunks(5).map(|op| op[1].into()).collect();
+            assert_eq!(run(original, &values, Hardfork::London), (gas, refund), "{} from {}", code, original);
+        }
+    }
+
+    #[test]
+    fn cold_slots_and_earlier_forks() {
+        // Berlin only differs in the clearing refund; a cold slot adds 2100 once.
+        assert_eq!(run(1, &[0, 1, 0], Hardfork::Berlin), (5918, 17800));
+        let mut slot = StorageSlot::new(1.into());
+        let london = SstoreSchedule::for_fork(Hardfork::London);
+        assert_eq!(slot.write(0.into(), &london), SstoreCost { gas: 5000, refund: 4800 });
+        assert_eq!(slot.write(1.into(), &london), SstoreCost { gas: 100, refund: -4800 + 2800 });
+
+        let frontier = SstoreSchedule::for_fork(Hardfork::Frontier);
+        let mut slot = StorageSlot::new(0.into());
+        assert_eq!(slot.write(1.into(), &frontier), SstoreCost { gas: 20000, refund: 0 });
+        assert_eq!(slot.write(0.into(), &frontier), SstoreCost { gas: 5000, refund: 15000 });
+    }
+
+    #[test]
+    fn refunds_are_capped_per_fork() {
+        let london = SstoreSchedule::for_fork(Hardfork::London);
+        assert_eq!(london.capped_refund(50_000, 19_900), 10_000);
+        assert_eq!(london.capped_refund(50_000, 4_800), 4_800);
+        assert_eq!(london.capped_refund(50_000, -100), 0);
+        assert_eq!(SstoreSchedule::for_fork(Hardfork::Berlin).capped_refund(50_000, 30_000), 25_000);
+    }
+}
@@ -0,0 +1,323 @@
+//! What the same call costs when sent as each transaction type.
+//!
+//! Legacy (type 0) and EIP-2930 (type 1) transactions pay a single gas price;
+//! EIP-1559 (type 2) and EIP-7702 (type 4) ones pay the base fee plus a capped
+//! tip. Types 1, 2 and 4 can carry an access list, which is paid for up front
+//! and makes the listed accounts and slots warm from the start.
+
+use std::fmt;
+
+use crate::evm::gas::{COLD_ACCOUNT_ACCESS, COLD_SLOAD, WARM_ACCESS};
+use crate::fee::{compute_fee, FeeError, FeeParams};
+use crate::intrinsic::{intrinsic_gas, Hardfork, IntrinsicGasError, TxPayload};
+use crate::units::Wei;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxType {
+    Legacy,
+    AccessList,
+    DynamicFee,
+    SetCode,
+}
+
+impl TxType {
+    pub fn id(self) -> u8 {
+        match self {
+            TxType::Legacy => 0,
+            TxType::AccessList => 1,
+            TxType::DynamicFee => 2,
+            TxType::SetCode => 4,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TxType::Legacy => "legacy",
+            TxType::AccessList => "EIP-2930",
+            TxType::DynamicFee => "EIP-1559",
+            TxType::SetCode => "EIP-7702",
+        }
+    }
+
+    /// Fields that change what this type pays, besides gas limit and calldata.
+    pub fn fee_fields(self) -> &'static [&'static str] {
+        match self {
+            TxType::Legacy => &["gasPrice"],
+            TxType::AccessList => &["gasPrice", "accessList"],
+            TxType::DynamicFee => &["maxFeePerGas", "maxPriorityFeePerGas", "accessList"],
+            TxType::SetCode => &["maxFeePerGas", "maxPriorityFeePerGas", "accessList", "authorizationList"],
+        }
+    }
+
+    /// First fork that accepts this type.
+    pub fn introduced_in(self) -> Hardfork {
+        match self {
+            TxType::Legacy => Hardfork::Frontier,
+            TxType::AccessList => Hardfork::Berlin,
+            TxType::DynamicFee => Hardfork::London,
+            TxType::SetCode => Hardfork::Prague,
+        }
+    }
+
+    fn has_access_list(self) -> bool {
+        self != TxType::Legacy
+    }
+
+    fn has_dynamic_fee(self) -> bool {
+        matches!(self, TxType::DynamicFee | TxType::SetCode)
+    }
+}
+
+impl fmt::Display for TxType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&format!("type {} ({})", self.id(), self.name()))
+    }
+}
+
+/// The call being priced. `execution_gas` is measured without an access list,
+/// so every account and slot below is paid for cold.
+///
+/// A slot the call reads first saves 2000 gas once listed, since a warm SLOAD
+/// costs 100 instead of 2100. A slot it writes first saves the whole 2100: a
+/// cold SSTORE pays that surcharge, a warm one pays nothing extra.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CallProfile<'a> {
+    pub data: &'a [u8],
+    pub execution_gas: u64,
+    /// Slots of the called contract. The contract itself is already warm, but
+    /// it has to be listed to list its slots.
+    pub target_slots: u64,
+    /// Other contracts the call touches, and the slots read or written there.
+    pub external_addresses: u64,
+    pub external_slots: u64,
+    /// How many of the target and external slots are written before being read.
+    pub written_slots: u64,
+    /// Authorizations a type-4 transaction carries.
+    pub authorizations: u64,
+}
+
+impl CallProfile<'_> {
+    /// Addresses and storage keys in an access list covering the whole call.
+    pub fn access_list(&self) -> (u64, u64) {
+        let target = u64::from(self.target_slots > 0);
+        (target + self.external_addresses, self.target_slots + self.external_slots)
+    }
+
+    /// Execution gas saved because the access list made everything warm.
+    pub fn access_list_savings(&self) -> u64 {
+        let slots = self.target_slots + self.external_slots;
+        let written = self.written_slots.min(slots);
+        self.external_addresses * (COLD_ACCOUNT_ACCESS - WARM_ACCESS)
+            + (slots - written) * (COLD_SLOAD - WARM_ACCESS)
+            + written * COLD_SLOAD
+    }
+}
+
+/// Prices on offer. Legacy types pay `gas_price`, the others offer
+/// `max_fee_per_gas` and `max_priority_fee_per_gas`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Market {
+    /// Fork of the block the transaction is included in.
+    pub fork: Hardfork,
+    /// Ignored before London, where the whole gas price goes to the miner.
+    pub base_fee_per_gas: Wei,
+    pub gas_price: Wei,
+    pub max_fee_per_gas: Wei,
+    pub max_priority_fee_per_gas: Wei,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxCost {
+    pub tx_type: TxType,
+    pub with_access_list: bool,
+    pub intrinsic_gas: u64,
+    pub execution_gas: u64,
+    /// Intrinsic plus execution gas, or the EIP-7623 floor if that is higher.
+    pub gas_used: u64,
+    pub effective_gas_price: Wei,
+    pub total_fee: Wei,
+    pub burnt_fee: Wei,
+    pub validator_tip: Wei,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompareError {
+    Intrinsic(IntrinsicGasError),
+    Fee(FeeError),
+    TypeUnsupported { tx_type: TxType, fork: Hardfork },
+    GasPriceBelowBaseFee { gas_price: Wei, base_fee_per_gas: Wei },
+    Overflow,
+}
+
+impl fmt::Display for CompareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompareError::Intrinsic(err) => write!(f, "{}", err),
+            CompareError::Fee(err) => write!(f, "{}", err),
+            CompareError::TypeUnsupported { tx_type, fork } => {
+                write!(f, "{} transactions need {} or later, not {}", tx_type.name(), tx_type.introduced_in(), fork)
+            }
+            CompareError::GasPriceBelowBaseFee { gas_price, base_fee_per_gas } => write!(
+                f,
+                "gas price ({}) is below the block base fee ({})",
+                gas_price, base_fee_per_gas
+            ),
+            CompareError::Overflow => write!(f, "fee does not fit in 256 bits"),
+        }
+    }
+}
+
+impl std::error::Error for CompareError {}
+
+impl From<IntrinsicGasError> for CompareError {
+    fn from(err: IntrinsicGasError) -> Self {
+        CompareError::Intrinsic(err)
+    }
+}
+
+impl From<FeeError> for CompareError {
+    fn from(err: FeeError) -> Self {
+        CompareError::Fee(err)
+    }
+}
+
+/// Prices `call` as `tx_type` in a block of `market.fork`.
+pub fn price_call(
+    call: &CallProfile,
+    market: &Market,
+    tx_type: TxType,
+    with_access_list: bool,
+) -> Result<TxCost, CompareError> {
+    if market.fork < tx_type.introduced_in() {
+        return Err(CompareError::TypeUnsupported { tx_type, fork: market.fork });
+    }
+    let with_access_list = with_access_list && tx_type.has_access_list() && call.access_list() != (0, 0);
+    let (addresses, keys) = if with_access_list { call.access_list() } else { (0, 0) };
+    let payload = TxPayload {
+        data: call.data,
+        is_create: false,
+        access_list_addresses: addresses,
+        access_list_storage_keys: keys,
+        authorizations: if tx_type == TxType::SetCode { call.authorizations } else { 0 },
+    };
+    let intrinsic = intrinsic_gas(&payload, market.fork)?;
+
+    let execution_gas = if with_access_list {
+        call.execution_gas.saturating_sub(call.access_list_savings())
+    } else {
+        call.execution_gas
+    };
+    let gas_used = (intrinsic.total() + execution_gas).max(intrinsic.floor.unwrap_or(0));
+
+    let (effective_gas_price, total_fee, burnt_fee, validator_tip) = if tx_type.has_dynamic_fee() {
+        let fee = compute_fee(FeeParams {
+            gas_used,
+            base_fee_per_gas: market.base_fee_per_gas,
+            max_fee_per_gas: market.max_fee_per_gas,
+            max_priority_fee_per_gas: market.max_priority_fee_per_gas,
+        })?;
+        (fee.effective_gas_price, fee.total_fee, fee.burnt_fee, fee.validator_tip)
+    } else {
+        // A legacy gas price acts as both max fee and max priority fee.
+        let base_fee_per_gas = if market.fork >= Hardfork::London { market.base_fee_per_gas } else { Wei::zero() };
+        if market.gas_price < base_fee_per_gas {
+            return Err(CompareError::GasPriceBelowBaseFee {
+                gas_price: market.gas_price,
+                base_fee_per_gas,
+            });
+        }
+        let for_gas_used = |price: Wei| price.checked_mul(gas_used).ok_or(CompareError::Overflow);
+        (
+            market.gas_price,
+            for_gas_used(market.gas_price)?,
+            for_gas_used(base_fee_per_gas)?,
+            for_gas_used(market.gas_price.saturating_sub(base_fee_per_gas))?,
+        )
+    };
+
+    Ok(TxCost {
+        tx_type,
+        with_access_list,
+        intrinsic_gas: intrinsic.total(),
+        execution_gas,
+        gas_used,
+        effective_gas_price,
+        total_fee,
+        burnt_fee,
+        validator_tip,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Gwei;
+
+    fn market(fork: Hardfork) -> Market {
+        let gwei = |n: u64| Wei::from(Gwei::new(n));
+        Market {
+            fork,
+            base_fee_per_gas: gwei(10),
+            gas_price: gwei(12),
+            max_fee_per_gas: gwei(30),
+            max_priority_fee_per_gas: gwei(2),
+        }
+    }
+
+    #[test]
+    fn written_slots_save_the_whole_cold_surcharge() {
+        let call = CallProfile {
+            target_slots: 3,
+            external_addresses: 1,
+            external_slots: 2,
+            ..Default::default()
+        };
+        assert_eq!(call.access_list(), (2, 5));
+        assert_eq!(call.access_list_savings(), 2_500 + 5 * 2_000);
+
+        let writes = CallProfile { written_slots: 2, ..call };
+        assert_eq!(writes.access_list_savings(), 2_500 + 3 * 2_000 + 2 * 2_100);
+        let all_written = CallProfile { written_slots: 9, ..call };
+        assert_eq!(all_written.access_list_savings(), 2_500 + 5 * 2_100);
+    }
+
+    #[test]
+    fn access_list_pays_for_its_slots() {
+        let call = CallProfile {
+            execution_gas: 100_000,
+            target_slots: 25,
+            written_slots: 5,
+            ..Default::default()
+        };
+        let cost = price_call(&call, &market(Hardfork::Osaka), TxType::AccessList, true).unwrap();
+        assert_eq!(cost.intrinsic_gas, 21_000 + 2_400 + 25 * 1_900);
+        assert_eq!(cost.execution_gas, 100_000 - 20 * 2_000 - 5 * 2_100);
+        let without = price_call(&call, &market(Hardfork::Osaka), TxType::AccessList, false).unwrap();
+        assert_eq!(without.gas_used - cost.gas_used, 20 * 2_000 + 5 * 2_100 - 2_400 - 25 * 1_900);
+    }
+
+    #[test]
+    fn priced_under_the_market_fork() {
+        let data = [0xff; 100];
+        let call = CallProfile { data: &data, ..Default::default() };
+
+        // EIP-7623 lifts a calldata-heavy call to the floor from Prague on.
+        let cancun = price_call(&call, &market(Hardfork::Cancun), TxType::DynamicFee, false).unwrap();
+        assert_eq!(cancun.gas_used, 21_000 + 100 * 16);
+        let prague = price_call(&call, &market(Hardfork::Prague), TxType::DynamicFee, false).unwrap();
+        assert_eq!(prague.gas_used, 21_000 + 400 * 10);
+
+        // Before London there is no base fee to burn.
+        let frontier = price_call(&call, &market(Hardfork::Frontier), TxType::Legacy, false).unwrap();
+        assert_eq!(frontier.gas_used, 21_000 + 100 * 68);
+        assert_eq!(frontier.burnt_fee, Wei::zero());
+        assert_eq!(frontier.validator_tip, frontier.total_fee);
+
+        assert_eq!(
+            price_call(&call, &market(Hardfork::Berlin), TxType::DynamicFee, false),
+            Err(CompareError::TypeUnsupported { tx_type: TxType::DynamicFee, fork: Hardfork::Berlin })
+        );
+        let set_code = price_call(&call, &market(Hardfork::Cancun), TxType::SetCode, false).unwrap_err();
+        assert_eq!(set_code.to_string(), "EIP-7702 transactions need prague or later, not cancun");
+    }
+}
@@ -0,0 +1,336 @@
+//! Ether denominations backed by 256-bit integers.
+//!
+//! `Wei`, `Gwei` and `Ether` all store an exact amount of wei; they only differ
+//! in the unit they parse and print by default, so converting between them
+//! never loses precision.
+
+use std::fmt;
+use std::str::FromStr;
+
+use ethereum_types::U256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unit {
+    Wei,
+    Gwei,
+    Ether,
+}
+
+impl Unit {
+    /// Number of decimal places between this unit and wei.
+    pub fn decimals(self) -> usize {
+        match self {
+            Unit::Wei => 0,
+            Unit::Gwei => 9,
+            Unit::Ether => 18,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Unit::Wei => "wei",
+            Unit::Gwei => "gwei",
+            Unit::Ether => "ether",
+        }
+    }
+
+    /// Amount of wei in one of this unit.
+    pub fn wei_per_unit(self) -> U256 {
+        U256::exp10(self.decimals())
+    }
+}
+
+impl FromStr for Unit {
+    type Err = UnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "wei" => Ok(Unit::Wei),
+            "gwei" => Ok(Unit::Gwei),
+            "ether" | "eth" => Ok(Unit::Ether),
+            _ => Err(UnitError::UnknownUnit(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnitError {
+    Empty,
+    InvalidNumber(String),
+    UnknownUnit(String),
+    /// More decimal places than the unit can represent in wei.
+    TooPrecise { value: String, unit: Unit },
+    Overflow,
+}
+
+impl fmt::Display for UnitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnitError::Empty => write!(f, "amount is empty"),
+            UnitError::InvalidNumber(value) => write!(f, "`{}` is not a decimal number", value),
+            UnitError::UnknownUnit(unit) => write!(f, "unknown unit `{}`, expected wei, gwei or ether", unit),
+            UnitError::TooPrecise { value, unit: Unit::Wei } => write!(f, "`{}` is not a whole number of wei", value),
+            UnitError::TooPrecise { value, unit } => write!(
+                f,
+                "`{}` has more than {} decimal places, which is below 1 wei",
+                value,
+                unit.decimals()
+            ),
+            UnitError::Overflow => write!(f, "amount does not fit in 256 bits of wei"),
+        }
+    }
+}
+
+impl std::error::Error for UnitError {}
+
+/// Parses `"1.5"`, `"1.5 gwei"` or `"0.0001ether"` into wei. Without a unit
+/// suffix the number is read in `default_unit`.
+pub fn parse_amount(input: &str, default_unit: Unit) -> Result<U256, UnitError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(UnitError::Empty);
+    }
+
+    let split = input.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number = number.trim();
+    let unit = if unit.is_empty() { default_unit } else { unit.trim().parse()? };
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+        return Err(UnitError::InvalidNumber(number.to_string()));
+    }
+
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > unit.decimals() {
+        return Err(UnitError::TooPrecise {
+            value: number.to_string(),
+            unit,
+        });
+    }
+
+    let whole = if whole.is_empty() {
+        U256::zero()
+    } else {
+        U256::from_dec_str(whole).map_err(|_| UnitError::Overflow)?
+    };
+    let fraction = if fraction.is_empty() {
+        U256::zero()
+    } else {
+        U256::from_dec_str(fraction).map_err(|_| UnitError::Overflow)? * U256::exp10(unit.decimals() - fraction.len())
+    };
+
+    whole
+        .checked_mul(unit.wei_per_unit())
+        .and_then(|wei| wei.checked_add(fraction))
+        .ok_or(UnitError::Overflow)
+}
+
+/// Writes `wei` in `unit` with every significant decimal, e.g. `1.5 gwei`.
+pub fn format_amount(wei: U256, unit: Unit) -> String {
+    let (whole, fraction) = wei.div_mod(unit.wei_per_unit());
+    if fraction.is_zero() {
+        return format!("{} {}", whole, unit.name());
+    }
+
+    let fraction = format!("{:0>width$}", fraction.to_string(), width = unit.decimals());
+    format!("{}.{} {}", whole, fraction.trim_end_matches('0'), unit.name())
+}
+
+macro_rules! denomination {
+    ($name:ident, $unit:expr) => {
+        #[doc = concat!("An amount in ", stringify!($name), ", stored exactly in wei.")]
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(U256);
+
+        impl $name {
+            pub const UNIT: Unit = $unit;
+
+            pub fn zero() -> Self {
+                Self(U256::zero())
+            }
+
+            /// `amount` whole units, e.g. `Gwei::new(20)` is 20 gwei.
+            pub fn new(amount: u64) -> Self {
+                Self(U256::from(amount) * Self::UNIT.wei_per_unit())
+            }
+
+            pub fn from_wei(wei: impl Into<U256>) -> Self {
+                Self(wei.into())
+            }
+
+            pub fn wei(self) -> U256 {
+                self.0
+            }
+
+            pub fn is_zero(self) -> bool {
+                self.0.is_zero()
+            }
+
+            pub fn checked_add(self, other: Self) -> Option<Self> {
+                self.0.checked_add(other.0).map(Self)
+            }
+
+            pub fn checked_sub(self, other: Self) -> Option<Self> {
+                self.0.checked_sub(other.0).map(Self)
+            }
+
+            pub fn checked_mul(self, factor: impl Into<U256>) -> Option<Self> {
+                self.0.checked_mul(factor.into()).map(Self)
+            }
+
+            pub fn checked_div(self, divisor: impl Into<U256>) -> Option<Self> {
+                self.0.checked_div(divisor.into()).map(Self)
+            }
+
+            pub fn saturating_sub(self, other: Self) -> Self {
+                Self(self.0.saturating_sub(other.0))
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.pad(&format_amount(self.0, Self::UNIT))
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = UnitError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                parse_amount(s, Self::UNIT).map(Self)
+            }
+        }
+    };
+}
+
+macro_rules! convert {
+    ($from:ident => $($to:ident),+) => {
+        $(
+            impl From<$from> for $to {
+                fn from(amount: $from) -> Self {
+                    Self(amount.0)
+                }
+            }
+        )+
+    };
+}
+
+denomination!(Wei, Unit::Wei);
+denomination!(Gwei, Unit::Gwei);
+denomination!(Ether, Unit::Ether);
+
+convert!(Wei => Gwei, Ether);
+convert!(Gwei => Wei, Ether);
+convert!(Ether => Wei, Gwei);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEI_PER_GWEI: u64 = 1_000_000_000;
+    const WEI_PER_ETHER: u64 = 1_000_000_000_000_000_000;
+
+    #[test]
+    fn parses_the_documented_amounts() {
+        assert_eq!("1.5 gwei".parse::<Wei>(), Ok(Wei::from_wei(1_500_000_000u64)));
+        assert_eq!("0.0001 ether".parse::<Wei>(), Ok(Wei::from_wei(100_000_000_000_000u64)));
+        assert_eq!("0.0001ether".parse::<Wei>(), Ok(Wei::from_wei(100_000_000_000_000u64)));
+        assert_eq!("1.5 GWEI".parse::<Wei>(), Ok(Wei::from_wei(1_500_000_000u64)));
+        assert_eq!("2 ETH".parse::<Gwei>(), Ok(Gwei::new(2_000_000_000)));
+    }
+
+    #[test]
+    fn bare_numbers_use_the_default_unit() {
+        assert_eq!("1.5".parse::<Gwei>(), Ok(Gwei::from_wei(1_500_000_000u64)));
+        assert_eq!(parse_amount(".5", Unit::Gwei), Ok(U256::from(WEI_PER_GWEI / 2)));
+        assert_eq!(parse_amount("5.", Unit::Ether), Ok(U256::from(5 * WEI_PER_ETHER)));
+        assert_eq!(parse_amount(" 42 ", Unit::Wei), Ok(U256::from(42)));
+        assert_eq!(parse_amount("1.000000000", Unit::Gwei), Ok(U256::from(WEI_PER_GWEI)));
+    }
+
+    #[test]
+    fn rejects_malformed_amounts() {
+        assert_eq!(parse_amount("", Unit::Wei), Err(UnitError::Empty));
+        assert_eq!(parse_amount("   ", Unit::Wei), Err(UnitError::Empty));
+        assert_eq!(parse_amount("1 finney", Unit::Wei), Err(UnitError::UnknownUnit(String::from("finney"))));
+        assert_eq!(parse_amount(".", Unit::Wei), Err(UnitError::InvalidNumber(String::from("."))));
+        assert_eq!(parse_amount("-1 gwei", Unit::Wei), Err(UnitError::InvalidNumber(String::from("-1"))));
+        assert_eq!(parse_amount("1.2.3", Unit::Wei), Err(UnitError::InvalidNumber(String::from("1.2.3"))));
+    }
+
+    #[test]
+    fn rejects_amounts_below_one_wei() {
+        assert_eq!(
+            parse_amount("0.1 wei", Unit::Ether),
+            Err(UnitError::TooPrecise { value: String::from("0.1"), unit: Unit::Wei })
+        );
+        let nineteen_decimals = "0.0000000000000000001";
+        assert_eq!(
+            parse_amount(nineteen_decimals, Unit::Ether),
+            Err(UnitError::TooPrecise { value: String::from(nineteen_decimals), unit: Unit::Ether })
+        );
+        // Trailing zeros are not significant.
+        assert_eq!(parse_amount("0.0000000000000000010", Unit::Ether), Ok(U256::from(1)));
+    }
+
+    #[test]
+    fn rejects_amounts_above_256_bits() {
+        let max = U256::MAX.to_string();
+        assert_eq!(parse_amount(&max, Unit::Wei), Ok(U256::MAX));
+        assert_eq!(parse_amount(&format!("{}0", max), Unit::Wei), Err(UnitError::Overflow));
+        assert_eq!(parse_amount(&format!("{} gwei", max), Unit::Wei), Err(UnitError::Overflow));
+        assert_eq!(parse_amount("1e9 gwei", Unit::Wei), Err(UnitError::UnknownUnit(String::from("e9 gwei"))));
+    }
+
+    #[test]
+    fn formats_every_significant_decimal() {
+        assert_eq!(format_amount(U256::from(1_500_000_000u64), Unit::Gwei), "1.5 gwei");
+        assert_eq!(format_amount(U256::from(100_000_000_000_000u64), Unit::Ether), "0.0001 ether");
+        assert_eq!(format_amount(U256::from(1), Unit::Ether), "0.000000000000000001 ether");
+        assert_eq!(format_amount(U256::zero(), Unit::Gwei), "0 gwei");
+        assert_eq!(Ether::new(3).to_string(), "3 ether");
+        assert_eq!(Gwei::from_wei(1).to_string(), "0.000000001 gwei");
+        assert_eq!(format!("{:>10}", Wei::from_wei(7)), "     7 wei");
+    }
+
+    #[test]
+    fn format_and_parse_round_trip() {
+        let amounts = [U256::zero(), U256::from(1), U256::from(1_500_000_000u64), U256::exp10(18) + 1, U256::MAX];
+        for wei in amounts {
+            for unit in [Unit::Wei, Unit::Gwei, Unit::Ether] {
+                assert_eq!(parse_amount(&format_amount(wei, unit), Unit::Wei), Ok(wei), "{} in {}", wei, unit.name());
+            }
+            let ether = Ether::from_wei(wei);
+            assert_eq!(ether.to_string().parse::<Ether>(), Ok(ether));
+        }
+    }
+
+    #[test]
+    fn conversions_keep_the_exact_amount() {
+        let ether: Ether = "1.000000000000000001".parse().unwrap();
+        let gwei = Gwei::from(ether);
+        let wei = Wei::from(gwei);
+        assert_eq!(wei.wei(), U256::exp10(18) + 1);
+        assert_eq!(gwei.to_string(), "1000000000.000000001 gwei");
+        assert_eq!(Ether::from(wei), ether);
+        assert_eq!(Ether::from(Gwei::new(1)), Ether::from_wei(WEI_PER_GWEI));
+        assert_eq!(Gwei::from(Wei::new(5)).wei(), U256::from(5));
+    }
+
+    #[test]
+    fn checked_arithmetic_reports_overflow() {
+        let max = Wei::from_wei(U256::MAX);
+        assert_eq!(Gwei::new(1).checked_add(Gwei::new(2)), Some(Gwei::new(3)));
+        assert_eq!(max.checked_add(Wei::from_wei(1)), None);
+        assert_eq!(Gwei::new(1).checked_sub(Gwei::new(2)), None);
+        assert_eq!(Gwei::new(3).checked_sub(Gwei::new(2)), Some(Gwei::new(1)));
+        assert_eq!(Gwei::new(2).checked_mul(3), Some(Gwei::new(6)));
+        assert_eq!(max.checked_mul(2), None);
+        assert_eq!(Gwei::new(6).checked_div(3), Some(Gwei::new(2)));
+        assert_eq!(Gwei::new(6).checked_div(0), None);
+        assert_eq!(Gwei::new(1).saturating_sub(Gwei::new(2)), Gwei::zero());
+        assert!(Wei::zero().is_zero());
+    }
+}
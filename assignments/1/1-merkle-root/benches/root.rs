@@ -0,0 +1,33 @@
+//! Root throughput of `MerkleTree` against the parallel path.
+//!
+//! Run with `cargo bench`; Criterion reports leaves per second.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use merkle_root::hasher::{Hash, Hasher, Sha256};
+use merkle_root::parallel;
+use merkle_root::tree::{MerkleTree, Policy};
+
+fn leaves(count: usize) -> Vec<Hash> {
+    (0..count as u64).map(|i| Sha256::hash(&i.to_le_bytes())).collect()
+}
+
+fn root(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sha256 root");
+    group.sample_size(10);
+
+    for count in [1 << 10, 1 << 16, 1 << 20] {
+        let leaves = leaves(count);
+        group.throughput(Throughput::Elements(count as u64));
+
+        group.bench_with_input(BenchmarkId::new("sequential", count), &leaves, |b, leaves| {
+            b.iter(|| MerkleTree::<Sha256>::from_leaves(leaves.clone()).root())
+        });
+        group.bench_with_input(BenchmarkId::new("parallel", count), &leaves, |b, leaves| {
+            b.iter(|| parallel::root::<Sha256>(leaves, Policy::Promote))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, root);
+criterion_main!(benches);
@@ -0,0 +1,164 @@
+//! Consistency proofs for append-only trees (RFC 6962, section 2.1.2).
+//!
+//! A consistency proof shows that the tree of `new_size` leaves starts with
+//! the same `old_size` leaves as an older tree, so a log was only appended to.
+//! Use them with `Rfc6962<H>` and `Policy::Promote`, which give trees the
+//! RFC 6962 shape.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::hasher::{Hash, Hasher};
+use crate::proof::{hex_hashes, ProofError};
+use crate::tree::{MerkleTree, Policy};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsistencyProof {
+    pub old_size: u64,
+    pub new_size: u64,
+    #[serde(with = "hex_hashes")]
+    pub hashes: Vec<Hash>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsistencyError {
+    /// The old tree must have between 1 and `new_size` leaves.
+    InvalidSizes { old_size: usize, new_size: usize },
+    /// Only promoted trees grow by appending.
+    UnsupportedPolicy(Policy),
+}
+
+impl fmt::Display for ConsistencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConsistencyError::InvalidSizes { old_size, new_size } => write!(
+                f,
+                "cannot prove a tree of {} leaves against one of {}",
+                old_size, new_size
+            ),
+            ConsistencyError::UnsupportedPolicy(policy) => {
+                write!(f, "consistency proofs need the promote policy, not {}", policy)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConsistencyError {}
+
+impl<H: Hasher> MerkleTree<H> {
+    /// Proves that the first `old_size` leaves of this tree form the older tree.
+    pub fn consistency_proof(&self, old_size: usize) -> Result<ConsistencyProof, ConsistencyError> {
+        if self.policy() != Policy::Promote {
+            return Err(ConsistencyError::UnsupportedPolicy(self.policy()));
+        }
+        if old_size == 0 || old_size > self.len() {
+            return Err(ConsistencyError::InvalidSizes {
+                old_size,
+                new_size: self.len(),
+            });
+        }
+
+        let mut hashes = Vec::new();
+        self.subproof(old_size, 0, self.len(), true, &mut hashes);
+        Ok(ConsistencyProof {
+            old_size: old_size as u64,
+            new_size: self.len() as u64,
+            hashes,
+        })
+    }
+
+    /// RFC 6962 `SUBPROOF(m, D[start:end], b)`, appended to `out`.
+    fn subproof(&self, m: usize, start: usize, end: usize, whole_old_tree: bool, out: &mut Vec<Hash>) {
+        let size = end - start;
+        if m == size {
+            if !whole_old_tree {
+                out.push(self.subtree_root(start, end));
+            }
+            return;
+        }
+
+        let k = split_point(size);
+        if m <= k {
+            self.subproof(m, start, start + k, whole_old_tree, out);
+            out.push(self.subtree_root(start + k, end));
+        } else {
+            self.subproof(m - k, start + k, end, false, out);
+            out.push(self.subtree_root(start, start + k));
+        }
+    }
+
+    /// Root of the leaves `start..end`. Every range `subproof` asks for is
+    /// aligned, so it is a node of the promoted tree.
+    fn subtree_root(&self, start: usize, end: usize) -> Hash {
+        let level = (end - start).next_power_of_two().trailing_zeros() as usize;
+        *self.node(level, start >> level).expect("an aligned range is a node")
+    }
+}
+
+impl ConsistencyProof {
+    /// Checks that `old_root` is the root of the first `old_size` leaves of
+    /// the tree with `new_root` (the algorithm of RFC 9162, section 2.1.4.2).
+    pub fn verify<H: Hasher>(&self, old_root: &Hash, new_root: &Hash) -> bool {
+        let (old_size, new_size) = (self.old_size, self.new_size);
+        if old_size == 0 || old_size > new_size {
+            return false;
+        }
+        if old_size == new_size {
+            return self.hashes.is_empty() && old_root == new_root;
+        }
+
+        // When the old tree is a complete subtree its root is the first node.
+        let mut path = self.hashes.iter();
+        let first = if old_size.is_power_of_two() {
+            *old_root
+        } else {
+            match path.next() {
+                Some(hash) => *hash,
+                None => return false,
+            }
+        };
+
+        let mut fn_ = old_size - 1;
+        let mut sn = new_size - 1;
+        while fn_ & 1 == 1 {
+            fn_ >>= 1;
+            sn >>= 1;
+        }
+
+        let (mut fr, mut sr) = (first, first);
+        for hash in path {
+            if sn == 0 {
+                return false;
+            }
+            if fn_ & 1 == 1 || fn_ == sn {
+                fr = H::hash_pair(hash, &fr);
+                sr = H::hash_pair(hash, &sr);
+                while fn_ & 1 == 0 && fn_ != 0 {
+                    fn_ >>= 1;
+                    sn >>= 1;
+                }
+            } else {
+                sr = H::hash_pair(&sr, hash);
+            }
+            fn_ >>= 1;
+            sn >>= 1;
+        }
+
+        fr == *old_root && sr == *new_root && sn == 0
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a proof always serializes")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ProofError> {
+        serde_json::from_str(json).map_err(|e| ProofError::InvalidJson(e.to_string()))
+    }
+}
+
+/// Largest power of two below `size`, where RFC 6962 splits a tree.
+fn split_point(size: usize) -> usize {
+    1 << (usize::BITS - 1 - (size - 1).leading_zeros())
+}
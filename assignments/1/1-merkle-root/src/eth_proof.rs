@@ -0,0 +1,376 @@
+//! Account and storage proofs in the format of `eth_getProof` (EIP-1186).
+//!
+//! The state trie maps `keccak256(address)` to the RLP of
+//! `[nonce, balance, storageRoot, codeHash]`, and each contract's storage trie
+//! maps `keccak256(slot)` to the RLP of the slot's value. An account proof is
+//! a Patricia proof in the state trie, and a storage proof one in the storage
+//! trie whose root the account holds. Checking both against the `stateRoot` of
+//! a block header shows what the account held at that block without trusting
+//! the node that served the proof.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use ethereum_types::U256;
+use rlp::{Rlp, RlpStream};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::hasher::{Hash, Hasher, Keccak256};
+use crate::patricia::{verify_proof, PatriciaProofError, PatriciaTrie, EMPTY_TRIE_ROOT};
+
+pub type Address = [u8; 20];
+
+/// Code hash of an account without code: `keccak256("")`.
+pub const EMPTY_CODE_HASH: Hash = [
+    0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0, 0xe5, 0x00, 0xb6,
+    0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+];
+
+/// Index of `stateRoot` in an RLP-encoded block header.
+const HEADER_STATE_ROOT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Account {
+    pub nonce: u64,
+    pub balance: U256,
+    pub storage_root: Hash,
+    pub code_hash: Hash,
+}
+
+impl Account {
+    fn rlp(&self) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(4);
+        stream.append(&self.nonce);
+        stream.append(&self.balance);
+        stream.append(&self.storage_root.as_slice());
+        stream.append(&self.code_hash.as_slice());
+        stream.out().to_vec()
+    }
+
+    fn from_rlp(encoded: &[u8]) -> Option<Self> {
+        let account = Rlp::new(encoded);
+        if account.item_count().ok()? != 4 {
+            return None;
+        }
+        Some(Account {
+            nonce: account.val_at(0).ok()?,
+            balance: account.val_at(1).ok()?,
+            storage_root: account.at(2).ok()?.data().ok()?.try_into().ok()?,
+            code_hash: account.at(3).ok()?.data().ok()?.try_into().ok()?,
+        })
+    }
+}
+
+/// What `eth_getProof` reports for an account that does not exist.
+impl Default for Account {
+    fn default() -> Self {
+        Account {
+            nonce: 0,
+            balance: U256::zero(),
+            storage_root: EMPTY_TRIE_ROOT,
+            code_hash: EMPTY_CODE_HASH,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageProof {
+    pub slot: U256,
+    pub value: U256,
+    pub proof: Vec<Vec<u8>>,
+}
+
+/// One `eth_getProof` result: an account and some of its storage slots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountProof {
+    pub address: Address,
+    pub account: Account,
+    pub account_proof: Vec<Vec<u8>>,
+    pub storage_proof: Vec<StorageProof>,
+}
+
+#[derive(Debug)]
+pub enum EthProofError {
+    Parse(String),
+    Rpc(String),
+    InvalidHex(String),
+    InvalidQuantity(String),
+    InvalidHeader(String),
+    /// The header does not hash to the block hash it was checked against.
+    HeaderHashMismatch,
+    AccountProof(PatriciaProofError),
+    StorageProof { slot: U256, error: PatriciaProofError },
+    /// The state trie holds something that is not an account.
+    InvalidAccount,
+    /// The storage trie holds something that is not an RLP integer.
+    InvalidStorageValue { slot: U256 },
+    /// The proven account differs from the one in the response.
+    AccountMismatch,
+    StorageMismatch { slot: U256, proven: U256 },
+}
+
+impl fmt::Display for EthProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EthProofError::Parse(msg) => write!(f, "could not parse the proof: {}", msg),
+            EthProofError::Rpc(msg) => write!(f, "node returned an error: {}", msg),
+            EthProofError::InvalidHex(value) => write!(f, "`{}` is not 0x-prefixed hex of the right length", value),
+            EthProofError::InvalidQuantity(value) => write!(f, "`{}` is not a hex quantity", value),
+            EthProofError::InvalidHeader(msg) => write!(f, "not an RLP block header: {}", msg),
+            EthProofError::HeaderHashMismatch => write!(f, "the header does not hash to the block hash"),
+            EthProofError::AccountProof(err) => write!(f, "invalid account proof: {}", err),
+            EthProofError::StorageProof { slot, error } => write!(f, "invalid proof of slot {:#x}: {}", slot, error),
+            EthProofError::InvalidAccount => write!(f, "the state trie value is not an RLP account"),
+            EthProofError::InvalidStorageValue { slot } => write!(f, "slot {:#x} does not hold an RLP integer", slot),
+            EthProofError::AccountMismatch => write!(f, "the proven account differs from the claimed one"),
+            EthProofError::StorageMismatch { slot, proven } => {
+                write!(f, "slot {:#x} holds {:#x}, not the claimed value", slot, proven)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EthProofError {}
+
+impl AccountProof {
+    /// Checks the account against `state_root`, then every storage slot
+    /// against the account's storage root. A missing account must be claimed
+    /// as empty and a missing slot as zero.
+    pub fn verify(&self, state_root: &Hash) -> Result<(), EthProofError> {
+        let key = Keccak256::hash(&self.address);
+        let proven = match verify_proof(state_root, &key, &self.account_proof).map_err(EthProofError::AccountProof)? {
+            Some(encoded) => Account::from_rlp(&encoded).ok_or(EthProofError::InvalidAccount)?,
+            None => Account::default(),
+        };
+        if proven != self.account {
+            return Err(EthProofError::AccountMismatch);
+        }
+
+        for storage in &self.storage_proof {
+            let key = Keccak256::hash(&slot_bytes(storage.slot));
+            let proven = match verify_proof(&self.account.storage_root, &key, &storage.proof)
+                .map_err(|error| EthProofError::StorageProof { slot: storage.slot, error })?
+            {
+                Some(encoded) => Rlp::new(&encoded)
+                    .as_val::<U256>()
+                    .map_err(|_| EthProofError::InvalidStorageValue { slot: storage.slot })?,
+                None => U256::zero(),
+            };
+            if proven != storage.value {
+                return Err(EthProofError::StorageMismatch { slot: storage.slot, proven });
+            }
+        }
+        Ok(())
+    }
+
+    /// Decodes either a bare `eth_getProof` result or the whole JSON-RPC
+    /// response it came in.
+    pub fn from_json(json: &str) -> Result<Self, EthProofError> {
+        let mut value: Value = serde_json::from_str(json).map_err(|e| EthProofError::Parse(e.to_string()))?;
+        if let Some(error) = value.get("error") {
+            let message = error.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+            return Err(EthProofError::Rpc(message.to_string()));
+        }
+        if let Some(result) = value.get_mut("result") {
+            value = result.take();
+        }
+
+        let raw: RawAccountProof = serde_json::from_value(value).map_err(|e| EthProofError::Parse(e.to_string()))?;
+        let storage_proof = raw
+            .storage_proof
+            .iter()
+            .map(|storage| {
+                Ok(StorageProof {
+                    slot: quantity(&storage.key)?,
+                    value: quantity(&storage.value)?,
+                    proof: nodes(&storage.proof)?,
+                })
+            })
+            .collect::<Result<_, EthProofError>>()?;
+
+        Ok(AccountProof {
+            address: fixed(&raw.address)?,
+            account: Account {
+                nonce: quantity(&raw.nonce)?
+                    .try_into()
+                    .map_err(|_| EthProofError::InvalidQuantity(raw.nonce.clone()))?,
+                balance: quantity(&raw.balance)?,
+                storage_root: fixed(&raw.storage_hash)?,
+                code_hash: fixed(&raw.code_hash)?,
+            },
+            account_proof: nodes(&raw.account_proof)?,
+            storage_proof,
+        })
+    }
+
+    /// The `eth_getProof` result as a node would return it.
+    pub fn to_json(&self) -> String {
+        let hex = |bytes: &[u8]| format!("0x{}", hex::encode(bytes));
+        let raw = RawAccountProof {
+            address: hex(&self.address),
+            account_proof: self.account_proof.iter().map(|node| hex(node)).collect(),
+            balance: format!("{:#x}", self.account.balance),
+            code_hash: hex(&self.account.code_hash),
+            nonce: format!("{:#x}", self.account.nonce),
+            storage_hash: hex(&self.account.storage_root),
+            storage_proof: self
+                .storage_proof
+                .iter()
+                .map(|storage| RawStorageProof {
+                    key: hex(&slot_bytes(storage.slot)),
+                    value: format!("{:#x}", storage.value),
+                    proof: storage.proof.iter().map(|node| hex(node)).collect(),
+                })
+                .collect(),
+        };
+        serde_json::to_string_pretty(&raw).expect("a proof always serializes")
+    }
+}
+
+/// Accounts and their storage, kept in a state trie so their proofs can be
+/// generated the way a node answers `eth_getProof`.
+#[derive(Debug, Clone, Default)]
+pub struct WorldState {
+    accounts: HashMap<Address, (Account, PatriciaTrie)>,
+    trie: PatriciaTrie,
+}
+
+impl WorldState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets an account's nonce, balance and code hash, creating it if needed.
+    pub fn set_account(&mut self, address: Address, nonce: u64, balance: U256, code_hash: Hash) {
+        let (account, _) = self.accounts.entry(address).or_default();
+        account.nonce = nonce;
+        account.balance = balance;
+        account.code_hash = code_hash;
+        self.commit(address);
+    }
+
+    /// Sets a storage slot, creating the account if needed. Zero clears it,
+    /// and clearing a slot of an account that does not exist does nothing.
+    pub fn set_storage(&mut self, address: Address, slot: U256, value: U256) {
+        if value.is_zero() && !self.accounts.contains_key(&address) {
+            return;
+        }
+        let (account, storage) = self.accounts.entry(address).or_default();
+        let key = Keccak256::hash(&slot_bytes(slot));
+        if value.is_zero() {
+            storage.remove(&key);
+        } else {
+            storage.insert(&key, &rlp::encode(&value));
+        }
+        account.storage_root = storage.root();
+        self.commit(address);
+    }
+
+    pub fn account(&self, address: &Address) -> Option<&Account> {
+        self.accounts.get(address).map(|(account, _)| account)
+    }
+
+    pub fn root(&self) -> Hash {
+        self.trie.root()
+    }
+
+    /// What `eth_getProof(address, slots)` returns at this state.
+    pub fn proof(&self, address: &Address, slots: &[U256]) -> AccountProof {
+        let key = Keccak256::hash(address);
+        let (account, storage) = match self.accounts.get(address) {
+            Some((account, storage)) => (*account, Some(storage)),
+            None => (Account::default(), None),
+        };
+
+        let storage_proof = slots
+            .iter()
+            .map(|&slot| {
+                let key = Keccak256::hash(&slot_bytes(slot));
+                let proof = storage.map(|storage| storage.prove(&key)).unwrap_or_default();
+                let value = storage
+                    .and_then(|storage| storage.get(&key))
+                    .map(|encoded| Rlp::new(encoded).as_val().expect("slots hold RLP values"))
+                    .unwrap_or_default();
+                StorageProof { slot, value, proof }
+            })
+            .collect();
+
+        AccountProof {
+            address: *address,
+            account,
+            account_proof: self.trie.prove(&key),
+            storage_proof,
+        }
+    }
+
+    fn commit(&mut self, address: Address) {
+        let (account, _) = &self.accounts[&address];
+        self.trie.insert(&Keccak256::hash(&address), &account.rlp());
+    }
+}
+
+/// `stateRoot` of an RLP-encoded block header, after checking the header
+/// hashes to `block_hash`.
+pub fn header_state_root(header: &[u8], block_hash: &Hash) -> Result<Hash, EthProofError> {
+    if Keccak256::hash(header) != *block_hash {
+        return Err(EthProofError::HeaderHashMismatch);
+    }
+    let header = Rlp::new(header);
+    if !header.is_list() {
+        return Err(EthProofError::InvalidHeader(String::from("not a list")));
+    }
+    header
+        .at(HEADER_STATE_ROOT)
+        .and_then(|root| root.data().map(<[u8]>::to_vec))
+        .map_err(|e| EthProofError::InvalidHeader(e.to_string()))?
+        .try_into()
+        .map_err(|_| EthProofError::InvalidHeader(String::from("stateRoot is not 32 bytes")))
+}
+
+/// `eth_getProof` as it comes over the wire, with hex-encoded fields.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawAccountProof {
+    address: String,
+    account_proof: Vec<String>,
+    balance: String,
+    code_hash: String,
+    nonce: String,
+    storage_hash: String,
+    storage_proof: Vec<RawStorageProof>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawStorageProof {
+    key: String,
+    value: String,
+    proof: Vec<String>,
+}
+
+fn slot_bytes(slot: U256) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    slot.to_big_endian(&mut bytes);
+    bytes
+}
+
+fn quantity(value: &str) -> Result<U256, EthProofError> {
+    let invalid = || EthProofError::InvalidQuantity(value.to_string());
+    let digits = value.strip_prefix("0x").ok_or_else(invalid)?;
+    U256::from_str_radix(digits, 16).map_err(|_| invalid())
+}
+
+fn bytes(value: &str) -> Result<Vec<u8>, EthProofError> {
+    let invalid = || EthProofError::InvalidHex(value.to_string());
+    hex::decode(value.strip_prefix("0x").ok_or_else(invalid)?).map_err(|_| invalid())
+}
+
+fn fixed<const N: usize>(value: &str) -> Result<[u8; N], EthProofError> {
+    bytes(value)?
+        .try_into()
+        .map_err(|_| EthProofError::InvalidHex(value.to_string()))
+}
+
+fn nodes(values: &[String]) -> Result<Vec<Vec<u8>>, EthProofError> {
+    values.iter().map(|value| bytes(value)).collect()
+}
@@ -0,0 +1,112 @@
+//! Hash functions a `MerkleTree` can be built with.
+
+use std::marker::PhantomData;
+
+use sha2::Digest;
+
+/// Every supported hash function produces 32 bytes.
+pub type Hash = [u8; 32];
+
+pub trait Hasher {
+    /// Name shown to users, e.g. `sha256`.
+    const NAME: &'static str;
+
+    fn hash(data: &[u8]) -> Hash;
+
+    /// Parent of two nodes: the hash of `left || right`.
+    fn hash_pair(left: &Hash, right: &Hash) -> Hash {
+        let mut data = [0u8; 64];
+        data[..32].copy_from_slice(left);
+        data[32..].copy_from_slice(right);
+        Self::hash(&data)
+    }
+}
+
+pub struct Sha256;
+
+impl Hasher for Sha256 {
+    const NAME: &'static str = "sha256";
+
+    fn hash(data: &[u8]) -> Hash {
+        sha2::Sha256::digest(data).into()
+    }
+}
+
+pub struct Keccak256;
+
+impl Hasher for Keccak256 {
+    const NAME: &'static str = "keccak256";
+
+    fn hash(data: &[u8]) -> Hash {
+        sha3::Keccak256::digest(data).into()
+    }
+}
+
+/// SHA-256 applied twice, as Bitcoin hashes transactions and tree nodes.
+pub struct DoubleSha256;
+
+impl Hasher for DoubleSha256 {
+    const NAME: &'static str = "double-sha256";
+
+    fn hash(data: &[u8]) -> Hash {
+        sha2::Sha256::digest(sha2::Sha256::digest(data)).into()
+    }
+}
+
+/// Prefix of hashed leaf data.
+pub const LEAF_PREFIX: u8 = 0x00;
+/// Prefix of hashed child pairs.
+pub const NODE_PREFIX: u8 = 0x01;
+
+/// Domain-separated hashing as in RFC 6962 (Certificate Transparency): leaves
+/// are `H(0x00 || data)` and nodes `H(0x01 || left || right)`.
+///
+/// Plain `H` hashes a node exactly like a leaf whose data is `left || right`,
+/// so anyone can present an internal node as a leaf and prove it with the
+/// path above it. The prefixes make the two kinds of input disjoint. Trees
+/// built with `Policy::Promote` then have the same shape as RFC 6962 trees.
+///
+/// It wraps each hasher of this module, and its `NAME` names the inner hasher,
+/// e.g. `rfc6962-sha256`.
+pub struct Rfc6962<H: Hasher>(PhantomData<H>);
+
+impl<H: Hasher> Rfc6962<H> {
+    fn hash_leaf(data: &[u8]) -> Hash {
+        let mut prefixed = Vec::with_capacity(1 + data.len());
+        prefixed.push(LEAF_PREFIX);
+        prefixed.extend_from_slice(data);
+        H::hash(&prefixed)
+    }
+
+    fn hash_node(left: &Hash, right: &Hash) -> Hash {
+        let mut data = [0u8; 65];
+        data[0] = NODE_PREFIX;
+        data[1..33].copy_from_slice(left);
+        data[33..].copy_from_slice(right);
+        H::hash(&data)
+    }
+}
+
+macro_rules! rfc6962 {
+    ($($inner:ident => $name:literal),+) => {
+        $(
+            impl Hasher for Rfc6962<$inner> {
+                const NAME: &'static str = $name;
+
+                fn hash(data: &[u8]) -> Hash {
+                    Self::hash_leaf(data)
+                }
+
+                fn hash_pair(left: &Hash, right: &Hash) -> Hash {
+                    Self::hash_node(left, right)
+                }
+            }
+        )+
+    };
+}
+
+rfc6962!(
+    Sha256 => "rfc6962-sha256",
+    Keccak256 => "rfc6962-keccak256",
+    DoubleSha256 => "rfc6962-double-sha256"
+);
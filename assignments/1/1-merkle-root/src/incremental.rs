@@ -0,0 +1,129 @@
+//! Fixed-depth, append-only Merkle tree that keeps one frontier node per level
+//! instead of the leaves, the way the beacon chain deposit contract does.
+//!
+//! Missing leaves are zero, so an empty subtree of height `h` hashes to
+//! `zero_hashes[h]`. Appending a leaf walks up until it finds a level where
+//! its subtree is a left child and stores it there; the root is rebuilt from
+//! those stored left children and the zero hashes.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::hasher::{Hash, Hasher, Sha256};
+
+/// Depth of the deposit contract tree.
+pub const DEPOSIT_CONTRACT_TREE_DEPTH: usize = 32;
+
+/// The deposit contract's tree: SHA-256 and depth 32.
+pub type DepositTree = IncrementalMerkleTree<Sha256, DEPOSIT_CONTRACT_TREE_DEPTH>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeFull {
+    pub capacity: u64,
+}
+
+impl fmt::Display for TreeFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the tree is full at {} leaves", self.capacity)
+    }
+}
+
+impl std::error::Error for TreeFull {}
+
+pub struct IncrementalMerkleTree<H: Hasher, const DEPTH: usize> {
+    /// `branch[h]` is the last completed left subtree of height `h`.
+    branch: [Hash; DEPTH],
+    zero_hashes: [Hash; DEPTH],
+    count: u64,
+    hasher: PhantomData<H>,
+}
+
+impl<H: Hasher, const DEPTH: usize> IncrementalMerkleTree<H, DEPTH> {
+    /// Like the deposit contract, the last of the `2^DEPTH` slots is never filled.
+    pub const CAPACITY: u64 = (1 << DEPTH) - 1;
+
+    pub fn new() -> Self {
+        const { assert!(DEPTH > 0 && DEPTH < 64, "depth must be between 1 and 63") };
+
+        let mut zero_hashes = [[0u8; 32]; DEPTH];
+        for height in 1..DEPTH {
+            zero_hashes[height] = H::hash_pair(&zero_hashes[height - 1], &zero_hashes[height - 1]);
+        }
+
+        Self {
+            branch: [[0u8; 32]; DEPTH],
+            zero_hashes,
+            count: 0,
+            hasher: PhantomData,
+        }
+    }
+
+    /// Appends a leaf, as the deposit contract's `deposit` does with the
+    /// deposit data root.
+    pub fn push(&mut self, leaf: Hash) -> Result<(), TreeFull> {
+        if self.count >= Self::CAPACITY {
+            return Err(TreeFull { capacity: Self::CAPACITY });
+        }
+
+        self.count += 1;
+        let mut size = self.count;
+        let mut node = leaf;
+        for height in 0..DEPTH {
+            if size & 1 == 1 {
+                self.branch[height] = node;
+                return Ok(());
+            }
+            node = H::hash_pair(&self.branch[height], &node);
+            size >>= 1;
+        }
+        unreachable!("a tree below capacity always has a free level")
+    }
+
+    /// Root of all `2^DEPTH` slots, the empty ones being zero.
+    pub fn root(&self) -> Hash {
+        let mut node = [0u8; 32];
+        let mut size = self.count;
+        for height in 0..DEPTH {
+            node = if size & 1 == 1 {
+                H::hash_pair(&self.branch[height], &node)
+            } else {
+                H::hash_pair(&node, &self.zero_hashes[height])
+            };
+            size >>= 1;
+        }
+        node
+    }
+
+    /// `get_deposit_root`: the root with the leaf count mixed in, as SSZ does
+    /// for lists. The count is 8 little-endian bytes padded to 32.
+    pub fn deposit_root(&self) -> Hash {
+        let mut length = [0u8; 32];
+        length[..8].copy_from_slice(&self.count.to_le_bytes());
+        H::hash_pair(&self.root(), &length)
+    }
+
+    /// `get_deposit_count`.
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The frontier: one node per level, which is all the tree stores.
+    pub fn branch(&self) -> &[Hash; DEPTH] {
+        &self.branch
+    }
+
+    /// `zero_hashes[h]` is the root of an empty subtree of height `h`.
+    pub fn zero_hashes(&self) -> &[Hash; DEPTH] {
+        &self.zero_hashes
+    }
+}
+
+impl<H: Hasher, const DEPTH: usize> Default for IncrementalMerkleTree<H, DEPTH> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
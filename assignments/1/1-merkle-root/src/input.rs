@@ -0,0 +1,332 @@
+//! Reading leaves from files: one per line of text, one per row of a CSV
+//! column, one per element of a JSON array, or one per fixed-size record of a
+//! binary file.
+//!
+//! Items are read one at a time and turned into their 32-byte leaf straight
+//! away, so a large export never sits in memory as text. Raw items are hashed
+//! with the tree's hasher; pre-hashed ones must already be 32-byte hashes
+//! (hex in text formats).
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::de::{DeserializeSeed, SeqAccess, Visitor};
+use serde_json::Value;
+
+use crate::hasher::{Hash, Hasher};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputFormat {
+    /// One item per non-empty line.
+    Lines,
+    /// One item per row, taken from a single column.
+    Csv(CsvColumn),
+    /// An array whose elements are the items. Strings are used as they are;
+    /// other values as their compact JSON text.
+    Json,
+    /// Back-to-back records of `record_size` bytes.
+    Binary { record_size: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvColumn {
+    /// Zero-based column of a file without a header row.
+    Index(usize),
+    /// Column named in the header row.
+    Name(String),
+}
+
+impl InputFormat {
+    /// Guesses the format from the file extension: `.csv` (first column),
+    /// `.json`, `.bin` (32-byte records) or anything else as lines.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => InputFormat::Csv(CsvColumn::Index(0)),
+            Some("json") => InputFormat::Json,
+            Some("bin") => InputFormat::Binary { record_size: 32 },
+            _ => InputFormat::Lines,
+        }
+    }
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    /// `lines`, `csv`, `json` or `binary`, with the defaults of `from_path`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "lines" | "text" | "txt" => Ok(InputFormat::Lines),
+            "csv" => Ok(InputFormat::Csv(CsvColumn::Index(0))),
+            "json" => Ok(InputFormat::Json),
+            "binary" | "bin" => Ok(InputFormat::Binary { record_size: 32 }),
+            _ => Err(format!("unknown input format `{}`", s)),
+        }
+    }
+}
+
+impl FromStr for CsvColumn {
+    type Err = String;
+
+    /// A number selects a column by index, anything else by header name.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse() {
+            Ok(index) => CsvColumn::Index(index),
+            Err(_) => CsvColumn::Name(s.to_string()),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum InputError {
+    Io(io::Error),
+    /// Item `item` (a line number in text formats, counting from 1) is malformed.
+    Parse { item: usize, message: String },
+    /// A pre-hashed item is not 32 bytes of hex.
+    InvalidHash { item: usize, value: String },
+    MissingColumn(String),
+    /// A binary file does not end on a record boundary.
+    PartialRecord { record_size: usize, trailing: usize },
+    /// Pre-hashed binary records must be 32 bytes.
+    RecordSize(usize),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::Io(err) => write!(f, "could not read leaves: {}", err),
+            InputError::Parse { item, message } => write!(f, "item {}: {}", item, message),
+            InputError::InvalidHash { item, value } => {
+                write!(f, "item {}: `{}` is not a 32-byte hex hash", item, value)
+            }
+            InputError::MissingColumn(name) => write!(f, "the CSV header has no column `{}`", name),
+            InputError::PartialRecord { record_size, trailing } => write!(
+                f,
+                "the file ends with {} bytes, short of a {}-byte record",
+                trailing, record_size
+            ),
+            InputError::RecordSize(size) => write!(f, "pre-hashed records are 32 bytes, not {}", size),
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+impl From<io::Error> for InputError {
+    fn from(err: io::Error) -> Self {
+        InputError::Io(err)
+    }
+}
+
+/// Reads the leaves in `path`. With `prehashed`, items are used as leaves
+/// as they are; otherwise each is hashed with `H`.
+pub fn read_leaves<H: Hasher>(path: &Path, format: &InputFormat, prehashed: bool) -> Result<Vec<Hash>, InputError> {
+    read_leaves_from::<H, _>(BufReader::new(File::open(path)?), format, prehashed)
+}
+
+/// `read_leaves` over any reader, such as stdin.
+pub fn read_leaves_from<H: Hasher, R: BufRead>(
+    reader: R,
+    format: &InputFormat,
+    prehashed: bool,
+) -> Result<Vec<Hash>, InputError> {
+    let leaf = |item: usize, data: &[u8]| -> Result<Hash, InputError> {
+        if !prehashed {
+            return Ok(H::hash(data));
+        }
+        let text = String::from_utf8_lossy(data);
+        let text = text.trim();
+        let invalid = || InputError::InvalidHash {
+            item,
+            value: text.to_string(),
+        };
+        hex::decode(text.strip_prefix("0x").unwrap_or(text))
+            .map_err(|_| invalid())?
+            .try_into()
+            .map_err(|_| invalid())
+    };
+
+    match format {
+        InputFormat::Lines => read_lines(reader, leaf),
+        InputFormat::Csv(column) => read_csv(reader, column, leaf),
+        InputFormat::Json => read_json(reader, leaf),
+        InputFormat::Binary { record_size } => read_binary::<H, _>(reader, *record_size, prehashed),
+    }
+}
+
+fn read_lines<R: BufRead>(
+    reader: R,
+    leaf: impl Fn(usize, &[u8]) -> Result<Hash, InputError>,
+) -> Result<Vec<Hash>, InputError> {
+    let mut leaves = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.strip_suffix('\r').unwrap_or(&line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        leaves.push(leaf(index + 1, line.as_bytes())?);
+    }
+    Ok(leaves)
+}
+
+fn read_csv<R: BufRead>(
+    reader: R,
+    column: &CsvColumn,
+    leaf: impl Fn(usize, &[u8]) -> Result<Hash, InputError>,
+) -> Result<Vec<Hash>, InputError> {
+    let mut lines = reader.lines().enumerate();
+    let index = match column {
+        CsvColumn::Index(index) => *index,
+        CsvColumn::Name(name) => {
+            let header = match lines.next() {
+                Some((_, line)) => line?,
+                None => String::new(),
+            };
+            csv_fields(&header)
+                .iter()
+                .position(|field| field == name)
+                .ok_or_else(|| InputError::MissingColumn(name.clone()))?
+        }
+    };
+
+    let mut leaves = Vec::new();
+    for (line_no, line) in lines {
+        let line = line?;
+        let line = line.strip_suffix('\r').unwrap_or(&line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = csv_fields(line);
+        let field = fields.get(index).ok_or_else(|| InputError::Parse {
+            item: line_no + 1,
+            message: format!("expected at least {} columns, got {}", index + 1, fields.len()),
+        })?;
+        leaves.push(leaf(line_no + 1, field.as_bytes())?);
+    }
+    Ok(leaves)
+}
+
+/// Splits a CSV row on commas outside double quotes; `""` inside quotes is a quote.
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().expect("starts with one field");
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            _ => field.push(c),
+        }
+    }
+    fields
+}
+
+fn read_json<R: Read>(
+    reader: R,
+    leaf: impl Fn(usize, &[u8]) -> Result<Hash, InputError>,
+) -> Result<Vec<Hash>, InputError> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let leaves = serde::Deserializer::deserialize_seq(&mut deserializer, LeafVisitor { leaf: &leaf }).map_err(|e| {
+        if e.is_io() {
+            InputError::Io(e.into())
+        } else {
+            InputError::Parse {
+                item: e.line(),
+                message: e.to_string(),
+            }
+        }
+    })?;
+    deserializer.end().map_err(|e| InputError::Parse {
+        item: e.line(),
+        message: e.to_string(),
+    })?;
+    leaves
+}
+
+/// Turns each array element into a leaf as soon as it is parsed. A bad leaf
+/// stops the parse and comes back as the inner result.
+struct LeafVisitor<'a, F> {
+    leaf: &'a F,
+}
+
+impl<'de, F: Fn(usize, &[u8]) -> Result<Hash, InputError>> Visitor<'de> for LeafVisitor<'_, F> {
+    type Value = Result<Vec<Hash>, InputError>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of leaves")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut leaves = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element_seed(ItemSeed)? {
+            let data = match value {
+                Value::String(text) => text,
+                other => other.to_string(),
+            };
+            match (self.leaf)(leaves.len() + 1, data.as_bytes()) {
+                Ok(leaf) => leaves.push(leaf),
+                Err(err) => return Ok(Err(err)),
+            }
+        }
+        Ok(Ok(leaves))
+    }
+}
+
+/// Parses one array element.
+struct ItemSeed;
+
+impl<'de> DeserializeSeed<'de> for ItemSeed {
+    type Value = Value;
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        serde::Deserialize::deserialize(deserializer)
+    }
+}
+
+fn read_binary<H: Hasher, R: Read>(mut reader: R, record_size: usize, prehashed: bool) -> Result<Vec<Hash>, InputError> {
+    if record_size == 0 || (prehashed && record_size != 32) {
+        return Err(InputError::RecordSize(record_size));
+    }
+
+    let mut leaves = Vec::new();
+    let mut record = vec![0u8; record_size];
+    loop {
+        let filled = fill(&mut reader, &mut record)?;
+        if filled == 0 {
+            return Ok(leaves);
+        }
+        if filled < record_size {
+            return Err(InputError::PartialRecord {
+                record_size,
+                trailing: filled,
+            });
+        }
+        leaves.push(if prehashed {
+            record[..].try_into().expect("record is 32 bytes")
+        } else {
+            H::hash(&record)
+        });
+    }
+}
+
+/// Reads until `buf` is full or the input ends, returning the bytes read.
+fn fill<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(filled)
+}
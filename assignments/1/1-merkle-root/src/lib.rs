@@ -0,0 +1,13 @@
+pub mod consistency;
+pub mod eth_proof;
+pub mod hasher;
+pub mod input;
+pub mod incremental;
+pub mod mmr;
+pub mod multiproof;
+pub mod parallel;
+pub mod patricia;
+pub mod proof;
+pub mod sparse;
+pub mod standard;
+pub mod tree;
@@ -1,156 +1,606 @@
-use std::{hash, io};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
 
-use ethers::utils::{hex};
-use sha2::{Sha256, Digest};
-use sha3::Keccak256;
-// use sha3::{Keccak256};
+use merkle_root::hasher::{DoubleSha256, Hash, Hasher, Keccak256, Sha256};
+use merkle_root::input::{read_leaves, read_leaves_from, CsvColumn, InputFormat};
+use merkle_root::parallel;
+use merkle_root::proof::{verify_proof, MerkleProof};
+use merkle_root::tree::{MerkleTree, Policy};
 
 struct Transaction {
     tx: String
 }
 
-// #[derive(Debug, Clone, Copy)]
 impl Transaction {
     fn new(metadata: &str) -> Self {
-        Self { 
-            tx: String::from(metadata) 
+        Self {
+            tx: String::from(metadata)
         }
     }
 }
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
 
-    let tx1 = Transaction::new("Alice sent 2 eth");
-    let tx2 = Transaction::new("Jacks sent 1 eth");
-    let tx3 = Transaction::new("Mike sent 8 eth");
-    let tx4 = Transaction::new("Richard sent 2 eth");
-    let tx5 = Transaction::new("Key sent 2 eth");
+    let result = match args.first().map(String::as_str) {
+        None => run_interactive(),
+        Some("list") => run_list(&args[1..]),
+        Some("add") => run_add(&args[1..]),
+        Some("remove") => run_remove(&args[1..]),
+        Some("root") => run_root(args[1..].to_vec()),
+        Some("tree") => run_tree(args[1..].to_vec()),
+        Some("prove") => run_prove(args[1..].to_vec()),
+        Some("verify") => run_verify(args[1..].to_vec()),
+        Some(other) => Err(format!("unknown command `{}`", other)),
+    };
 
-    
-    print!(" 1 => Proceed   ");
-    print!("      2 => Concatenate Transactions");
-    println!("");
-    println!("This is Block #10290, please validate transactions");
-    let mut trxn_input = String::new();
-    io::stdin().read_line(&mut trxn_input).expect("Failed to read this line");
-    
-    let mut txns = vec![tx1, tx2, tx3, tx4, tx5];
-    let mut hashed_trxn = Vec::new();
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        eprintln!();
+        print_usage();
+        process::exit(1);
+    }
+}
+
+fn print_usage() {
+    eprintln!("Usage:");
+    eprintln!("  merkle_root                                    interactive menu");
+    eprintln!("  merkle_root list <transactions.txt>");
+    eprintln!("  merkle_root add <transactions.txt> <transaction>...");
+    eprintln!("  merkle_root remove <transactions.txt> <index>");
+    eprintln!("  merkle_root root <file|-> [input options] [--hash <name>] [--policy <name>]");
+    eprintln!("  merkle_root tree <file|-> [input options] [--hash <name>] [--policy <name>]");
+    eprintln!("  merkle_root prove <file|-> <index> [input options] [--hash <name>] [--policy <name>] [--hex]");
+    eprintln!("  merkle_root verify <proof.json|0xproof|-> <root> <index> <leaves>");
+    eprintln!("                     (--data <transaction>|--leaf <hash>) [--hash <name>] [--policy <name>]");
+    eprintln!();
+    eprintln!("Input options: [--format lines|csv|json|binary] [--column <name|index>] [--record-size <n>]");
+    eprintln!("               [--prehashed]");
+    eprintln!("Hashes: sha256 (default), keccak256, double-sha256");
+    eprintln!("Policies: promote (default), duplicate, sorted-pairs");
+    eprintln!("The format defaults to the file extension: .csv, .json, .bin or lines of text.");
+    eprintln!("Transactions are numbered from 0. verify exits with status 1 if the proof is invalid.");
+}
+
+/// Hash function picked on the command line or in the menu.
+#[derive(Debug, Clone, Copy, Default)]
+enum Algorithm {
+    #[default]
+    Sha256,
+    Keccak256,
+    DoubleSha256,
+}
+
+impl Algorithm {
+    const ALL: [Algorithm; 3] = [Algorithm::Sha256, Algorithm::Keccak256, Algorithm::DoubleSha256];
 
-    for i in txns.iter_mut(){
-        let mut hash = Sha256::new();
-        hash.update(i.tx.clone());
-        let result = hash.finalize().to_vec();
-        hashed_trxn.push(result);
+    fn name(self) -> &'static str {
+        match self {
+            Algorithm::Sha256 => Sha256::NAME,
+            Algorithm::Keccak256 => Keccak256::NAME,
+            Algorithm::DoubleSha256 => DoubleSha256::NAME,
+        }
     }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
 
-    for hash in hashed_trxn.iter() {
-        println!("{:?}", hex::encode(hash));
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_ascii_lowercase();
+        Algorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name() == lower)
+            .ok_or_else(|| format!("unknown hash `{}`", s))
     }
+}
 
+/// Runs `$body` with `$hasher` naming the `Hasher` type `$algorithm` selects.
+macro_rules! with_hasher {
+    ($algorithm:expr, $hasher:ident => $body:expr) => {
+        match $algorithm {
+            Algorithm::Sha256 => {
+                type $hasher = Sha256;
+                $body
+            }
+            Algorithm::Keccak256 => {
+                type $hasher = Keccak256;
+                $body
+            }
+            Algorithm::DoubleSha256 => {
+                type $hasher = DoubleSha256;
+                $body
+            }
+        }
+    };
+}
 
-    let sha256_root = get_merkle_root_through_sha256(hashed_trxn.clone());
-    // let Keccak256_root = get_merkle_root_through_kecheck(hashed_trxn);
+// Interactive menu.
 
-    println!(""); 
-    println!("Merkel root using sha256: ___ {}", hex::encode(sha256_root));
-    // println!("Merkel root using keccak256: ___ {}", hex::encode(Keccak256_root));
+/// The block being edited in the menu.
+struct Session {
+    transactions: Vec<Transaction>,
+    algorithm: Algorithm,
 }
 
+impl Session {
+    fn leaves<H: Hasher>(&self) -> Vec<Hash> {
+        self.transactions.iter().map(|t| H::hash(t.tx.as_bytes())).collect()
+    }
 
-fn get_merkle_root_through_sha256(mut hashes: Vec<Vec<u8>>) -> Vec<u8> {
+    fn root(&self) -> Option<Hash> {
+        with_hasher!(self.algorithm, H => parallel::root::<H>(&self.leaves::<H>(), Policy::Promote))
+    }
 
-    println!("");
-    println!("Using Sha256");
-    if hashes.is_empty(){
-        return vec![]
+    fn print_menu(&self) {
+        println!();
+        println!(
+            "This is Block #10290 with {} transactions, hashed with {}",
+            self.transactions.len(),
+            self.algorithm.name()
+        );
+        println!(" 1 => List transactions       2 => Add a transaction");
+        println!(" 3 => Remove a transaction    4 => Choose the hash algorithm");
+        println!(" 5 => Show the tree           6 => Prove a transaction");
+        println!(" 7 => Verify a proof          q => Quit");
     }
-    
-    
-    while hashes.len() > 1{
-        let mut next_level = Vec::new();
-        
-        let mut i = 0;
 
-        println!("");
-        println!("CURRENT LEVEL");
-        for (i, h) in hashes.iter().enumerate(){
-            println!("Transaction {}: {}", i, hex::encode(h))
+    /// Runs menu entry `choice`, reading whatever else it needs from `input`.
+    /// Returns `false` once the user quits.
+    fn handle(&mut self, choice: &str, input: &mut impl BufRead) -> Result<bool, String> {
+        match choice {
+            "1" => {
+                let texts: Vec<&str> = self.transactions.iter().map(|t| t.tx.as_str()).collect();
+                print_transactions(&texts);
+            }
+            "2" => {
+                let Some(text) = prompt(input, "Transaction: ")? else {
+                    return Ok(false);
+                };
+                check_transaction(&text)?;
+                self.transactions.push(Transaction::new(&text));
+                println!("Added transaction {}", self.transactions.len() - 1);
+            }
+            "3" => {
+                let Some(index) = prompt(input, "Index of the transaction to remove: ")? else {
+                    return Ok(false);
+                };
+                let index = transaction_index(&index, self.transactions.len())?;
+                println!("Removed \"{}\"", self.transactions.remove(index).tx);
+            }
+            "4" => {
+                for (number, algorithm) in Algorithm::ALL.into_iter().enumerate() {
+                    println!(" {} => {}", number + 1, algorithm.name());
+                }
+                let Some(choice) = prompt(input, "Hash algorithm: ")? else {
+                    return Ok(false);
+                };
+                self.algorithm = match choice.parse::<usize>() {
+                    Ok(number) if (1..=Algorithm::ALL.len()).contains(&number) => Algorithm::ALL[number - 1],
+                    _ => choice.parse()?,
+                };
+                println!("Using {}", self.algorithm.name());
+            }
+            "5" => with_hasher!(self.algorithm, H => print_tree::<H>(self.leaves::<H>(), Policy::Promote)),
+            "6" => {
+                let Some(index) = prompt(input, "Index of the transaction to prove: ")? else {
+                    return Ok(false);
+                };
+                let index = transaction_index(&index, self.transactions.len())?;
+                with_hasher!(self.algorithm, H => print_proof::<H>(self.leaves::<H>(), index, Policy::Promote, false))?;
+            }
+            "7" => {
+                let Some(proof) = prompt_proof(input)? else {
+                    return Ok(false);
+                };
+                let proof = parse_proof(&proof)?;
+                let Some(root) = prompt(input, "Root (blank for this block's root): ")? else {
+                    return Ok(false);
+                };
+                let root = match root.as_str() {
+                    "" => self.root().ok_or("the block has no transactions")?,
+                    _ => parse_hash(&root)?,
+                };
+                let Some(index) = prompt(input, "Index of the transaction it proves: ")? else {
+                    return Ok(false);
+                };
+                let index = transaction_index(&index, self.transactions.len())?;
+                let tx = &self.transactions[index].tx;
+                let leaf = hash_tx(self.algorithm, tx);
+                let (index, count) = (index as u64, self.transactions.len() as u64);
+                with_hasher!(self.algorithm, H => {
+                    check_proof::<H>(&proof, &leaf, index, count, &root, Policy::Promote, Some(tx))
+                });
+            }
+            "q" | "quit" | "exit" => return Ok(false),
+            "" => {}
+            other => return Err(format!("unknown menu entry `{}`", other)),
         }
+        Ok(true)
+    }
+}
 
-        while i < hashes.len() {
+fn hash_tx(algorithm: Algorithm, text: &str) -> Hash {
+    with_hasher!(algorithm, H => H::hash(text.as_bytes()))
+}
 
-            if i + 1 < hashes.len() {
+/// Starts from the five transactions of the original demo block.
+fn run_interactive() -> Result<(), String> {
+    let mut session = Session {
+        transactions: vec![
+            Transaction::new("Alice sent 2 eth"),
+            Transaction::new("Jacks sent 1 eth"),
+            Transaction::new("Mike sent 8 eth"),
+            Transaction::new("Richard sent 2 eth"),
+            Transaction::new("Key sent 2 eth"),
+        ],
+        algorithm: Algorithm::default(),
+    };
 
-            println!("");
-            println!("Hashing ==[{}
-            || {}]==", hex::encode(hashes[i].clone()), hex::encode(hashes[i + 1].clone()));
+    let mut input = io::stdin().lock();
+    loop {
+        session.print_menu();
+        let Some(choice) = prompt(&mut input, "> ")? else {
+            return Ok(());
+        };
+        match session.handle(&choice, &mut input) {
+            Ok(true) => {}
+            Ok(false) => return Ok(()),
+            Err(err) => println!("error: {}", err),
+        }
+    }
+}
 
-                let mut hasher = Sha256::new();
-                hasher.update(&hashes[i]);
-                hasher.update(&hashes[i + 1]);
-                let result: Vec<u8> = hasher.finalize().to_vec();
-                println!("");
-                println!("Result: {}", hex::encode(&result));
-                next_level.push(result);
-            }else {
-                println!("Unlinked hash: {}", hex::encode(hashes[i].clone()));
-                next_level.push(hashes[i].clone());
-            }
-            
-            i += 2;
+/// Prints `label` and reads one trimmed line, `None` at the end of input.
+fn prompt(input: &mut impl BufRead, label: &str) -> Result<Option<String>, String> {
+    print!("{}", label);
+    io::stdout().flush().map_err(|e| e.to_string())?;
+
+    let mut line = String::new();
+    match input.read_line(&mut line) {
+        Ok(0) => Ok(None),
+        Ok(_) => Ok(Some(line.trim().to_string())),
+        Err(err) => Err(format!("could not read input: {}", err)),
+    }
+}
+
+/// Reads a proof as hex on one line or as JSON, pretty-printed over several
+/// lines until its braces balance.
+fn prompt_proof(input: &mut impl BufRead) -> Result<Option<String>, String> {
+    let Some(mut proof) = prompt(input, "Proof (JSON or hex): ")? else {
+        return Ok(None);
+    };
+    while proof.matches('{').count() > proof.matches('}').count() {
+        let mut line = String::new();
+        match input.read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => proof.push_str(&line),
+            Err(err) => return Err(format!("could not read input: {}", err)),
         }
-        hashes = next_level;
     }
+    Ok(Some(proof))
+}
+
+// Subcommands. `list`, `add` and `remove` keep a block's transactions in a
+// text file, one per line, which `root`, `tree` and `prove` read like any
+// other input file.
+
+fn run_list(args: &[String]) -> Result<(), String> {
+    let [path] = args else {
+        return Err("list expects a transactions file".to_string());
+    };
+    let transactions = read_transactions(path)?;
+    let texts: Vec<&str> = transactions.iter().map(String::as_str).collect();
+    print_transactions(&texts);
+    Ok(())
+}
+
+/// Appends transactions, creating the file if needed.
+fn run_add(args: &[String]) -> Result<(), String> {
+    let [path, texts @ ..] = args else {
+        return Err("add expects a transactions file".to_string());
+    };
+    if texts.is_empty() {
+        return Err("add expects at least one transaction".to_string());
+    }
+    texts.iter().try_for_each(|text| check_transaction(text))?;
+
+    let mut transactions = if Path::new(path).exists() { read_transactions(path)? } else { Vec::new() };
+    let first = transactions.len();
+    transactions.extend(texts.iter().cloned());
+    write_transactions(path, &transactions)?;
+    for (index, text) in transactions.iter().enumerate().skip(first) {
+        println!("Added transaction {}: {}", index, text);
+    }
+    Ok(())
+}
+
+fn run_remove(args: &[String]) -> Result<(), String> {
+    let [path, index] = args else {
+        return Err("remove expects a transactions file and an index".to_string());
+    };
+    let mut transactions = read_transactions(path)?;
+    let index = transaction_index(index, transactions.len())?;
+    let removed = transactions.remove(index);
+    write_transactions(path, &transactions)?;
+    println!("Removed transaction {}: {}", index, removed);
+    Ok(())
+}
 
-    hashes.pop().unwrap()
+/// Prints the root of the leaves read from a file, or stdin for `-`.
+fn run_root(mut args: Vec<String>) -> Result<(), String> {
+    let input = take_input_options(&mut args)?;
+    let (algorithm, policy) = take_tree_options(&mut args)?;
+    let [path] = args.as_slice() else {
+        return Err("root expects one input file".to_string());
+    };
 
+    with_hasher!(algorithm, H => {
+        let leaves = read_input::<H>(path, &input)?;
+        let root = parallel::root::<H>(&leaves, policy).ok_or_else(|| format!("{}: no leaves", path))?;
+        println!("Leaves: {}", leaves.len());
+        println!("Merkle root using {} ({}): {}", H::NAME, policy, hex::encode(root));
+    });
+    Ok(())
+}
+
+fn run_tree(mut args: Vec<String>) -> Result<(), String> {
+    let input = take_input_options(&mut args)?;
+    let (algorithm, policy) = take_tree_options(&mut args)?;
+    let [path] = args.as_slice() else {
+        return Err("tree expects one input file".to_string());
+    };
 
+    with_hasher!(algorithm, H => print_tree::<H>(read_input::<H>(path, &input)?, policy));
+    Ok(())
 }
 
-fn get_merkle_root_through_kecheck(mut hashes: Vec<Vec<u8>>) -> Vec<u8> {
-    println!("");
-    println!("Using Keccak256");
-  if hashes.is_empty(){
-        return vec![]
+fn run_prove(mut args: Vec<String>) -> Result<(), String> {
+    let hex_only = take_flag(&mut args, "--hex");
+    let input = take_input_options(&mut args)?;
+    let (algorithm, policy) = take_tree_options(&mut args)?;
+    let [path, index] = args.as_slice() else {
+        return Err("prove expects an input file and a leaf index".to_string());
+    };
+    let index: usize = parse_arg(index, "the leaf index")?;
+
+    with_hasher!(algorithm, H => print_proof::<H>(read_input::<H>(path, &input)?, index, policy, hex_only))
+}
+
+/// Checks a proof given as a JSON file, hex, or either on stdin for `-`, for
+/// the leaf given by `--data` or `--leaf` at `index` of a `leaves`-leaf tree.
+fn run_verify(mut args: Vec<String>) -> Result<(), String> {
+    let data = take_option(&mut args, "--data")?;
+    let leaf = take_option(&mut args, "--leaf")?;
+    let (algorithm, policy) = take_tree_options(&mut args)?;
+    let [proof, root, index, leaf_count] = args.as_slice() else {
+        return Err("verify expects a proof, a root, the leaf index and the number of leaves".to_string());
+    };
+    let index: u64 = parse_arg(index, "the leaf index")?;
+    let leaf_count: u64 = parse_arg(leaf_count, "the number of leaves")?;
+
+    let proof = if proof == "-" {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text).map_err(|e| format!("could not read the proof: {}", e))?;
+        text
+    } else if Path::new(proof).is_file() {
+        fs::read_to_string(proof).map_err(|e| format!("{}: {}", proof, e))?
+    } else {
+        proof.clone()
+    };
+    let proof = parse_proof(&proof)?;
+    let root = parse_hash(root)?;
+    let leaf = match (&data, leaf) {
+        (Some(data), None) => with_hasher!(algorithm, H => H::hash(data.as_bytes())),
+        (None, Some(leaf)) => parse_hash(&leaf)?,
+        _ => return Err("verify expects the proven leaf as either --data or --leaf".to_string()),
+    };
+
+    let valid = with_hasher!(algorithm, H => {
+        check_proof::<H>(&proof, &leaf, index, leaf_count, &root, policy, data.as_deref())
+    });
+    if !valid {
+        process::exit(1);
     }
-    
-    
-    while hashes.len() > 1{
-        let mut next_level = Vec::new();
-        
-        let mut i = 0;
+    Ok(())
+}
 
-        println!("");
-        println!("CURRENT LEVEL");
-        for (i, h) in hashes.iter().enumerate(){
-            println!("Transaction {}: {}", i, hex::encode(h))
-        }
+// Output shared by the menu and the subcommands.
 
-        while i < hashes.len() {
+fn print_transactions(transactions: &[&str]) {
+    if transactions.is_empty() {
+        println!("No transactions");
+    }
+    for (index, text) in transactions.iter().enumerate() {
+        println!("  {:>3}: {}", index, text);
+    }
+}
 
-            if i + 1 < hashes.len() {
+/// Prints every level of the tree over `leaves`, then its root.
+fn print_tree<H: Hasher>(leaves: Vec<Hash>, policy: Policy) {
+    let tree = MerkleTree::<H>::from_leaves_with(leaves, policy);
 
-            println!("");
-            println!("Hashing ==[{}
-            || {}]==", hex::encode(hashes[i].clone()), hex::encode(hashes[i + 1].clone()));
+    println!();
+    println!("Using {} ({})", H::NAME, policy);
+    print!("{}", tree);
+    match tree.root() {
+        Some(root) => println!("Merkle root: {}", hex::encode(root)),
+        None => println!("The tree is empty"),
+    }
+}
 
-                let mut hasher = Keccak256::new();
-                hasher.update(&hashes[i]);
-                hasher.update(&hashes[i + 1]);
-                let result: Vec<u8> = hasher.finalize().to_vec();
-                println!("");
-                println!("Result: {}", hex::encode(&result));
-                next_level.push(result);
-            }else {
-                next_level.push(hashes[i].clone());
-            }
-            
-            i += 2;
+/// Prints the proof of leaf `index` as JSON and hex, or only as hex.
+fn print_proof<H: Hasher>(leaves: Vec<Hash>, index: usize, policy: Policy, hex_only: bool) -> Result<(), String> {
+    let tree = MerkleTree::<H>::from_leaves_with(leaves, policy);
+    let proof = tree
+        .proof(index)
+        .ok_or_else(|| format!("no leaf {}: there are {}", index, tree.len()))?;
+
+    if hex_only {
+        println!("{}", proof.to_hex());
+    } else {
+        println!("{}", proof.to_json());
+        println!("Hex: {}", proof.to_hex());
+        println!("Leaves: {}", tree.len());
+        println!("Root: {}", hex::encode(tree.root().expect("a tree with a proof has a root")));
+    }
+    Ok(())
+}
+
+/// Prints whether `proof` shows `leaf`, the hash of `data` if given, at
+/// `index` of a `leaf_count`-leaf tree with root `root`, and why not.
+fn check_proof<H: Hasher>(
+    proof: &MerkleProof,
+    leaf: &Hash,
+    index: u64,
+    leaf_count: u64,
+    root: &Hash,
+    policy: Policy,
+    data: Option<&str>,
+) -> bool {
+    let result = verify_proof::<H>(proof, leaf, index, leaf_count, root, policy);
+    let subject = match data {
+        Some(data) => format!("\"{}\"", data),
+        None => format!("0x{}", hex::encode(leaf)),
+    };
+    println!("{} is leaf {} of {} under the {} root: {}", subject, index, leaf_count, H::NAME, result.is_ok());
+    if let Err(err) = &result {
+        println!("Rejected: {}", err);
+    }
+    println!("Valid: {}", result.is_ok());
+    result.is_ok()
+}
+
+fn parse_proof(text: &str) -> Result<MerkleProof, String> {
+    let proof = if text.trim_start().starts_with('{') {
+        MerkleProof::from_json(text)
+    } else {
+        MerkleProof::from_hex(text)
+    };
+    proof.map_err(|e| e.to_string())
+}
+
+fn parse_hash(text: &str) -> Result<Hash, String> {
+    hex::decode(text.trim().trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("`{}` is not a 32-byte hex hash", text))
+}
+
+fn transaction_index(value: &str, count: usize) -> Result<usize, String> {
+    let index: usize = parse_arg(value, "the transaction index")?;
+    if index >= count {
+        return Err(format!("no transaction {}: there are {}", index, count));
+    }
+    Ok(index)
+}
+
+/// Transactions are stored one per line, so they cannot span lines or be blank.
+fn check_transaction(text: &str) -> Result<(), String> {
+    if text.trim().is_empty() || text.contains(['\n', '\r']) {
+        return Err(format!("invalid transaction {:?}: it must be one non-blank line", text));
+    }
+    Ok(())
+}
+
+/// Non-blank lines of a transactions file, as the `lines` input format reads them.
+fn read_transactions(path: &str) -> Result<Vec<String>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    Ok(text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(String::from)
+        .collect())
+}
+
+fn write_transactions(path: &str, transactions: &[String]) -> Result<(), String> {
+    let text: String = transactions.iter().map(|t| format!("{}\n", t)).collect();
+    fs::write(path, text).map_err(|e| format!("{}: {}", path, e))
+}
+
+// Argument parsing.
+
+/// Options saying how to read an input file.
+struct InputOptions {
+    format: Option<String>,
+    column: Option<String>,
+    record_size: Option<String>,
+    prehashed: bool,
+}
+
+fn take_input_options(args: &mut Vec<String>) -> Result<InputOptions, String> {
+    Ok(InputOptions {
+        format: take_option(args, "--format")?,
+        column: take_option(args, "--column")?,
+        record_size: take_option(args, "--record-size")?,
+        prehashed: take_flag(args, "--prehashed"),
+    })
+}
+
+fn take_tree_options(args: &mut Vec<String>) -> Result<(Algorithm, Policy), String> {
+    let algorithm = match take_option(args, "--hash")? {
+        Some(value) => value.parse()?,
+        None => Algorithm::default(),
+    };
+    let policy = match take_option(args, "--policy")? {
+        Some(value) => value.parse()?,
+        None => Policy::default(),
+    };
+    Ok((algorithm, policy))
+}
+
+/// Leaves read from `path`, or stdin for `-`.
+fn read_input<H: Hasher>(path: &str, options: &InputOptions) -> Result<Vec<Hash>, String> {
+    let mut format = match &options.format {
+        Some(value) => value.parse::<InputFormat>()?,
+        None => InputFormat::from_path(Path::new(path)),
+    };
+    match (&mut format, &options.column, &options.record_size) {
+        (InputFormat::Csv(column), Some(value), _) => *column = value.parse::<CsvColumn>()?,
+        (InputFormat::Binary { record_size }, _, Some(value)) => *record_size = parse_arg(value, "--record-size")?,
+        (_, Some(_), _) => return Err("--column only applies to csv input".to_string()),
+        (_, _, Some(_)) => return Err("--record-size only applies to binary input".to_string()),
+        _ => {}
+    }
+
+    if path == "-" {
+        read_leaves_from::<H, _>(io::stdin().lock(), &format, options.prehashed)
+    } else {
+        read_leaves::<H>(Path::new(path), &format, options.prehashed)
+    }
+    .map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Removes `name` from `args`, returning whether it was there.
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|arg| arg == name) {
+        Some(index) => {
+            args.remove(index);
+            true
         }
-        hashes = next_level;
+        None => false,
     }
+}
 
-    hashes.pop().unwrap()
+/// Removes `name <value>` from `args`, returning the value.
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, String> {
+    let Some(index) = args.iter().position(|arg| arg == name) else {
+        return Ok(None);
+    };
+    if index + 1 >= args.len() {
+        return Err(format!("{} expects a value", name));
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Ok(Some(value))
+}
 
+fn parse_arg<T: FromStr>(value: &str, name: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{}` for {}", value, name))
 }
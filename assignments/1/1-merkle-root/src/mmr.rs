@@ -0,0 +1,243 @@
+//! Merkle Mountain Range: an append-only list of perfect binary trees
+//! ("mountains") of decreasing size, one per set bit of the leaf count.
+//!
+//! Appending a leaf adds a mountain of one leaf and merges equal-sized
+//! mountains from the right, like carrying when incrementing a binary number.
+//! Nodes are stored in the order they are created, so the nodes of any
+//! earlier size are a prefix of the current ones. Old roots can therefore be
+//! recomputed, and proofs made for them stay valid forever.
+//!
+//! The root bags the peaks from the right: `H(p0, H(p1, ... H(pk-1, pk)))`.
+
+use std::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+
+use crate::hasher::{Hash, Hasher};
+use crate::proof::{hex_hash, hex_hashes, ProofError};
+
+/// Most leaves a range can hold. Beyond it, the nodes of the largest
+/// mountain can no longer be numbered in a `u64`.
+pub const MAX_LEAF_COUNT: u64 = (1 << 63) - 1;
+
+/// Proves that `leaf` is leaf `leaf_index` of the range when it had `leaf_count` leaves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MmrProof {
+    pub leaf_index: u64,
+    pub leaf_count: u64,
+    #[serde(with = "hex_hash")]
+    pub leaf: Hash,
+    /// Siblings from the leaf up to the peak of its mountain.
+    #[serde(with = "hex_hashes")]
+    pub siblings: Vec<Hash>,
+    /// Every other peak, left to right.
+    #[serde(with = "hex_hashes")]
+    pub peaks: Vec<Hash>,
+}
+
+pub struct MerkleMountainRange<H: Hasher> {
+    nodes: Vec<Hash>,
+    leaf_count: u64,
+    hasher: PhantomData<H>,
+}
+
+/// Mountain holding leaf `leaf_index` when there are `leaf_count` leaves.
+struct Mountain {
+    /// Position among the peaks, left to right.
+    peak: usize,
+    height: u32,
+    /// Index of the leaf within the mountain.
+    offset: u64,
+    /// Position of the mountain's first node.
+    start: u64,
+}
+
+impl Mountain {
+    fn find(leaf_index: u64, leaf_count: u64) -> Option<Self> {
+        if leaf_index >= leaf_count || leaf_count > MAX_LEAF_COUNT {
+            return None;
+        }
+
+        let (mut first_leaf, mut start) = (0, 0);
+        for (peak, height) in peak_heights(leaf_count).enumerate() {
+            let leaves = 1u64 << height;
+            if leaf_index < first_leaf + leaves {
+                return Some(Mountain {
+                    peak,
+                    height,
+                    offset: leaf_index - first_leaf,
+                    start,
+                });
+            }
+            first_leaf += leaves;
+            start += mountain_size(height);
+        }
+        unreachable!("the mountains cover every leaf below leaf_count")
+    }
+}
+
+impl<H: Hasher> MerkleMountainRange<H> {
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            leaf_count: 0,
+            hasher: PhantomData,
+        }
+    }
+
+    /// Appends a leaf and returns its index.
+    pub fn push(&mut self, leaf: Hash) -> u64 {
+        self.nodes.push(leaf);
+        // Each trailing one bit of the old count is a mountain the new leaf
+        // completes a pair with.
+        for height in 0..self.leaf_count.trailing_ones() {
+            let right = self.nodes[self.nodes.len() - 1];
+            let left = self.nodes[self.nodes.len() - 1 - mountain_size(height) as usize];
+            self.nodes.push(H::hash_pair(&left, &right));
+        }
+        self.leaf_count += 1;
+        self.leaf_count - 1
+    }
+
+    pub fn len(&self) -> u64 {
+        self.leaf_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaf_count == 0
+    }
+
+    /// Number of stored nodes, leaves included.
+    pub fn size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn peaks(&self) -> Vec<Hash> {
+        self.peaks_at(self.leaf_count).expect("the current size exists")
+    }
+
+    /// `None` while the range is empty.
+    pub fn root(&self) -> Option<Hash> {
+        self.root_at(self.leaf_count)
+    }
+
+    /// Root the range had when it held `leaf_count` leaves.
+    pub fn root_at(&self, leaf_count: u64) -> Option<Hash> {
+        bag_peaks::<H>(&self.peaks_at(leaf_count)?)
+    }
+
+    pub fn proof(&self, leaf_index: u64) -> Option<MmrProof> {
+        self.proof_at(leaf_index, self.leaf_count)
+    }
+
+    /// Proof of leaf `leaf_index` against `root_at(leaf_count)`.
+    pub fn proof_at(&self, leaf_index: u64, leaf_count: u64) -> Option<MmrProof> {
+        let mut peaks = self.peaks_at(leaf_count)?;
+        let mountain = Mountain::find(leaf_index, leaf_count)?;
+        peaks.remove(mountain.peak);
+
+        // Walk down from the peak, then list the siblings bottom-up.
+        let mut siblings = Vec::with_capacity(mountain.height as usize);
+        let mut start = mountain.start;
+        for height in (0..mountain.height).rev() {
+            let left_root = start + mountain_size(height) - 1;
+            let right_root = left_root + mountain_size(height);
+            if mountain.offset >> height & 1 == 1 {
+                siblings.push(self.nodes[left_root as usize]);
+                start = left_root + 1;
+            } else {
+                siblings.push(self.nodes[right_root as usize]);
+            }
+        }
+        siblings.reverse();
+
+        let leaf_position = mountain.start + 2 * mountain.offset - u64::from(mountain.offset.count_ones());
+        Some(MmrProof {
+            leaf_index,
+            leaf_count,
+            leaf: self.nodes[leaf_position as usize],
+            siblings,
+            peaks,
+        })
+    }
+
+    /// Peaks when the range held `leaf_count` leaves, `None` if it never did.
+    fn peaks_at(&self, leaf_count: u64) -> Option<Vec<Hash>> {
+        if leaf_count > self.leaf_count {
+            return None;
+        }
+
+        let mut end = 0;
+        Some(
+            peak_heights(leaf_count)
+                .map(|height| {
+                    end += mountain_size(height);
+                    self.nodes[end as usize - 1]
+                })
+                .collect(),
+        )
+    }
+}
+
+impl<H: Hasher> Default for MerkleMountainRange<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MmrProof {
+    /// Root obtained by climbing to the leaf's peak and bagging it with the
+    /// other peaks. `None` if the proof does not fit `leaf_count`, or
+    /// `leaf_count` is above `MAX_LEAF_COUNT`.
+    pub fn compute_root<H: Hasher>(&self) -> Option<Hash> {
+        let mountain = Mountain::find(self.leaf_index, self.leaf_count)?;
+        if self.siblings.len() != mountain.height as usize
+            || self.peaks.len() + 1 != self.leaf_count.count_ones() as usize
+        {
+            return None;
+        }
+
+        let peak = self.siblings.iter().enumerate().fold(self.leaf, |node, (height, sibling)| {
+            if mountain.offset >> height & 1 == 1 {
+                H::hash_pair(sibling, &node)
+            } else {
+                H::hash_pair(&node, sibling)
+            }
+        });
+
+        let mut peaks = self.peaks.clone();
+        peaks.insert(mountain.peak, peak);
+        bag_peaks::<H>(&peaks)
+    }
+
+    pub fn verify<H: Hasher>(&self, root: &Hash) -> bool {
+        self.compute_root::<H>() == Some(*root)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a proof always serializes")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ProofError> {
+        serde_json::from_str(json).map_err(|e| ProofError::InvalidJson(e.to_string()))
+    }
+}
+
+/// Heights of the mountains for `leaf_count` leaves, largest first.
+fn peak_heights(leaf_count: u64) -> impl Iterator<Item = u32> {
+    (0..u64::BITS).rev().filter(move |height| leaf_count >> height & 1 == 1)
+}
+
+/// Nodes in a mountain of the given height.
+fn mountain_size(height: u32) -> u64 {
+    (2 << height) - 1
+}
+
+fn bag_peaks<H: Hasher>(peaks: &[Hash]) -> Option<Hash> {
+    peaks
+        .iter()
+        .rev()
+        .copied()
+        .reduce(|right, left| H::hash_pair(&left, &right))
+}
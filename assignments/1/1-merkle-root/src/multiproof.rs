@@ -0,0 +1,128 @@
+//! Multiproofs: one proof for several leaves, where siblings the verifier can
+//! compute from the other leaves are left out. The layout is OpenZeppelin's,
+//! so a proof can be passed straight to `MerkleProof.multiProofVerify`.
+//!
+//! Works on trees stored root first in one array, node `i` having children
+//! `2i + 1` and `2i + 2`, with pairs hashed smaller hash first.
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::hasher::{Hash, Keccak256};
+use crate::proof::hex_hashes;
+use crate::tree::Policy;
+
+/// `leaves` in the order the verifier consumes them. Each flag says whether
+/// the next pair takes its second node from the computed nodes (`true`) or
+/// from `proof` (`false`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiProof {
+    #[serde(with = "hex_hashes")]
+    pub leaves: Vec<Hash>,
+    #[serde(with = "hex_hashes")]
+    pub proof: Vec<Hash>,
+    pub proof_flags: Vec<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultiProofError {
+    EmptyTree,
+    NotALeaf(usize),
+    DuplicateIndex(usize),
+    /// `leaves + proof` must be one more than the number of flags.
+    InvalidLength { leaves: usize, proof: usize, flags: usize },
+}
+
+impl fmt::Display for MultiProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MultiProofError::EmptyTree => write!(f, "an empty tree has nothing to prove"),
+            MultiProofError::NotALeaf(index) => write!(f, "index {} is not a leaf", index),
+            MultiProofError::DuplicateIndex(index) => write!(f, "index {} is proven twice", index),
+            MultiProofError::InvalidLength { leaves, proof, flags } => write!(
+                f,
+                "{} leaves and {} proof hashes do not fit {} flags",
+                leaves, proof, flags
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MultiProofError {}
+
+impl MultiProof {
+    /// Multiproof for the nodes at `indices` of `tree`, which must be leaves.
+    /// Leaves come out from the highest index down, as OpenZeppelin orders them.
+    pub fn generate(tree: &[Hash], indices: &[usize]) -> Result<Self, MultiProofError> {
+        if tree.is_empty() {
+            return Err(MultiProofError::EmptyTree);
+        }
+        let first_leaf = tree.len() / 2;
+        let mut indices = indices.to_vec();
+        if let Some(index) = indices.iter().find(|i| !(first_leaf..tree.len()).contains(i)) {
+            return Err(MultiProofError::NotALeaf(*index));
+        }
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        if let Some(pair) = indices.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(MultiProofError::DuplicateIndex(pair[0]));
+        }
+
+        let mut stack: VecDeque<usize> = indices.iter().copied().collect();
+        let mut proof = Vec::new();
+        let mut proof_flags = Vec::new();
+        while let Some(&node) = stack.front().filter(|node| **node > 0) {
+            stack.pop_front();
+            let sibling = if node % 2 == 1 { node + 1 } else { node - 1 };
+            if stack.front() == Some(&sibling) {
+                proof_flags.push(true);
+                stack.pop_front();
+            } else {
+                proof_flags.push(false);
+                proof.push(tree[sibling]);
+            }
+            stack.push_back((node - 1) / 2);
+        }
+        if indices.is_empty() {
+            proof.push(tree[0]);
+        }
+
+        Ok(Self {
+            leaves: indices.iter().map(|i| tree[*i]).collect(),
+            proof,
+            proof_flags,
+        })
+    }
+
+    /// Root the proof leads to, as `MerkleProof.processMultiProof` computes it.
+    pub fn compute_root(&self) -> Result<Hash, MultiProofError> {
+        let invalid = || MultiProofError::InvalidLength {
+            leaves: self.leaves.len(),
+            proof: self.proof.len(),
+            flags: self.proof_flags.len(),
+        };
+        if self.leaves.len() + self.proof.len() != self.proof_flags.len() + 1 {
+            return Err(invalid());
+        }
+
+        let mut stack: VecDeque<Hash> = self.leaves.iter().copied().collect();
+        let mut proof = self.proof.iter();
+        for flag in &self.proof_flags {
+            let a = stack.pop_front().ok_or_else(invalid)?;
+            let b = if *flag { stack.pop_front() } else { proof.next().copied() }.ok_or_else(invalid)?;
+            stack.push_back(Policy::SortedPairs.hash_pair::<Keccak256>(&a, &b));
+        }
+
+        // The length check leaves exactly one node: the root.
+        match (stack.pop_back(), proof.next()) {
+            (Some(root), None) | (None, Some(&root)) => Ok(root),
+            _ => Err(invalid()),
+        }
+    }
+
+    pub fn verify(&self, root: &Hash) -> Result<bool, MultiProofError> {
+        Ok(self.compute_root()? == *root)
+    }
+}
@@ -0,0 +1,56 @@
+//! Root computation for large leaf sets, spread across threads with rayon.
+//!
+//! Only the root is computed: no levels are kept, nothing is printed, and the
+//! hashes live in two buffers allocated up front. Each level is written into
+//! the spare buffer from pairs of the current one, then the two swap. Pairs
+//! are hashed in parallel and paired exactly as `MerkleTree` pairs them, so
+//! the root is the same for every `Policy`.
+
+use rayon::prelude::*;
+
+use crate::hasher::{Hash, Hasher};
+use crate::tree::Policy;
+
+/// Pairs one thread hashes at least, so small levels are not split into tasks
+/// that cost more to schedule than to hash.
+const MIN_PAIRS_PER_TASK: usize = 1024;
+
+/// Root over leaves that are already hashed, `None` if there are none.
+pub fn root<H: Hasher>(leaves: &[Hash], policy: Policy) -> Option<Hash> {
+    reduce::<H>(leaves.to_vec(), policy)
+}
+
+/// Root over raw items, hashing them in parallel first.
+pub fn root_from_data<H: Hasher, T: AsRef<[u8]> + Sync>(items: &[T], policy: Policy) -> Option<Hash> {
+    let mut leaves = Vec::with_capacity(items.len());
+    items
+        .par_iter()
+        .with_min_len(MIN_PAIRS_PER_TASK)
+        .map(|item| H::hash(item.as_ref()))
+        .collect_into_vec(&mut leaves);
+    reduce::<H>(leaves, policy)
+}
+
+/// Hashes `current` down to one node, reusing its buffer and one other.
+fn reduce<H: Hasher>(mut current: Vec<Hash>, policy: Policy) -> Option<Hash> {
+    let mut next = vec![[0u8; 32]; current.len().div_ceil(2)];
+    while current.len() > 1 {
+        let parents = current.len().div_ceil(2);
+        next[..parents]
+            .par_iter_mut()
+            .zip(current.par_chunks(2))
+            .with_min_len(MIN_PAIRS_PER_TASK)
+            .for_each(|(parent, pair)| {
+                *parent = match (pair, policy) {
+                    ([left, right], _) => policy.hash_pair::<H>(left, right),
+                    ([single], Policy::Duplicate) => H::hash_pair(single, single),
+                    ([single], _) => *single,
+                    _ => unreachable!("par_chunks(2) yields one or two nodes"),
+                }
+            });
+
+        std::mem::swap(&mut current, &mut next);
+        current.truncate(parents);
+    }
+    current.first().copied()
+}
@@ -0,0 +1,461 @@
+//! Ethereum's Merkle Patricia Trie, the structure behind `stateRoot`,
+//! `transactionsRoot` and `receiptsRoot` (Yellow Paper, appendix D).
+//!
+//! Keys are walked a nibble at a time. A branch node has one child per nibble
+//! plus a value, an extension node shares a run of nibbles among the keys
+//! below it, and a leaf node holds the rest of one key and its value. Paths in
+//! leaves and extensions are hex-prefix encoded. A node is RLP encoded, and
+//! its parent stores that encoding directly when it is under 32 bytes, or its
+//! Keccak-256 hash otherwise.
+//!
+//! A proof is the list of RLP-encoded nodes on a key's path, from the root
+//! down, leaving out nodes already inlined in their parent. It is the format of
+//! `accountProof` and `storageProof` in `eth_getProof`.
+
+use std::fmt;
+
+use rlp::{Rlp, RlpStream};
+
+use crate::hasher::{Hash, Hasher, Keccak256};
+
+/// Root of a trie with no keys: `keccak256(rlp(""))`.
+pub const EMPTY_TRIE_ROOT: Hash = [
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e, 0x5b, 0x48, 0xe0,
+    0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatriciaProofError {
+    /// The proof ends before the key's path does.
+    MissingNode,
+    /// Node `index` is not the one its parent (or the root) refers to.
+    HashMismatch { index: usize },
+    /// Node `index`, or a node inlined in it, is not a valid trie node.
+    InvalidNode { index: usize },
+    /// The path ends before the proof does.
+    UnusedNodes,
+}
+
+impl fmt::Display for PatriciaProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatriciaProofError::MissingNode => write!(f, "the proof ends before the key's path"),
+            PatriciaProofError::HashMismatch { index } => {
+                write!(f, "proof node {} does not match the hash its parent refers to", index)
+            }
+            PatriciaProofError::InvalidNode { index } => write!(f, "proof node {} is not a trie node", index),
+            PatriciaProofError::UnusedNodes => write!(f, "the proof has nodes past the end of the key's path"),
+        }
+    }
+}
+
+impl std::error::Error for PatriciaProofError {}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+enum Node {
+    #[default]
+    Empty,
+    Leaf {
+        path: Vec<u8>,
+        value: Vec<u8>,
+    },
+    Extension {
+        path: Vec<u8>,
+        child: Box<Node>,
+    },
+    Branch {
+        children: Box<[Node; 16]>,
+        value: Option<Vec<u8>>,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PatriciaTrie {
+    root: Node,
+    len: usize,
+}
+
+impl PatriciaTrie {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `EMPTY_TRIE_ROOT` while the trie is empty.
+    pub fn root(&self) -> Hash {
+        Keccak256::hash(&self.root.encode())
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        let path = nibbles(key);
+        let mut node = &self.root;
+        let mut rest = &path[..];
+        loop {
+            match node {
+                Node::Empty => return None,
+                Node::Leaf { path, value } => return (path[..] == *rest).then_some(&value[..]),
+                Node::Extension { path, child } => {
+                    rest = rest.strip_prefix(&path[..])?;
+                    node = child;
+                }
+                Node::Branch { children, value } => match rest.split_first() {
+                    None => return value.as_deref(),
+                    Some((nibble, tail)) => {
+                        node = &children[*nibble as usize];
+                        rest = tail;
+                    }
+                },
+            }
+        }
+    }
+
+    pub fn contains(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Sets `key` to `value`. As in Ethereum, an empty value removes the key.
+    pub fn insert(&mut self, key: &[u8], value: &[u8]) {
+        if value.is_empty() {
+            self.remove(key);
+            return;
+        }
+        if !self.contains(key) {
+            self.len += 1;
+        }
+        let root = std::mem::take(&mut self.root);
+        self.root = root.insert(&nibbles(key), value.to_vec());
+    }
+
+    /// Removes `key` and returns whether it was there.
+    pub fn remove(&mut self, key: &[u8]) -> bool {
+        if !self.contains(key) {
+            return false;
+        }
+        self.len -= 1;
+        let root = std::mem::take(&mut self.root);
+        self.root = root.remove(&nibbles(key));
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Nodes on the path to `key`, root first. If the key is absent, the path
+    /// stops where it leaves the trie, which proves the absence. An empty trie
+    /// gives an empty proof, as `eth_getProof` does.
+    pub fn prove(&self, key: &[u8]) -> Vec<Vec<u8>> {
+        let path = nibbles(key);
+        let mut proof = Vec::new();
+        let mut node = &self.root;
+        let mut rest = &path[..];
+        while *node != Node::Empty {
+            let encoded = node.encode();
+            if proof.is_empty() || encoded.len() >= 32 {
+                proof.push(encoded);
+            }
+
+            match node {
+                Node::Empty | Node::Leaf { .. } => break,
+                Node::Extension { path, child } => match rest.strip_prefix(&path[..]) {
+                    Some(tail) => {
+                        node = child;
+                        rest = tail;
+                    }
+                    None => break,
+                },
+                Node::Branch { children, .. } => match rest.split_first() {
+                    Some((nibble, tail)) => {
+                        node = &children[*nibble as usize];
+                        rest = tail;
+                    }
+                    None => break,
+                },
+            }
+        }
+        proof
+    }
+}
+
+/// Child reference read out of a proof node.
+enum Reference {
+    Hash(Hash),
+    Inline(Vec<u8>),
+    Absent,
+}
+
+/// Follows `key` through `proof` from `root` and returns its value, or `None`
+/// if the proof shows the key is not in the trie.
+pub fn verify_proof(root: &Hash, key: &[u8], proof: &[Vec<u8>]) -> Result<Option<Vec<u8>>, PatriciaProofError> {
+    if proof.is_empty() {
+        return if *root == EMPTY_TRIE_ROOT {
+            Ok(None)
+        } else {
+            Err(PatriciaProofError::MissingNode)
+        };
+    }
+
+    let path = nibbles(key);
+    let mut rest = &path[..];
+    let mut nodes = proof.iter().enumerate();
+    let mut reference = Reference::Hash(*root);
+    let mut index = 0;
+    let value = loop {
+        let encoded = match reference {
+            Reference::Absent => break None,
+            Reference::Inline(encoded) => encoded,
+            Reference::Hash(hash) => {
+                let (next, encoded) = nodes.next().ok_or(PatriciaProofError::MissingNode)?;
+                index = next;
+                if Keccak256::hash(encoded) != hash {
+                    return Err(PatriciaProofError::HashMismatch { index });
+                }
+                encoded.clone()
+            }
+        };
+
+        let invalid = |_| PatriciaProofError::InvalidNode { index };
+        let node = Rlp::new(&encoded);
+        if node.is_empty() {
+            break None;
+        }
+        match node.item_count().map_err(invalid)? {
+            17 => match rest.split_first() {
+                None => {
+                    let value = node.at(16).and_then(|value| value.data().map(<[u8]>::to_vec)).map_err(invalid)?;
+                    break (!value.is_empty()).then_some(value);
+                }
+                Some((nibble, tail)) => {
+                    reference = read_reference(&node.at(*nibble as usize).map_err(invalid)?, index)?;
+                    rest = tail;
+                }
+            },
+            2 => {
+                let prefix = node.at(0).and_then(|prefix| prefix.data().map(<[u8]>::to_vec)).map_err(invalid)?;
+                let (path, leaf) = decode_hex_prefix(&prefix).ok_or(PatriciaProofError::InvalidNode { index })?;
+                if leaf {
+                    if path[..] != *rest {
+                        break None;
+                    }
+                    break Some(node.at(1).and_then(|value| value.data().map(<[u8]>::to_vec)).map_err(invalid)?);
+                }
+                match rest.strip_prefix(&path[..]) {
+                    None => break None,
+                    Some(tail) => {
+                        reference = read_reference(&node.at(1).map_err(invalid)?, index)?;
+                        rest = tail;
+                    }
+                }
+            }
+            _ => return Err(PatriciaProofError::InvalidNode { index }),
+        }
+    };
+
+    match nodes.next() {
+        Some(_) => Err(PatriciaProofError::UnusedNodes),
+        None => Ok(value),
+    }
+}
+
+fn read_reference(item: &Rlp, index: usize) -> Result<Reference, PatriciaProofError> {
+    if item.is_list() {
+        return Ok(Reference::Inline(item.as_raw().to_vec()));
+    }
+    match item.data() {
+        Ok([]) => Ok(Reference::Absent),
+        Ok(hash) if hash.len() == 32 => Ok(Reference::Hash(hash.try_into().expect("checked length"))),
+        _ => Err(PatriciaProofError::InvalidNode { index }),
+    }
+}
+
+impl Node {
+    fn insert(self, path: &[u8], value: Vec<u8>) -> Node {
+        match self {
+            Node::Empty => Node::Leaf {
+                path: path.to_vec(),
+                value,
+            },
+            Node::Leaf { path: existing, .. } if existing == path => Node::Leaf { path: existing, value },
+            Node::Leaf { path: existing, value: old } => {
+                let shared = common_prefix(&existing, path);
+                let branch = Node::branch().insert(&existing[shared..], old).insert(&path[shared..], value);
+                Node::extension(&path[..shared], branch)
+            }
+            Node::Extension { path: existing, child } => {
+                let shared = common_prefix(&existing, path);
+                if shared == existing.len() {
+                    return Node::Extension {
+                        child: Box::new(child.insert(&path[shared..], value)),
+                        path: existing,
+                    };
+                }
+
+                // The extension splits: what is left of it hangs off a new branch.
+                let mut children: Box<[Node; 16]> = Box::default();
+                children[existing[shared] as usize] = Node::extension(&existing[shared + 1..], *child);
+                let branch = Node::Branch { children, value: None }.insert(&path[shared..], value);
+                Node::extension(&path[..shared], branch)
+            }
+            Node::Branch { mut children, value: old } => match path.split_first() {
+                None => Node::Branch {
+                    children,
+                    value: Some(value),
+                },
+                Some((nibble, rest)) => {
+                    let child = std::mem::take(&mut children[*nibble as usize]);
+                    children[*nibble as usize] = child.insert(rest, value);
+                    Node::Branch { children, value: old }
+                }
+            },
+        }
+    }
+
+    /// Removes `path`, which must be in the trie, and merges the nodes left
+    /// with a single child so the trie stays in its canonical shape.
+    fn remove(self, path: &[u8]) -> Node {
+        match self {
+            Node::Empty | Node::Leaf { .. } => Node::Empty,
+            Node::Extension { path: existing, child } => {
+                let child = child.remove(&path[existing.len()..]);
+                Node::extension(&existing, child)
+            }
+            Node::Branch { mut children, value } => {
+                let value = match path.split_first() {
+                    None => None,
+                    Some((nibble, rest)) => {
+                        let child = std::mem::take(&mut children[*nibble as usize]);
+                        children[*nibble as usize] = child.remove(rest);
+                        value
+                    }
+                };
+
+                let mut occupied = children.iter().enumerate().filter(|(_, child)| **child != Node::Empty);
+                match (occupied.next(), occupied.next(), value) {
+                    (None, _, None) => Node::Empty,
+                    (None, _, Some(value)) => Node::Leaf { path: Vec::new(), value },
+                    (Some((nibble, _)), None, None) => {
+                        let child = std::mem::take(&mut children[nibble]);
+                        Node::extension(&[nibble as u8], child)
+                    }
+                    (_, _, value) => Node::Branch { children, value },
+                }
+            }
+        }
+    }
+
+    fn branch() -> Node {
+        Node::Branch {
+            children: Box::default(),
+            value: None,
+        }
+    }
+
+    /// `child` reached through `path`, merging the path into a leaf or
+    /// extension child and leaving out empty paths.
+    fn extension(path: &[u8], child: Node) -> Node {
+        match child {
+            _ if path.is_empty() => child,
+            Node::Empty => Node::Empty,
+            Node::Leaf { path: rest, value } => Node::Leaf {
+                path: [path, &rest].concat(),
+                value,
+            },
+            Node::Extension { path: rest, child } => Node::Extension {
+                path: [path, &rest].concat(),
+                child,
+            },
+            branch => Node::Extension {
+                path: path.to_vec(),
+                child: Box::new(branch),
+            },
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut stream = RlpStream::new();
+        match self {
+            Node::Empty => {
+                stream.append_empty_data();
+            }
+            Node::Leaf { path, value } => {
+                stream.begin_list(2);
+                stream.append(&hex_prefix(path, true));
+                stream.append(value);
+            }
+            Node::Extension { path, child } => {
+                stream.begin_list(2);
+                stream.append(&hex_prefix(path, false));
+                child.append_reference(&mut stream);
+            }
+            Node::Branch { children, value } => {
+                stream.begin_list(17);
+                for child in children.iter() {
+                    child.append_reference(&mut stream);
+                }
+                match value {
+                    Some(value) => stream.append(value),
+                    None => stream.append_empty_data(),
+                };
+            }
+        }
+        stream.out().to_vec()
+    }
+
+    /// How a parent refers to this node: inline below 32 bytes, else by hash.
+    fn append_reference(&self, stream: &mut RlpStream) {
+        if *self == Node::Empty {
+            stream.append_empty_data();
+            return;
+        }
+        let encoded = self.encode();
+        if encoded.len() < 32 {
+            stream.append_raw(&encoded, 1);
+        } else {
+            stream.append(&Keccak256::hash(&encoded).to_vec());
+        }
+    }
+}
+
+/// Hex-prefix encoding (Yellow Paper, appendix C): the nibbles packed into
+/// bytes behind a flag nibble recording whether the path belongs to a leaf and
+/// whether its length is odd. An odd path shares its first byte with the flag.
+pub fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 2 } else { 0 };
+    let mut out = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        out.push((flag + 1) << 4 | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        out.push(flag << 4);
+        nibbles
+    };
+    out.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+    out
+}
+
+/// The nibbles of a hex-prefixed path and whether it belongs to a leaf.
+fn decode_hex_prefix(encoded: &[u8]) -> Option<(Vec<u8>, bool)> {
+    let (first, rest) = encoded.split_first()?;
+    let flag = first >> 4;
+    if flag > 3 || (flag & 1 == 0 && first & 0x0f != 0) {
+        return None;
+    }
+
+    let mut path = Vec::with_capacity(rest.len() * 2 + 1);
+    if flag & 1 == 1 {
+        path.push(first & 0x0f);
+    }
+    path.extend(nibbles(rest));
+    Some((path, flag & 2 == 2))
+}
+
+fn nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect()
+}
+
+fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
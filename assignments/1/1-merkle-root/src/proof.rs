@@ -0,0 +1,287 @@
+//! Inclusion proofs: the sibling hashes on the path from a leaf to the root.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::hasher::{Hash, Hasher};
+use crate::tree::Policy;
+
+/// Side of the path node the sibling sits on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofStep {
+    #[serde(with = "hex_hash")]
+    pub hash: Hash,
+    pub position: Side,
+}
+
+/// Proves that `leaf` is the leaf at `leaf_index`. Levels where the path
+/// node had no sibling and was promoted contribute no step; under
+/// `Policy::Duplicate` the node itself is the sibling.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleProof {
+    pub leaf_index: u64,
+    #[serde(with = "hex_hash")]
+    pub leaf: Hash,
+    pub siblings: Vec<ProofStep>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProofError {
+    InvalidHex(String),
+    InvalidJson(String),
+    /// The binary encoding must be 40 bytes plus 33 per step.
+    InvalidLength(usize),
+    InvalidSide(u8),
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofError::InvalidHex(msg) => write!(f, "invalid proof hex: {}", msg),
+            ProofError::InvalidJson(msg) => write!(f, "invalid proof JSON: {}", msg),
+            ProofError::InvalidLength(len) => {
+                write!(f, "a {} byte proof is not 40 bytes plus 33 bytes per sibling", len)
+            }
+            ProofError::InvalidSide(byte) => write!(f, "sibling position must be 0 or 1, got {}", byte),
+        }
+    }
+}
+
+impl std::error::Error for ProofError {}
+
+/// Why `verify_proof` rejected a proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The claimed index is not inside a tree of `leaf_count` leaves.
+    IndexOutOfRange { index: u64, leaf_count: u64 },
+    /// The proof is for another position than the one being checked.
+    WrongIndex { expected: u64, found: u64 },
+    /// The proof's leaf is not the hash of the data being checked.
+    WrongLeaf,
+    /// A leaf at this index and tree size has `expected` siblings.
+    WrongLength { expected: usize, found: usize },
+    /// Sibling `step` is on the other side of the path than the index implies.
+    WrongSide { step: usize },
+    /// Under `Policy::Duplicate`, sibling `step` must repeat the path node.
+    NotDuplicate { step: usize },
+    WrongRoot,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::IndexOutOfRange { index, leaf_count } => {
+                write!(f, "leaf {} is outside a tree of {} leaves", index, leaf_count)
+            }
+            VerifyError::WrongIndex { expected, found } => {
+                write!(f, "the proof is for leaf {}, not leaf {}", found, expected)
+            }
+            VerifyError::WrongLeaf => write!(f, "the proof is for another leaf"),
+            VerifyError::WrongLength { expected, found } => {
+                write!(f, "the path needs {} siblings, the proof has {}", expected, found)
+            }
+            VerifyError::WrongSide { step } => write!(f, "sibling {} is on the wrong side", step),
+            VerifyError::NotDuplicate { step } => write!(f, "sibling {} does not repeat the path node", step),
+            VerifyError::WrongRoot => write!(f, "the path leads to another root"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+impl MerkleProof {
+    /// Root obtained by hashing the leaf with every sibling in turn, pairing
+    /// them as `policy` does.
+    pub fn compute_root<H: Hasher>(&self, policy: Policy) -> Hash {
+        self.siblings.iter().fold(self.leaf, |node, step| match step.position {
+            Side::Left => policy.hash_pair::<H>(&step.hash, &node),
+            Side::Right => policy.hash_pair::<H>(&node, &step.hash),
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a proof always serializes")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ProofError> {
+        serde_json::from_str(json).map_err(|e| ProofError::InvalidJson(e.to_string()))
+    }
+
+    /// Binary layout: leaf index as 8 big-endian bytes, the leaf, then per
+    /// sibling one position byte (0 = left, 1 = right) and its hash.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(40 + 33 * self.siblings.len());
+        out.extend_from_slice(&self.leaf_index.to_be_bytes());
+        out.extend_from_slice(&self.leaf);
+        for step in &self.siblings {
+            out.push(match step.position {
+                Side::Left => 0,
+                Side::Right => 1,
+            });
+            out.extend_from_slice(&step.hash);
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProofError> {
+        if bytes.len() < 40 || !(bytes.len() - 40).is_multiple_of(33) {
+            return Err(ProofError::InvalidLength(bytes.len()));
+        }
+
+        let leaf_index = u64::from_be_bytes(bytes[..8].try_into().expect("8 bytes"));
+        let leaf = bytes[8..40].try_into().expect("32 bytes");
+        let siblings = bytes[40..]
+            .chunks(33)
+            .map(|chunk| {
+                let position = match chunk[0] {
+                    0 => Side::Left,
+                    1 => Side::Right,
+                    other => return Err(ProofError::InvalidSide(other)),
+                };
+                Ok(ProofStep {
+                    hash: chunk[1..].try_into().expect("32 bytes"),
+                    position,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            leaf_index,
+            leaf,
+            siblings,
+        })
+    }
+
+    /// `to_bytes` as a `0x`-prefixed hex string.
+    pub fn to_hex(&self) -> String {
+        format!("0x{}", hex::encode(self.to_bytes()))
+    }
+
+    pub fn from_hex(value: &str) -> Result<Self, ProofError> {
+        let bytes = hex::decode(value.trim().trim_start_matches("0x")).map_err(|e| ProofError::InvalidHex(e.to_string()))?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// Checks that `proof` shows `leaf` at `leaf_index` of a `leaf_count`-leaf
+/// tree with the given root, without needing the tree. The sides of the path
+/// come from the index, not from the proof, so a proof for another position or
+/// with reordered siblings is rejected. `policy` must be the one the tree was
+/// built with; under `Policy::SortedPairs` sides do not affect the root and are
+/// not checked.
+pub fn verify_proof<H: Hasher>(
+    proof: &MerkleProof,
+    leaf: &Hash,
+    leaf_index: u64,
+    leaf_count: u64,
+    root: &Hash,
+    policy: Policy,
+) -> Result<(), VerifyError> {
+    if leaf_index >= leaf_count {
+        return Err(VerifyError::IndexOutOfRange {
+            index: leaf_index,
+            leaf_count,
+        });
+    }
+    if proof.leaf_index != leaf_index {
+        return Err(VerifyError::WrongIndex {
+            expected: leaf_index,
+            found: proof.leaf_index,
+        });
+    }
+    if proof.leaf != *leaf {
+        return Err(VerifyError::WrongLeaf);
+    }
+
+    let path = path_sides(leaf_index, leaf_count, policy);
+    if path.len() != proof.siblings.len() {
+        return Err(VerifyError::WrongLength {
+            expected: path.len(),
+            found: proof.siblings.len(),
+        });
+    }
+
+    let mut node = *leaf;
+    for (step, (sibling, (side, duplicate))) in proof.siblings.iter().zip(path).enumerate() {
+        if policy != Policy::SortedPairs && sibling.position != side {
+            return Err(VerifyError::WrongSide { step });
+        }
+        if duplicate && sibling.hash != node {
+            return Err(VerifyError::NotDuplicate { step });
+        }
+        node = match side {
+            Side::Left => policy.hash_pair::<H>(&sibling.hash, &node),
+            Side::Right => policy.hash_pair::<H>(&node, &sibling.hash),
+        };
+    }
+
+    if node != *root {
+        return Err(VerifyError::WrongRoot);
+    }
+    Ok(())
+}
+
+/// Side of every sibling on the path from `index`, level by level, and whether
+/// it is the path node itself (an unpaired node under `Policy::Duplicate`).
+/// Unpaired nodes under the other policies are promoted and have no sibling.
+fn path_sides(mut index: u64, mut count: u64, policy: Policy) -> Vec<(Side, bool)> {
+    let mut sides = Vec::new();
+    while count > 1 {
+        if index % 2 == 1 {
+            sides.push((Side::Left, false));
+        } else if index + 1 < count {
+            sides.push((Side::Right, false));
+        } else if policy == Policy::Duplicate {
+            sides.push((Side::Right, true));
+        }
+        index /= 2;
+        count = count.div_ceil(2);
+    }
+    sides
+}
+
+/// Serializes a hash as a `0x`-prefixed hex string.
+pub(crate) mod hex_hash {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use crate::hasher::Hash;
+
+    pub fn serialize<S: Serializer>(hash: &Hash, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{}", hex::encode(hash)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Hash, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        let bytes = hex::decode(value.trim_start_matches("0x")).map_err(de::Error::custom)?;
+        bytes
+            .try_into()
+            .map_err(|_| de::Error::custom(format!("`{}` is not a 32-byte hash", value)))
+    }
+}
+
+/// `hex_hash` for a list of hashes.
+pub(crate) mod hex_hashes {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::hasher::Hash;
+
+    #[derive(Serialize, Deserialize)]
+    struct Hex(#[serde(with = "super::hex_hash")] Hash);
+
+    pub fn serialize<S: Serializer>(hashes: &[Hash], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(hashes.iter().map(|hash| Hex(*hash)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Hash>, D::Error> {
+        Ok(Vec::<Hex>::deserialize(deserializer)?.into_iter().map(|Hex(hash)| hash).collect())
+    }
+}
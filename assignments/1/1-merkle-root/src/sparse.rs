@@ -0,0 +1,263 @@
+//! Sparse Merkle tree: a tree with one leaf for every 32-byte key, almost all
+//! of them empty.
+//!
+//! The bits of a key, most significant first, are the path from the root to
+//! its leaf, so a key's position never depends on what else is in the tree.
+//! That is what allows proving a key is absent: its leaf is empty. An empty
+//! subtree of height `h` hashes to the precomputed `default_hashes[h]`, and only
+//! nodes that differ from those are stored.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+
+use crate::hasher::{Hash, Hasher};
+use crate::proof::{hex_hash, hex_hashes};
+
+/// Levels below the root; one per key bit.
+pub const SPARSE_TREE_DEPTH: usize = 256;
+
+/// Value of an empty leaf. Storing it removes the key.
+pub const EMPTY_LEAF: Hash = [0u8; 32];
+
+/// Proof of the value at `key`, `EMPTY_LEAF` proving the key is absent.
+/// Siblings equal to the default hash of their height are left out: bit `h`
+/// of `bitmap` (counting from the least significant bit of the last byte) is
+/// set when the sibling at height `h` is included.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SparseProof {
+    #[serde(with = "hex_hash")]
+    pub key: Hash,
+    #[serde(with = "hex_hash")]
+    pub value: Hash,
+    #[serde(with = "hex_hash")]
+    pub bitmap: Hash,
+    /// Included siblings, from the leaf up.
+    #[serde(with = "hex_hashes")]
+    pub siblings: Vec<Hash>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SparseProofError {
+    /// The bitmap and the number of siblings disagree.
+    SiblingCount { bitmap: usize, siblings: usize },
+}
+
+impl fmt::Display for SparseProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SparseProofError::SiblingCount { bitmap, siblings } => {
+                write!(f, "bitmap marks {} siblings but the proof has {}", bitmap, siblings)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SparseProofError {}
+
+pub struct SparseMerkleTree<H: Hasher> {
+    /// Non-default nodes by height and by key with the bits below that
+    /// height cleared. Leaves are height 0.
+    nodes: HashMap<(usize, Hash), Hash>,
+    default_hashes: Vec<Hash>,
+    len: usize,
+    hasher: PhantomData<H>,
+}
+
+impl<H: Hasher> SparseMerkleTree<H> {
+    pub fn new() -> Self {
+        Self {
+            nodes: HashMap::new(),
+            default_hashes: default_hashes::<H>(),
+            len: 0,
+            hasher: PhantomData,
+        }
+    }
+
+    pub fn root(&self) -> Hash {
+        self.node(SPARSE_TREE_DEPTH, &EMPTY_LEAF)
+    }
+
+    /// Value at `key`, `EMPTY_LEAF` if it was never set.
+    pub fn get(&self, key: &Hash) -> Hash {
+        self.node(0, key)
+    }
+
+    pub fn contains(&self, key: &Hash) -> bool {
+        self.get(key) != EMPTY_LEAF
+    }
+
+    /// Number of keys with a value.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// `default_hashes()[h]` is the root of an empty subtree of height `h`.
+    pub fn default_hashes(&self) -> &[Hash] {
+        &self.default_hashes
+    }
+
+    pub fn insert(&mut self, key: Hash, value: Hash) {
+        self.update([(key, value)]);
+    }
+
+    pub fn remove(&mut self, key: &Hash) {
+        self.update([(*key, EMPTY_LEAF)]);
+    }
+
+    /// Sets many keys at once. Nodes shared by several keys' paths are hashed
+    /// once per batch instead of once per key. Later entries for the same key win.
+    pub fn update<I: IntoIterator<Item = (Hash, Hash)>>(&mut self, entries: I) {
+        let mut dirty = BTreeSet::new();
+        for (key, value) in entries {
+            match (self.contains(&key), value != EMPTY_LEAF) {
+                (false, true) => self.len += 1,
+                (true, false) => self.len -= 1,
+                _ => {}
+            }
+            self.set_node(0, key, value);
+            dirty.insert(key);
+        }
+
+        for height in 0..SPARSE_TREE_DEPTH {
+            let parents: BTreeSet<Hash> = dirty.iter().map(|prefix| clear_low_bits(prefix, height + 1)).collect();
+            for parent in &parents {
+                let left = self.node(height, parent);
+                let right = self.node(height, &with_bit(parent, height));
+                self.set_node(height + 1, *parent, H::hash_pair(&left, &right));
+            }
+            dirty = parents;
+        }
+    }
+
+    /// Proof of the value at `key`: inclusion if it is set, exclusion if not.
+    pub fn prove(&self, key: &Hash) -> SparseProof {
+        let mut bitmap = [0u8; 32];
+        let mut siblings = Vec::new();
+        for height in 0..SPARSE_TREE_DEPTH {
+            let sibling_prefix = flip_bit(&clear_low_bits(key, height), height);
+            let sibling = self.node(height, &sibling_prefix);
+            if sibling != self.default_hashes[height] {
+                bitmap = with_bit(&bitmap, height);
+                siblings.push(sibling);
+            }
+        }
+
+        SparseProof {
+            key: *key,
+            value: self.get(key),
+            bitmap,
+            siblings,
+        }
+    }
+
+    fn node(&self, height: usize, prefix: &Hash) -> Hash {
+        self.nodes
+            .get(&(height, *prefix))
+            .copied()
+            .unwrap_or(self.default_hashes[height])
+    }
+
+    /// Stores a node, or forgets it if it went back to the default.
+    fn set_node(&mut self, height: usize, prefix: Hash, hash: Hash) {
+        if hash == self.default_hashes[height] {
+            self.nodes.remove(&(height, prefix));
+        } else {
+            self.nodes.insert((height, prefix), hash);
+        }
+    }
+}
+
+impl<H: Hasher> Default for SparseMerkleTree<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SparseProof {
+    /// Whether this proves the key is in the tree rather than absent.
+    pub fn is_inclusion(&self) -> bool {
+        self.value != EMPTY_LEAF
+    }
+
+    /// Root obtained by hashing the value up the key's path.
+    pub fn compute_root<H: Hasher>(&self) -> Result<Hash, SparseProofError> {
+        let marked = self.bitmap.iter().map(|byte| byte.count_ones() as usize).sum();
+        if marked != self.siblings.len() {
+            return Err(SparseProofError::SiblingCount {
+                bitmap: marked,
+                siblings: self.siblings.len(),
+            });
+        }
+
+        let defaults = default_hashes::<H>();
+        let mut siblings = self.siblings.iter();
+        let mut node = self.value;
+        for (height, default) in defaults.iter().enumerate().take(SPARSE_TREE_DEPTH) {
+            let sibling = if bit(&self.bitmap, height) {
+                *siblings.next().expect("counted above")
+            } else {
+                *default
+            };
+            node = if bit(&self.key, height) {
+                H::hash_pair(&sibling, &node)
+            } else {
+                H::hash_pair(&node, &sibling)
+            };
+        }
+        Ok(node)
+    }
+
+    /// Checks the proof against `root`. Whether it shows inclusion or exclusion
+    /// is up to `value`; see `is_inclusion`.
+    pub fn verify<H: Hasher>(&self, root: &Hash) -> bool {
+        self.compute_root::<H>().is_ok_and(|computed| computed == *root)
+    }
+}
+
+/// `default_hashes[h]` for every height from the leaves (0) to the root (256).
+fn default_hashes<H: Hasher>() -> Vec<Hash> {
+    let mut hashes = Vec::with_capacity(SPARSE_TREE_DEPTH + 1);
+    hashes.push(EMPTY_LEAF);
+    for height in 0..SPARSE_TREE_DEPTH {
+        hashes.push(H::hash_pair(&hashes[height], &hashes[height]));
+    }
+    hashes
+}
+
+/// Bit `index` counting from the least significant bit of the last byte. At
+/// height `h` a key's path turns right when bit `h` is set.
+fn bit(value: &Hash, index: usize) -> bool {
+    value[31 - index / 8] & (1 << (index % 8)) != 0
+}
+
+fn with_bit(value: &Hash, index: usize) -> Hash {
+    let mut out = *value;
+    out[31 - index / 8] |= 1 << (index % 8);
+    out
+}
+
+fn flip_bit(value: &Hash, index: usize) -> Hash {
+    let mut out = *value;
+    out[31 - index / 8] ^= 1 << (index % 8);
+    out
+}
+
+/// `value` with its lowest `count` bits cleared: the prefix shared by every
+/// key under a node of height `count`.
+fn clear_low_bits(value: &Hash, count: usize) -> Hash {
+    let mut out = *value;
+    let whole_bytes = count / 8;
+    out[32 - whole_bytes..].fill(0);
+    if whole_bytes < 32 {
+        out[31 - whole_bytes] &= !((1u8 << (count % 8)) - 1);
+    }
+    out
+}
@@ -0,0 +1,414 @@
+//! Trees built exactly like `StandardMerkleTree` from `@openzeppelin/merkle-tree`,
+//! so their roots and proofs are accepted by OpenZeppelin's `MerkleProof.verify`.
+//!
+//! A leaf is `keccak256(keccak256(abi.encode(value...)))`; hashing twice keeps
+//! a leaf from ever being read as a 64-byte internal node. Leaves are sorted
+//! by hash and stored with the nodes in one array, root first, where node `i`
+//! has children `2i + 1` and `2i + 2`. Pairs are hashed smaller hash first.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use ethereum_types::U256;
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use crate::hasher::{Hash, Hasher, Keccak256};
+use crate::multiproof::{MultiProof, MultiProofError};
+use crate::proof::hex_hashes;
+use crate::tree::Policy;
+
+/// `format` field of the JSON dump.
+pub const FORMAT: &str = "standard-v1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StandardTreeError {
+    /// Leaves can be `address`, `bool`, `uint<N>`, `bytes<N>`, `bytes` and `string`.
+    UnsupportedType(String),
+    InvalidValue { ty: String, value: String },
+    ValueCount { expected: usize, got: usize },
+    NoValues,
+    InvalidDump(String),
+    InvalidIndex(usize),
+    MultiProof(MultiProofError),
+}
+
+impl fmt::Display for StandardTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StandardTreeError::UnsupportedType(ty) => write!(f, "leaf type `{}` is not supported", ty),
+            StandardTreeError::InvalidValue { ty, value } => write!(f, "`{}` is not a valid {}", value, ty),
+            StandardTreeError::ValueCount { expected, got } => {
+                write!(f, "the leaf encoding has {} types, the value has {}", expected, got)
+            }
+            StandardTreeError::NoValues => write!(f, "a tree needs at least one value"),
+            StandardTreeError::InvalidDump(msg) => write!(f, "invalid tree dump: {}", msg),
+            StandardTreeError::InvalidIndex(index) => write!(f, "there is no value {}", index),
+            StandardTreeError::MultiProof(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for StandardTreeError {}
+
+impl From<MultiProofError> for StandardTreeError {
+    fn from(err: MultiProofError) -> Self {
+        StandardTreeError::MultiProof(err)
+    }
+}
+
+/// A value and where its leaf sits in the tree array.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedValue {
+    #[serde(deserialize_with = "json_values")]
+    pub value: Vec<String>,
+    pub tree_index: usize,
+}
+
+/// A `MultiProof` carrying the proven values instead of their leaf hashes,
+/// as `StandardMerkleTree.getMultiProof` returns it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StandardMultiProof {
+    #[serde(deserialize_with = "json_leaves")]
+    pub leaves: Vec<Vec<String>>,
+    #[serde(with = "hex_hashes")]
+    pub proof: Vec<Hash>,
+    pub proof_flags: Vec<bool>,
+}
+
+/// The JSON written by `StandardMerkleTree.dump()`, fields in the same order.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Dump {
+    format: String,
+    tree: Vec<String>,
+    values: Vec<IndexedValue>,
+    leaf_encoding: Vec<String>,
+}
+
+pub struct StandardMerkleTree {
+    tree: Vec<Hash>,
+    values: Vec<IndexedValue>,
+    leaf_encoding: Vec<String>,
+}
+
+impl StandardMerkleTree {
+    /// Builds a tree over `values`, each ABI-encoded as the types in
+    /// `leaf_encoding`, e.g. `["address", "uint256"]`.
+    pub fn of<V, S>(values: &[V], leaf_encoding: &[&str]) -> Result<Self, StandardTreeError>
+    where
+        V: AsRef<[S]>,
+        S: AsRef<str>,
+    {
+        if values.is_empty() {
+            return Err(StandardTreeError::NoValues);
+        }
+
+        let mut hashed = values
+            .iter()
+            .enumerate()
+            .map(|(value_index, value)| Ok((leaf_hash(leaf_encoding, value.as_ref())?, value_index)))
+            .collect::<Result<Vec<_>, StandardTreeError>>()?;
+        hashed.sort();
+
+        // Leaves fill the end of the array in reverse, then every node above
+        // them is filled from the back.
+        let mut tree = vec![[0u8; 32]; 2 * hashed.len() - 1];
+        let last = tree.len() - 1;
+        for (leaf_index, (hash, _)) in hashed.iter().enumerate() {
+            tree[last - leaf_index] = *hash;
+        }
+        for i in (0..tree.len() - hashed.len()).rev() {
+            tree[i] = hash_pair(&tree[2 * i + 1], &tree[2 * i + 2]);
+        }
+
+        let mut indexed: Vec<IndexedValue> = values
+            .iter()
+            .map(|value| IndexedValue {
+                value: value.as_ref().iter().map(|item| item.as_ref().to_string()).collect(),
+                tree_index: 0,
+            })
+            .collect();
+        for (leaf_index, (_, value_index)) in hashed.iter().enumerate() {
+            indexed[*value_index].tree_index = last - leaf_index;
+        }
+
+        Ok(Self {
+            tree,
+            values: indexed,
+            leaf_encoding: leaf_encoding.iter().map(|ty| ty.to_string()).collect(),
+        })
+    }
+
+    pub fn root(&self) -> Hash {
+        self.tree[0]
+    }
+
+    pub fn leaf_encoding(&self) -> &[String] {
+        &self.leaf_encoding
+    }
+
+    /// Values in the order they were given.
+    pub fn values(&self) -> &[IndexedValue] {
+        &self.values
+    }
+
+    /// Every node, root first.
+    pub fn nodes(&self) -> &[Hash] {
+        &self.tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Index of `value`, compared by leaf hash so that e.g. differently
+    /// checksummed addresses still match.
+    pub fn index_of<S: AsRef<str>>(&self, value: &[S]) -> Result<Option<usize>, StandardTreeError> {
+        let hash = leaf_hash(&self.leaf_encoding, value)?;
+        Ok(self.values.iter().position(|v| self.tree[v.tree_index] == hash))
+    }
+
+    /// Proof for the value at `index`, the `bytes32[]` to pass to
+    /// `MerkleProof.verify`.
+    pub fn proof(&self, index: usize) -> Option<Vec<Hash>> {
+        let mut node = self.values.get(index)?.tree_index;
+        let mut proof = Vec::new();
+        while node > 0 {
+            let sibling = if node % 2 == 1 { node + 1 } else { node - 1 };
+            proof.push(self.tree[sibling]);
+            node = (node - 1) / 2;
+        }
+        Some(proof)
+    }
+
+    /// One proof for the values at `indices`, for `MerkleProof.multiProofVerify`.
+    /// The values come back in the order the contract needs their leaves.
+    pub fn multi_proof(&self, indices: &[usize]) -> Result<StandardMultiProof, StandardTreeError> {
+        let tree_indices = indices
+            .iter()
+            .map(|index| self.values.get(*index).map(|v| v.tree_index).ok_or(StandardTreeError::InvalidIndex(*index)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let proof = MultiProof::generate(&self.tree, &tree_indices)?;
+
+        let by_tree_index: HashMap<usize, &IndexedValue> = self.values.iter().map(|v| (v.tree_index, v)).collect();
+        let mut leaves = tree_indices;
+        leaves.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(StandardMultiProof {
+            leaves: leaves.iter().map(|i| by_tree_index[i].value.clone()).collect(),
+            proof: proof.proof,
+            proof_flags: proof.proof_flags,
+        })
+    }
+
+    /// Same bytes as `JSON.stringify(tree.dump())`.
+    pub fn to_json(&self) -> String {
+        let dump = Dump {
+            format: FORMAT.to_string(),
+            tree: self.tree.iter().map(|hash| format!("0x{}", hex::encode(hash))).collect(),
+            values: self.values.clone(),
+            leaf_encoding: self.leaf_encoding.clone(),
+        };
+        serde_json::to_string(&dump).expect("a tree always serializes")
+    }
+
+    /// Loads a dump made by this type or by `StandardMerkleTree.dump()`, and
+    /// checks that every leaf and node hashes to what the dump says.
+    pub fn from_json(json: &str) -> Result<Self, StandardTreeError> {
+        let invalid = |msg: String| StandardTreeError::InvalidDump(msg);
+        let dump: Dump = serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+        if dump.format != FORMAT {
+            return Err(invalid(format!("unknown format `{}`", dump.format)));
+        }
+        if dump.values.is_empty() {
+            return Err(StandardTreeError::NoValues);
+        }
+
+        let tree = dump
+            .tree
+            .iter()
+            .map(|node| {
+                hex::decode(node.trim_start_matches("0x"))
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| invalid(format!("`{}` is not a 32-byte hash", node)))
+            })
+            .collect::<Result<Vec<Hash>, _>>()?;
+        if tree.len() != 2 * dump.values.len() - 1 {
+            return Err(invalid(format!("{} values need {} nodes, not {}", dump.values.len(), 2 * dump.values.len() - 1, tree.len())));
+        }
+
+        let leaves = tree.len() - dump.values.len()..tree.len();
+        for value in &dump.values {
+            if !leaves.contains(&value.tree_index) {
+                return Err(invalid(format!("tree index {} is not a leaf", value.tree_index)));
+            }
+            if leaf_hash(&dump.leaf_encoding, &value.value)? != tree[value.tree_index] {
+                return Err(invalid(format!("leaf {} does not match its value", value.tree_index)));
+            }
+        }
+        for i in 0..leaves.start {
+            if hash_pair(&tree[2 * i + 1], &tree[2 * i + 2]) != tree[i] {
+                return Err(invalid(format!("node {} does not match its children", i)));
+            }
+        }
+
+        Ok(Self {
+            tree,
+            values: dump.values,
+            leaf_encoding: dump.leaf_encoding,
+        })
+    }
+}
+
+/// `keccak256(bytes.concat(keccak256(abi.encode(value...))))`.
+pub fn leaf_hash<T: AsRef<str>, S: AsRef<str>>(leaf_encoding: &[T], value: &[S]) -> Result<Hash, StandardTreeError> {
+    if leaf_encoding.len() != value.len() {
+        return Err(StandardTreeError::ValueCount {
+            expected: leaf_encoding.len(),
+            got: value.len(),
+        });
+    }
+
+    // Static values sit in the head; dynamic ones are appended after it, the
+    // head holding their offset.
+    let head_size = 32 * value.len();
+    let mut head = Vec::with_capacity(head_size);
+    let mut tail = Vec::new();
+    for (ty, item) in leaf_encoding.iter().zip(value) {
+        let (ty, item) = (ty.as_ref(), item.as_ref());
+        let invalid = || StandardTreeError::InvalidValue {
+            ty: ty.to_string(),
+            value: item.to_string(),
+        };
+        match ty {
+            "string" | "bytes" => {
+                let bytes = match ty {
+                    "string" => item.as_bytes().to_vec(),
+                    _ => parse_hex(item).ok_or_else(invalid)?,
+                };
+                head.extend_from_slice(&word(U256::from(head_size + tail.len())));
+                tail.extend_from_slice(&word(U256::from(bytes.len())));
+                tail.extend_from_slice(&bytes);
+                tail.resize(tail.len().next_multiple_of(32), 0);
+            }
+            _ => head.extend_from_slice(&encode_static(ty, item)?),
+        }
+    }
+
+    head.extend(tail);
+    Ok(Keccak256::hash(&Keccak256::hash(&head)))
+}
+
+/// ABI word for a value of a static type.
+fn encode_static(ty: &str, value: &str) -> Result<[u8; 32], StandardTreeError> {
+    let invalid = || StandardTreeError::InvalidValue {
+        ty: ty.to_string(),
+        value: value.to_string(),
+    };
+    let unsupported = || StandardTreeError::UnsupportedType(ty.to_string());
+
+    let mut out = [0u8; 32];
+    if ty == "address" {
+        let bytes = parse_hex(value).filter(|b| b.len() == 20).ok_or_else(invalid)?;
+        out[12..].copy_from_slice(&bytes);
+    } else if ty == "bool" {
+        out[31] = match value {
+            "true" => 1,
+            "false" => 0,
+            _ => return Err(invalid()),
+        };
+    } else if let Some(bits) = ty.strip_prefix("uint") {
+        let bits = match bits {
+            "" => 256,
+            _ => bits.parse::<usize>().ok().filter(|b| (8..=256).contains(b) && b % 8 == 0).ok_or_else(unsupported)?,
+        };
+        let n = match value.strip_prefix("0x") {
+            Some(digits) => U256::from_str_radix(digits, 16).ok(),
+            None => U256::from_dec_str(value).ok(),
+        };
+        out = word(n.filter(|n| n.bits() <= bits).ok_or_else(invalid)?);
+    } else if let Some(size) = ty.strip_prefix("bytes") {
+        let size = size.parse::<usize>().ok().filter(|s| (1..=32).contains(s)).ok_or_else(unsupported)?;
+        let bytes = parse_hex(value).filter(|b| b.len() == size).ok_or_else(invalid)?;
+        out[..size].copy_from_slice(&bytes);
+    } else {
+        return Err(unsupported());
+    }
+    Ok(out)
+}
+
+fn word(value: U256) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    value.to_big_endian(&mut out);
+    out
+}
+
+fn parse_hex(value: &str) -> Option<Vec<u8>> {
+    hex::decode(value.strip_prefix("0x")?).ok()
+}
+
+/// Root reached from `leaf` through `proof`, as `MerkleProof.processProof` computes it.
+pub fn process_proof(leaf: &Hash, proof: &[Hash]) -> Hash {
+    proof.iter().fold(*leaf, |node, sibling| hash_pair(&node, sibling))
+}
+
+/// Checks that `value` is in the tree with `root`, as `MerkleProof.verify` would.
+pub fn verify<T: AsRef<str>, S: AsRef<str>>(
+    root: &Hash,
+    leaf_encoding: &[T],
+    value: &[S],
+    proof: &[Hash],
+) -> Result<bool, StandardTreeError> {
+    Ok(process_proof(&leaf_hash(leaf_encoding, value)?, proof) == *root)
+}
+
+/// Checks that every value in `proof` is in the tree with `root`, as
+/// `MerkleProof.multiProofVerify` would.
+pub fn verify_multi_proof<T: AsRef<str>>(
+    root: &Hash,
+    leaf_encoding: &[T],
+    proof: &StandardMultiProof,
+) -> Result<bool, StandardTreeError> {
+    let leaves = proof
+        .leaves
+        .iter()
+        .map(|value| leaf_hash(leaf_encoding, value))
+        .collect::<Result<_, _>>()?;
+    let proof = MultiProof {
+        leaves,
+        proof: proof.proof.clone(),
+        proof_flags: proof.proof_flags.clone(),
+    };
+    Ok(proof.verify(root)?)
+}
+
+fn hash_pair(left: &Hash, right: &Hash) -> Hash {
+    Policy::SortedPairs.hash_pair::<Keccak256>(left, right)
+}
+
+/// JS dumps keep values as the caller gave them, so numbers and booleans
+/// may appear unquoted.
+fn json_values<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Vec::<serde_json::Value>::deserialize(deserializer)?
+        .into_iter()
+        .map(|item| match item {
+            serde_json::Value::String(s) => Ok(s),
+            serde_json::Value::Number(n) => Ok(n.to_string()),
+            serde_json::Value::Bool(b) => Ok(b.to_string()),
+            other => Err(de::Error::custom(format!("unsupported leaf value {}", other))),
+        })
+        .collect()
+}
+
+fn json_leaves<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<String>>, D::Error> {
+    #[derive(Deserialize)]
+    struct Leaf(#[serde(deserialize_with = "json_values")] Vec<String>);
+
+    Ok(Vec::<Leaf>::deserialize(deserializer)?.into_iter().map(|Leaf(value)| value).collect())
+}
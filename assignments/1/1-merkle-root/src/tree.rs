@@ -0,0 +1,209 @@
+//! A binary Merkle tree that keeps every level in memory.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use crate::hasher::{Hash, Hasher};
+use crate::proof::{MerkleProof, ProofStep, Side};
+
+/// How nodes are paired when building a level. Trees built by other systems
+/// only match ours when both use the same policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Policy {
+    /// A node without a sibling is promoted to the next level unchanged.
+    #[default]
+    Promote,
+    /// A node without a sibling is hashed with itself, as Bitcoin does.
+    Duplicate,
+    /// Every pair is hashed smaller hash first and a node without a sibling is
+    /// promoted, as OpenZeppelin's `MerkleProof` expects. Proof positions do
+    /// not matter under this policy.
+    SortedPairs,
+}
+
+impl Policy {
+    pub const ALL: [Policy; 3] = [Policy::Promote, Policy::Duplicate, Policy::SortedPairs];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Policy::Promote => "promote",
+            Policy::Duplicate => "duplicate",
+            Policy::SortedPairs => "sorted-pairs",
+        }
+    }
+
+    /// Parent of `left` and `right` under this policy.
+    pub fn hash_pair<H: Hasher>(self, left: &Hash, right: &Hash) -> Hash {
+        match self {
+            Policy::SortedPairs if right < left => H::hash_pair(right, left),
+            _ => H::hash_pair(left, right),
+        }
+    }
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl FromStr for Policy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_ascii_lowercase();
+        Policy::ALL
+            .into_iter()
+            .find(|policy| policy.name() == lower)
+            .ok_or_else(|| format!("unknown policy `{}`", s))
+    }
+}
+
+/// Level 0 holds the leaf hashes and the last level holds the root. How
+/// nodes are paired depends on the tree's `Policy`.
+pub struct MerkleTree<H: Hasher> {
+    levels: Vec<Vec<Hash>>,
+    policy: Policy,
+    hasher: PhantomData<H>,
+}
+
+impl<H: Hasher> MerkleTree<H> {
+    /// Builds a tree over leaves that are already hashed, promoting nodes
+    /// without a sibling.
+    pub fn from_leaves(leaves: Vec<Hash>) -> Self {
+        Self::from_leaves_with(leaves, Policy::Promote)
+    }
+
+    /// Builds a tree over leaves that are already hashed, pairing nodes as
+    /// `policy` says.
+    pub fn from_leaves_with(leaves: Vec<Hash>, policy: Policy) -> Self {
+        let mut levels = vec![leaves];
+
+        while levels[levels.len() - 1].len() > 1 {
+            let next = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| match (pair, policy) {
+                    ([left, right], _) => policy.hash_pair::<H>(left, right),
+                    ([single], Policy::Duplicate) => H::hash_pair(single, single),
+                    ([single], _) => *single,
+                    _ => unreachable!("chunks(2) yields one or two nodes"),
+                })
+                .collect();
+            levels.push(next);
+        }
+
+        Self {
+            levels,
+            policy,
+            hasher: PhantomData,
+        }
+    }
+
+    /// Hashes every item with `H` and builds a tree over the results.
+    pub fn from_data<I, T>(items: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        Self::from_data_with(items, Policy::Promote)
+    }
+
+    /// `from_data` with an explicit pairing policy.
+    pub fn from_data_with<I, T>(items: I, policy: Policy) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        Self::from_leaves_with(items.into_iter().map(|item| H::hash(item.as_ref())).collect(), policy)
+    }
+
+    pub fn policy(&self) -> Policy {
+        self.policy
+    }
+
+    /// `None` for a tree without leaves.
+    pub fn root(&self) -> Option<Hash> {
+        self.levels.last().and_then(|level| level.first()).copied()
+    }
+
+    pub fn leaves(&self) -> &[Hash] {
+        &self.levels[0]
+    }
+
+    pub fn leaf(&self, index: usize) -> Option<&Hash> {
+        self.node(0, index)
+    }
+
+    /// Node `index` of `level`, counting the leaves as level 0.
+    pub fn node(&self, level: usize, index: usize) -> Option<&Hash> {
+        self.levels.get(level)?.get(index)
+    }
+
+    /// Every level from the leaves up to the root.
+    pub fn levels(&self) -> &[Vec<Hash>] {
+        &self.levels
+    }
+
+    /// Number of levels above the leaves.
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    /// Inclusion proof for the leaf at `index`, or `None` if there is no such
+    /// leaf. Check it with the tree's policy.
+    pub fn proof(&self, index: usize) -> Option<MerkleProof> {
+        let leaf = *self.leaf(index)?;
+        let mut siblings = Vec::with_capacity(self.depth());
+        let mut position = index;
+
+        for level in &self.levels[..self.depth()] {
+            let sibling = position ^ 1;
+            match level.get(sibling) {
+                Some(hash) => siblings.push(ProofStep {
+                    hash: *hash,
+                    position: if sibling < position { Side::Left } else { Side::Right },
+                }),
+                // The unpaired node was hashed with itself.
+                None if self.policy == Policy::Duplicate => siblings.push(ProofStep {
+                    hash: level[position],
+                    position: Side::Right,
+                }),
+                None => {}
+            }
+            position /= 2;
+        }
+
+        Some(MerkleProof {
+            leaf_index: index as u64,
+            leaf,
+            siblings,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels[0].is_empty()
+    }
+}
+
+/// Prints every level, root first.
+impl<H: Hasher> fmt::Display for MerkleTree<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (level, nodes) in self.levels.iter().enumerate().rev() {
+            let label = match level {
+                0 => String::from("Leaves"),
+                _ if level == self.depth() => String::from("Root"),
+                _ => format!("Level {}", level),
+            };
+            writeln!(f, "{}", label)?;
+            for (index, node) in nodes.iter().enumerate() {
+                writeln!(f, "  {:>3}: {}", index, hex::encode(node))?;
+            }
+        }
+        Ok(())
+    }
+}
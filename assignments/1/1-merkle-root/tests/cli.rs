@@ -0,0 +1,137 @@
+//! The binary driven the way a script would: through subcommands, and through
+//! the interactive menu with its answers piped to stdin.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+use merkle_root::hasher::{Hasher, Keccak256, Sha256};
+use merkle_root::tree::MerkleTree;
+
+const DEMO: [&str; 5] = [
+    "Alice sent 2 eth",
+    "Jacks sent 1 eth",
+    "Mike sent 8 eth",
+    "Richard sent 2 eth",
+    "Key sent 2 eth",
+];
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_merkle_root"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn scratch_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("merkle_root_cli_{}_{}", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn root_hex<H: Hasher>(transactions: &[&str]) -> String {
+    hex::encode(MerkleTree::<H>::from_data(transactions).root().unwrap())
+}
+
+#[test]
+fn subcommands_edit_a_block_and_prove_its_transactions() {
+    let path = scratch_file("block.txt");
+    let file = path.to_str().unwrap();
+
+    stdout(&run(&["add", file, DEMO[0], DEMO[1], DEMO[2], "Bob sent 1 eth"], ""));
+    stdout(&run(&["add", file, DEMO[3], DEMO[4]], ""));
+    assert!(stdout(&run(&["remove", file, "3"], "")).contains("Bob sent 1 eth"));
+    let list = stdout(&run(&["list", file], ""));
+    assert_eq!(list.lines().count(), 5);
+    assert!(list.contains("  2: Mike sent 8 eth"));
+
+    let root = root_hex::<Keccak256>(&DEMO);
+    let output = stdout(&run(&["root", file, "--hash", "keccak256"], ""));
+    assert!(output.contains(&root), "{}", output);
+    assert!(stdout(&run(&["tree", file, "--hash", "keccak256"], "")).ends_with(&format!("Merkle root: {}\n", root)));
+
+    let proof = stdout(&run(&["prove", file, "2", "--hash", "keccak256", "--hex"], ""));
+    let valid = run(&["verify", proof.trim(), &root, "2", "5", "--hash", "keccak256", "--data", DEMO[2]], "");
+    let valid = stdout(&valid);
+    assert!(valid.contains("\"Mike sent 8 eth\" is leaf 2 of 5 under the keccak256 root: true"), "{}", valid);
+    assert!(valid.ends_with("Valid: true\n"));
+
+    // The wrong transaction, index, size or hash fails with status 1.
+    let wrong_data = run(&["verify", proof.trim(), &root, "2", "5", "--hash", "keccak256", "--data", DEMO[3]], "");
+    assert_eq!(wrong_data.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&wrong_data.stdout).contains("Rejected: the proof is for another leaf"));
+    let wrong_index = run(&["verify", proof.trim(), &root, "3", "5", "--hash", "keccak256", "--data", DEMO[2]], "");
+    assert_eq!(wrong_index.status.code(), Some(1));
+    let wrong_size = run(&["verify", proof.trim(), &root, "2", "4", "--hash", "keccak256", "--data", DEMO[2]], "");
+    assert_eq!(wrong_size.status.code(), Some(1));
+
+    let json = stdout(&run(&["prove", file, "2", "--hash", "keccak256"], ""));
+    assert!(json.contains("\nLeaves: 5\n"));
+    let leaf = format!("0x{}", hex::encode(Keccak256::hash(DEMO[2].as_bytes())));
+    let json = json.split("\nHex:").next().unwrap();
+    let wrong_hash = run(&["verify", "-", &root, "2", "5", "--leaf", &leaf], json);
+    assert_eq!(wrong_hash.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&wrong_hash.stdout).ends_with("Valid: false\n"));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn bad_arguments_print_usage() {
+    let bad = [
+        &["frobnicate"][..],
+        &["prove", "-", "x"],
+        &["root", "-", "--hash", "md5"],
+        &["add", "f.txt"],
+        &["verify", "0x00", "00", "0", "1"],
+    ];
+    for args in bad {
+        let output = run(args, "");
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.starts_with("error: ") && stderr.contains("Usage:"), "{}", stderr);
+    }
+}
+
+#[test]
+fn menu_edits_the_block_and_checks_proofs() {
+    // Prove Mike's transaction in the demo block with the subcommands, then
+    // check it from the menu after switching to keccak256.
+    let path = scratch_file("demo.txt");
+    let file = path.to_str().unwrap();
+    stdout(&run(&[&["add", file][..], &DEMO].concat(), ""));
+    let proof = stdout(&run(&["prove", file, "2", "--hash", "keccak256", "--hex"], ""));
+    std::fs::remove_file(&path).unwrap();
+
+    let answers = format!(
+        "2\nBob sent 1 eth\n3\n5\n1\n4\n2\n7\n{}\n\n2\n5\n6\n9\n3\n0\nq\nnever read\n",
+        proof.trim()
+    );
+    let output = stdout(&run(&[], &answers));
+
+    assert!(output.contains("Added transaction 5"));
+    assert!(output.contains("Removed \"Bob sent 1 eth\""));
+    assert!(output.contains("Using keccak256"));
+    assert!(output.contains("\"Mike sent 8 eth\" is leaf 2 of 5 under the keccak256 root: true"));
+    assert!(output.contains(&format!("Merkle root: {}", root_hex::<Keccak256>(&DEMO))));
+    assert!(output.contains("error: no transaction 9: there are 5"));
+    assert!(output.contains("Removed \"Alice sent 2 eth\""));
+    assert!(!output.contains(&root_hex::<Sha256>(&DEMO)));
+}
+
+#[test]
+fn menu_stops_at_the_end_of_input() {
+    let output = stdout(&run(&[], "1\n2\n"));
+    assert!(output.contains("  4: Key sent 2 eth"));
+    assert!(output.ends_with("Transaction: "));
+}
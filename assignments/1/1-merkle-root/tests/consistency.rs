@@ -0,0 +1,126 @@
+//! RFC 6962 consistency proofs, checked against the Certificate Transparency
+//! reference test data.
+
+use merkle_root::consistency::{ConsistencyError, ConsistencyProof};
+use merkle_root::hasher::{Hash, Rfc6962, Sha256};
+use merkle_root::tree::{MerkleTree, Policy};
+
+type Log = MerkleTree<Rfc6962<Sha256>>;
+
+const LEAVES: [&str; 8] = ["", "00", "10", "2021", "3031", "40414243", "5051525354555657", "606162636465666768696a6b6c6d6e6f"];
+
+fn log(size: usize) -> Log {
+    Log::from_data(LEAVES[..size].iter().map(|leaf| hex::decode(leaf).unwrap()))
+}
+
+/// A log of `size` numbered entries, for sizes past the reference data.
+fn entries(size: usize) -> Log {
+    Log::from_data((0..size).map(|i| format!("escrow event {}", i)))
+}
+
+fn hash(value: &str) -> Hash {
+    hex::decode(value).unwrap().try_into().unwrap()
+}
+
+#[test]
+fn matches_reference_proofs() {
+    let cases: [(usize, usize, Vec<&str>); 4] = [
+        (1, 1, vec![]),
+        (
+            1,
+            8,
+            vec![
+                "96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7",
+                "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                "6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4",
+            ],
+        ),
+        (
+            6,
+            8,
+            vec![
+                "0ebc5d3437fbe2db158b9f126a1d118e308181031d0a949f8dededebc558ef6a",
+                "ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0",
+                "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+            ],
+        ),
+        (
+            2,
+            5,
+            vec![
+                "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+            ],
+        ),
+    ];
+
+    for (old_size, new_size, expected) in cases {
+        let proof = log(new_size).consistency_proof(old_size).unwrap();
+        assert_eq!(proof.hashes, expected.iter().map(|h| hash(h)).collect::<Vec<_>>(), "{} -> {}", old_size, new_size);
+        assert!(proof.verify::<Rfc6962<Sha256>>(&log(old_size).root().unwrap(), &log(new_size).root().unwrap()));
+    }
+}
+
+#[test]
+fn every_prefix_is_consistent() {
+    for new_size in 1..=40 {
+        let new = entries(new_size);
+        for old_size in 1..=new_size {
+            let proof = new.consistency_proof(old_size).unwrap();
+            let old_root = entries(old_size).root().unwrap();
+            assert!(proof.verify::<Rfc6962<Sha256>>(&old_root, &new.root().unwrap()), "{} -> {}", old_size, new_size);
+        }
+    }
+}
+
+#[test]
+fn rewritten_history_is_rejected() {
+    let new = entries(13);
+    let mut rewritten: Vec<String> = (0..7).map(|i| format!("escrow event {}", i)).collect();
+    rewritten[3] = String::from("escrow event 3, amended");
+    let old_root = Log::from_data(&rewritten).root().unwrap();
+
+    let proof = new.consistency_proof(7).unwrap();
+    assert!(!proof.verify::<Rfc6962<Sha256>>(&old_root, &new.root().unwrap()));
+}
+
+#[test]
+fn tampered_proofs_are_rejected() {
+    let (old, new) = (entries(6), entries(11));
+    let (old_root, new_root) = (old.root().unwrap(), new.root().unwrap());
+    let proof = new.consistency_proof(6).unwrap();
+
+    for i in 0..proof.hashes.len() {
+        let mut tampered = proof.clone();
+        tampered.hashes[i][0] ^= 1;
+        assert!(!tampered.verify::<Rfc6962<Sha256>>(&old_root, &new_root), "hash {}", i);
+    }
+
+    let mut truncated = proof.clone();
+    truncated.hashes.pop();
+    assert!(!truncated.verify::<Rfc6962<Sha256>>(&old_root, &new_root));
+
+    let resized = ConsistencyProof { new_size: 40, ..proof.clone() };
+    assert!(!resized.verify::<Rfc6962<Sha256>>(&old_root, &new_root));
+    assert!(!proof.verify::<Rfc6962<Sha256>>(&new_root, &old_root));
+}
+
+#[test]
+fn rejects_impossible_sizes_and_other_policies() {
+    assert_eq!(
+        entries(5).consistency_proof(6).unwrap_err(),
+        ConsistencyError::InvalidSizes { old_size: 6, new_size: 5 }
+    );
+    assert!(entries(5).consistency_proof(0).is_err());
+
+    let sorted = MerkleTree::<Rfc6962<Sha256>>::from_data_with(["a", "b", "c"], Policy::SortedPairs);
+    assert_eq!(sorted.consistency_proof(2).unwrap_err(), ConsistencyError::UnsupportedPolicy(Policy::SortedPairs));
+}
+
+#[test]
+fn json_round_trips() {
+    let proof = entries(9).consistency_proof(4).unwrap();
+
+    assert_eq!(ConsistencyProof::from_json(&proof.to_json()).unwrap(), proof);
+    assert!(proof.to_json().contains("\"oldSize\": 4"));
+}
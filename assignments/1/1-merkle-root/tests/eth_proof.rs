@@ -0,0 +1,201 @@
+//! `eth_getProof` responses checked against the Holesky genesis header, and
+//! ones generated here.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ethereum_types::U256;
+use merkle_root::eth_proof::{header_state_root, Account, AccountProof, Address, EthProofError, WorldState, EMPTY_CODE_HASH};
+use merkle_root::hasher::{Hash, Hasher, Keccak256, Sha256};
+use merkle_root::patricia::{PatriciaProofError, EMPTY_TRIE_ROOT};
+use serde_json::Value;
+
+/// The published hash of the Holesky genesis block.
+const HOLESKY_GENESIS_HASH: &str = "b5f7f912443c940f21fd611f12828d75b534364ed9e95ca4e307729a4661bde4";
+
+fn fixture_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/eth_getProof").join(name)
+}
+
+fn fixture(name: &str) -> AccountProof {
+    AccountProof::from_json(&fs::read_to_string(fixture_path(name)).unwrap()).unwrap()
+}
+
+fn root(hex: &str) -> Hash {
+    hex::decode(hex).unwrap().try_into().unwrap()
+}
+
+fn quantity(hex: &str) -> U256 {
+    U256::from_str_radix(hex.trim_start_matches("0x"), 16).unwrap()
+}
+
+/// State root of the Holesky genesis header, once the header hashes to the
+/// published genesis hash.
+fn holesky_state_root() -> Hash {
+    let header = fs::read_to_string(fixture_path("holesky_genesis_header.hex")).unwrap();
+    let header = hex::decode(header.trim().trim_start_matches("0x")).unwrap();
+    header_state_root(&header, &root(HOLESKY_GENESIS_HASH)).unwrap()
+}
+
+fn address(last: u8) -> Address {
+    let mut address = [0u8; 20];
+    address[19] = last;
+    address
+}
+
+/// An account holding ether and a contract with three storage slots.
+fn escrow_state() -> WorldState {
+    let mut state = WorldState::new();
+    state.set_account(address(1), 1, U256::exp10(18), EMPTY_CODE_HASH);
+    state.set_account(address(2), 1, U256::zero(), Keccak256::hash(b"code"));
+    state.set_storage(address(2), U256::from(0), U256::from(42));
+    state.set_storage(address(2), U256::from(1), U256::from(0xdeadbeefu64));
+    state.set_storage(address(2), U256::from(7), U256::exp10(18));
+    state
+}
+
+#[test]
+fn verifies_holesky_responses_against_the_genesis_header() {
+    let state_root = holesky_state_root();
+    assert_eq!(hex::encode(state_root), "69d8c9d72f6fa4ad42d4702b433707212f90db395eb54dc20bc85de253788783");
+
+    // The deposit contract stores zero_hashes[1] in slot 0x22 and nothing in slot 0.
+    let deposit_contract = fixture("holesky_deposit_contract.json");
+    deposit_contract.verify(&state_root).unwrap();
+    let zero_hash_1 = Sha256::hash(&[0u8; 64]);
+    assert_eq!(deposit_contract.storage_proof[0].value, U256::from_big_endian(&zero_hash_1));
+    assert_eq!(deposit_contract.storage_proof[1].value, U256::zero());
+
+    let funded = fixture("holesky_funded_account.json");
+    funded.verify(&state_root).unwrap();
+    assert_eq!(funded.account.storage_root, EMPTY_TRIE_ROOT);
+    assert_eq!(funded.account.code_hash, EMPTY_CODE_HASH);
+
+    let missing = fixture("holesky_missing_account.json");
+    missing.verify(&state_root).unwrap();
+    assert_eq!(missing.account, Account::default());
+    assert!(missing.storage_proof[0].proof.is_empty());
+}
+
+#[test]
+fn rejects_tampered_responses() {
+    let state_root = holesky_state_root();
+    let proof = fixture("holesky_deposit_contract.json");
+
+    let mut richer = proof.clone();
+    richer.account.balance = U256::from(1);
+    assert!(matches!(richer.verify(&state_root), Err(EthProofError::AccountMismatch)));
+
+    let mut slot = proof.clone();
+    slot.storage_proof[1].value = U256::from(1);
+    assert!(matches!(slot.verify(&state_root), Err(EthProofError::StorageMismatch { .. })));
+
+    let mut truncated = proof.clone();
+    truncated.account_proof.pop();
+    assert!(matches!(
+        truncated.verify(&state_root),
+        Err(EthProofError::AccountProof(PatriciaProofError::MissingNode))
+    ));
+
+    let other_root = Keccak256::hash(b"another block");
+    assert!(matches!(
+        proof.verify(&other_root),
+        Err(EthProofError::AccountProof(PatriciaProofError::HashMismatch { index: 0 }))
+    ));
+}
+
+/// Rebuilds the Holesky genesis allocation and compares its root with the
+/// state root committed to by the genesis header.
+#[test]
+fn genesis_state_matches_the_header() {
+    let genesis = fs::read_to_string(fixture_path("holesky_genesis.json")).unwrap();
+    let genesis: Value = serde_json::from_str(&genesis).unwrap();
+    let mut state = WorldState::new();
+    for (address, account) in genesis["alloc"].as_object().unwrap() {
+        let address: Address = hex::decode(address.trim_start_matches("0x")).unwrap().try_into().unwrap();
+        let code_hash = match account["code"].as_str() {
+            Some(code) => Keccak256::hash(&hex::decode(code.trim_start_matches("0x")).unwrap()),
+            None => EMPTY_CODE_HASH,
+        };
+        state.set_account(address, 0, quantity(account["balance"].as_str().unwrap()), code_hash);
+        for (slot, value) in account["storage"].as_object().into_iter().flatten() {
+            state.set_storage(address, quantity(slot), quantity(value.as_str().unwrap()));
+        }
+    }
+
+    assert_eq!(state.root(), holesky_state_root());
+}
+
+#[test]
+fn clearing_storage_of_a_missing_account_creates_nothing() {
+    let mut state = escrow_state();
+    let before = state.root();
+
+    state.set_storage(address(9), U256::from(0), U256::zero());
+    assert_eq!(state.account(&address(9)), None);
+    assert_eq!(state.root(), before);
+}
+
+#[test]
+fn generated_proofs_verify() {
+    let state = escrow_state();
+    let slots = [U256::from(0), U256::from(1), U256::from(7), U256::from(5)];
+    let proof = state.proof(&address(2), &slots);
+
+    let values: Vec<U256> = proof.storage_proof.iter().map(|storage| storage.value).collect();
+    assert_eq!(values, [U256::from(42), U256::from(0xdeadbeefu64), U256::exp10(18), U256::zero()]);
+    proof.verify(&state.root()).unwrap();
+
+    let decoded = AccountProof::from_json(&proof.to_json()).unwrap();
+    assert_eq!(decoded, proof);
+    decoded.verify(&state.root()).unwrap();
+}
+
+#[test]
+fn proves_missing_accounts_and_cleared_slots() {
+    let mut state = escrow_state();
+    state.set_storage(address(2), U256::from(1), U256::zero());
+
+    let missing = state.proof(&address(3), &[U256::from(0)]);
+    assert_eq!(missing.account, Account::default());
+    assert!(missing.storage_proof[0].proof.is_empty());
+    missing.verify(&state.root()).unwrap();
+
+    let cleared = state.proof(&address(2), &[U256::from(1)]);
+    assert_eq!(cleared.storage_proof[0].value, U256::zero());
+    cleared.verify(&state.root()).unwrap();
+
+    let empty = WorldState::new();
+    assert_eq!(empty.root(), EMPTY_TRIE_ROOT);
+    empty.proof(&address(1), &[]).verify(&EMPTY_TRIE_ROOT).unwrap();
+}
+
+/// The mainnet genesis header: fixed fields, an empty 256-byte bloom, then the rest.
+#[test]
+fn reads_state_root_from_header() {
+    let header = hex::decode(format!(
+        "{}{}{}",
+        concat!(
+            "f90214a00000000000000000000000000000000000000000000000000000000000000000a01dcc4de8dec75d7aab85b567b6ccd41a",
+            "d312451b948a7413f0a142fd40d49347940000000000000000000000000000000000000000a0d7f8974fb5ac78d9ac099b9ad5018b",
+            "edc2ce0a72dad1827a1709da30580f0544a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a056e8",
+            "1f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421b90100",
+        ),
+        "00".repeat(256),
+        concat!(
+            "850400000000808213888080a011bbe8db4e347b4e8c937c1c8370e4b5ed33adb3db69cbdb7a38e1e50b1b82faa0000000000000",
+            "0000000000000000000000000000000000000000000000000000880000000000000042",
+        ),
+    ))
+    .unwrap();
+    let genesis = root("d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3");
+
+    assert_eq!(
+        hex::encode(header_state_root(&header, &genesis).unwrap()),
+        "d7f8974fb5ac78d9ac099b9ad5018bedc2ce0a72dad1827a1709da30580f0544"
+    );
+    assert!(matches!(
+        header_state_root(&header, &Keccak256::hash(b"block 1")),
+        Err(EthProofError::HeaderHashMismatch)
+    ));
+}
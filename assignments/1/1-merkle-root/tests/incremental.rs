@@ -0,0 +1,90 @@
+//! The incremental tree against the deposit contract's bytecode and a tree
+//! built in full.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use merkle_root::hasher::{Hash, Hasher, Sha256};
+use merkle_root::incremental::{DepositTree, IncrementalMerkleTree, TreeFull};
+use merkle_root::tree::MerkleTree;
+
+fn leaf(i: u8) -> Hash {
+    Sha256::hash(&[i])
+}
+
+fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/deposit_contract").join(name);
+    fs::read_to_string(path).unwrap()
+}
+
+/// `get_deposit_root()` of the mainnet deposit contract before its first deposit.
+#[test]
+fn empty_tree_matches_deposit_contract() {
+    let tree = DepositTree::new();
+
+    assert_eq!(
+        hex::encode(tree.deposit_root()),
+        "d70a234731285c6804c2a4f56711ddb8c82c99740f207854891028af34e27e5e"
+    );
+    assert_eq!(tree.root(), Sha256::hash_pair(&tree.zero_hashes()[31], &tree.zero_hashes()[31]));
+}
+
+/// The zero hashes the deposit contract was deployed with, from its storage
+/// in the Holesky genesis: slot `0x21 + h` holds the root of an empty subtree
+/// of height `h`, and slot `0x21`, for `h = 0`, is left zero.
+#[test]
+fn zero_hashes_match_the_deployed_contract() {
+    let storage: HashMap<String, String> = serde_json::from_str(&fixture("holesky_genesis_storage.json")).unwrap();
+    let tree = DepositTree::new();
+
+    assert_eq!(storage.len(), 31);
+    for (height, zero_hash) in tree.zero_hashes().iter().enumerate().skip(1) {
+        let slot = format!("0x{:064x}", 0x21 + height);
+        assert_eq!(storage[&slot], format!("0x{}", hex::encode(zero_hash)), "height {}", height);
+    }
+    assert_eq!(tree.zero_hashes()[0], [0u8; 32]);
+}
+
+/// `get_deposit_root()` and `get_deposit_count()` of the deposit contract
+/// bytecode after each deposit. Each leaf is the deposit's `deposit_data_root`,
+/// which the contract recomputes from the deposit and checks.
+#[test]
+fn matches_deposit_contract_after_each_deposit() {
+    let vectors: serde_json::Value = serde_json::from_str(&fixture("deposits.json")).unwrap();
+    let hash = |value: &serde_json::Value| hex::decode(&value.as_str().unwrap()[2..]).unwrap();
+
+    let mut tree = DepositTree::new();
+    assert_eq!(tree.deposit_root().to_vec(), hash(&vectors["empty_deposit_root"]));
+    for deposit in vectors["deposits"].as_array().unwrap() {
+        tree.push(hash(&deposit["deposit_data_root"]).try_into().unwrap()).unwrap();
+        assert_eq!(tree.len(), deposit["deposit_count"].as_u64().unwrap());
+        assert_eq!(tree.deposit_root().to_vec(), hash(&deposit["deposit_root"]), "{} deposits", tree.len());
+    }
+    assert_eq!(tree.len(), 9);
+}
+
+#[test]
+fn matches_a_full_tree_padded_with_zeros() {
+    let mut tree = IncrementalMerkleTree::<Sha256, 4>::new();
+    for count in 0..16u8 {
+        let mut leaves: Vec<Hash> = (0..count).map(leaf).collect();
+        leaves.resize(16, [0u8; 32]);
+        assert_eq!(Some(tree.root()), MerkleTree::<Sha256>::from_leaves(leaves).root(), "{} leaves", count);
+
+        if count < 15 {
+            tree.push(leaf(count)).unwrap();
+        }
+    }
+}
+
+#[test]
+fn stops_one_short_of_the_last_slot() {
+    let mut tree = IncrementalMerkleTree::<Sha256, 3>::new();
+    for i in 0..7 {
+        tree.push(leaf(i)).unwrap();
+    }
+
+    assert_eq!(tree.push(leaf(7)), Err(TreeFull { capacity: 7 }));
+    assert_eq!(tree.len(), 7);
+}
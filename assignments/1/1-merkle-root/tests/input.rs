@@ -0,0 +1,120 @@
+//! Leaves read from each input format, checked against the same items hashed directly.
+
+use std::path::Path;
+
+use merkle_root::hasher::{Hash, Hasher, Keccak256, Sha256};
+use merkle_root::input::{read_leaves, read_leaves_from, CsvColumn, InputError, InputFormat};
+
+const TXS: [&str; 5] = [
+    "Alice sent 2 eth",
+    "Jacks sent 1 eth",
+    "Mike sent 8 eth",
+    "Richard sent 2 eth",
+    "Key sent 2 eth",
+];
+
+fn hashed<H: Hasher>(items: &[&str]) -> Vec<Hash> {
+    items.iter().map(|item| H::hash(item.as_bytes())).collect()
+}
+
+fn read<H: Hasher>(input: &str, format: InputFormat, prehashed: bool) -> Result<Vec<Hash>, InputError> {
+    read_leaves_from::<H, _>(input.as_bytes(), &format, prehashed)
+}
+
+#[test]
+fn lines_skip_blank_lines() {
+    let input = "Alice sent 2 eth\r\nJacks sent 1 eth\n\n  \nMike sent 8 eth\nRichard sent 2 eth\nKey sent 2 eth";
+    assert_eq!(read::<Sha256>(input, InputFormat::Lines, false).unwrap(), hashed::<Sha256>(&TXS));
+}
+
+#[test]
+fn csv_by_index_and_by_header() {
+    let rows = "1,Alice sent 2 eth\n2,Jacks sent 1 eth\n3,\"Mike sent 8 eth\"\n\n4,Richard sent 2 eth\n5,Key sent 2 eth\n";
+    let by_index = read::<Keccak256>(rows, InputFormat::Csv(CsvColumn::Index(1)), false).unwrap();
+    assert_eq!(by_index, hashed::<Keccak256>(&TXS));
+
+    let with_header = format!("id,\"memo, text\"\n{}", rows);
+    let by_name = read::<Keccak256>(&with_header, InputFormat::Csv("memo, text".parse().unwrap()), false).unwrap();
+    assert_eq!(by_name, by_index);
+
+    let quoted = read::<Sha256>("\"say \"\"hi\"\"\",x\n", InputFormat::Csv(CsvColumn::Index(0)), false).unwrap();
+    assert_eq!(quoted, hashed::<Sha256>(&["say \"hi\""]));
+}
+
+#[test]
+fn csv_errors_name_the_problem() {
+    let missing = read::<Sha256>("id,memo\n1,a\n", InputFormat::Csv(CsvColumn::Name("tx".into())), false);
+    assert!(matches!(missing, Err(InputError::MissingColumn(name)) if name == "tx"));
+
+    let short = read::<Sha256>("1,a\n\n2\n", InputFormat::Csv(CsvColumn::Index(1)), false);
+    assert!(matches!(short, Err(InputError::Parse { item: 3, .. })));
+}
+
+#[test]
+fn json_strings_and_other_values() {
+    let input = serde_json::to_string(&TXS).unwrap();
+    assert_eq!(read::<Sha256>(&input, InputFormat::Json, false).unwrap(), hashed::<Sha256>(&TXS));
+
+    let mixed = read::<Sha256>("[1, {\"to\": \"0xab\", \"value\": 2}, null]", InputFormat::Json, false).unwrap();
+    assert_eq!(mixed, hashed::<Sha256>(&["1", "{\"to\":\"0xab\",\"value\":2}", "null"]));
+
+    assert!(matches!(read::<Sha256>("{\"a\": 1}", InputFormat::Json, false), Err(InputError::Parse { .. })));
+    assert!(matches!(read::<Sha256>("[\"a\"] [", InputFormat::Json, false), Err(InputError::Parse { .. })));
+}
+
+#[test]
+fn prehashed_hex_is_used_as_is() {
+    let leaves = hashed::<Sha256>(&TXS);
+    let lines: Vec<String> = leaves
+        .iter()
+        .enumerate()
+        .map(|(i, leaf)| if i % 2 == 0 { format!("0x{}", hex::encode(leaf)) } else { hex::encode(leaf) })
+        .collect();
+
+    assert_eq!(read::<Keccak256>(&lines.join("\n"), InputFormat::Lines, true).unwrap(), leaves);
+    let json = serde_json::to_string(&lines).unwrap();
+    assert_eq!(read::<Keccak256>(&json, InputFormat::Json, true).unwrap(), leaves);
+
+    let short = format!("{}\n{}", lines[0], &lines[1][2..]);
+    assert!(matches!(read::<Sha256>(&short, InputFormat::Lines, true), Err(InputError::InvalidHash { item: 2, .. })));
+}
+
+#[test]
+fn binary_records() {
+    let leaves = hashed::<Sha256>(&TXS);
+    let bytes = leaves.concat();
+    let prehashed = read_leaves_from::<Sha256, _>(&bytes[..], &InputFormat::Binary { record_size: 32 }, true).unwrap();
+    assert_eq!(prehashed, leaves);
+
+    let records = read_leaves_from::<Sha256, _>(&bytes[..], &InputFormat::Binary { record_size: 16 }, false).unwrap();
+    let expected: Vec<Hash> = bytes.chunks(16).map(Sha256::hash).collect();
+    assert_eq!(records, expected);
+
+    let partial = read_leaves_from::<Sha256, _>(&bytes[..40], &InputFormat::Binary { record_size: 32 }, false);
+    assert!(matches!(partial, Err(InputError::PartialRecord { record_size: 32, trailing: 8 })));
+    let wrong_size = read_leaves_from::<Sha256, _>(&bytes[..], &InputFormat::Binary { record_size: 16 }, true);
+    assert!(matches!(wrong_size, Err(InputError::RecordSize(16))));
+}
+
+#[test]
+fn formats_from_names_and_extensions() {
+    assert_eq!(InputFormat::from_path(Path::new("txs.csv")), InputFormat::Csv(CsvColumn::Index(0)));
+    assert_eq!(InputFormat::from_path(Path::new("txs.json")), InputFormat::Json);
+    assert_eq!(InputFormat::from_path(Path::new("leaves.bin")), InputFormat::Binary { record_size: 32 });
+    assert_eq!(InputFormat::from_path(Path::new("txs.txt")), InputFormat::Lines);
+    assert_eq!("JSON".parse::<InputFormat>(), Ok(InputFormat::Json));
+    assert!("xml".parse::<InputFormat>().is_err());
+    assert_eq!("2".parse::<CsvColumn>(), Ok(CsvColumn::Index(2)));
+}
+
+#[test]
+fn reads_from_a_file() {
+    let path = std::env::temp_dir().join(format!("merkle_root_input_{}.txt", std::process::id()));
+    std::fs::write(&path, TXS.join("\n")).unwrap();
+    let leaves = read_leaves::<Sha256>(&path, &InputFormat::from_path(&path), false);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(leaves.unwrap(), hashed::<Sha256>(&TXS));
+
+    let missing = read_leaves::<Sha256>(Path::new("/nonexistent/leaves.txt"), &InputFormat::Lines, false);
+    assert!(matches!(missing, Err(InputError::Io(_))));
+}
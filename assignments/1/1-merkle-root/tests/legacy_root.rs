@@ -0,0 +1,70 @@
+//! The generic tree against the hand-written SHA-256 and Keccak-256 root
+//! functions it replaced, on the demo block.
+
+use merkle_root::hasher::{Keccak256, Sha256};
+use merkle_root::tree::MerkleTree;
+use sha2::Digest;
+
+const TRANSACTIONS: [&str; 5] = [
+    "Alice sent 2 eth",
+    "Jacks sent 1 eth",
+    "Mike sent 8 eth",
+    "Richard sent 2 eth",
+    "Key sent 2 eth",
+];
+
+/// `get_merkle_root_through_sha256` and `get_merkle_root_through_kecheck` from
+/// the original `main.rs`, without the printing: pairs are hashed left to right
+/// and an unpaired hash moves up a level as it is.
+fn original_root<D: Digest>(mut hashes: Vec<Vec<u8>>) -> Vec<u8> {
+    if hashes.is_empty() {
+        return vec![];
+    }
+
+    while hashes.len() > 1 {
+        let mut next_level = Vec::new();
+        let mut i = 0;
+        while i < hashes.len() {
+            if i + 1 < hashes.len() {
+                let mut hasher = D::new();
+                hasher.update(&hashes[i]);
+                hasher.update(&hashes[i + 1]);
+                next_level.push(hasher.finalize().to_vec());
+            } else {
+                next_level.push(hashes[i].clone());
+            }
+            i += 2;
+        }
+        hashes = next_level;
+    }
+
+    hashes.pop().unwrap()
+}
+
+fn original_leaves<D: Digest>(transactions: &[&str]) -> Vec<Vec<u8>> {
+    transactions.iter().map(|tx| D::digest(tx.as_bytes()).to_vec()).collect()
+}
+
+#[test]
+fn demo_block_root_is_unchanged() {
+    let root = MerkleTree::<Sha256>::from_data(TRANSACTIONS).root().unwrap();
+    assert_eq!(hex::encode(root), "d295a7a74dea664d488ae948c55bbc56337b55a631df73dc1e23264bf8f90386");
+    assert_eq!(root.to_vec(), original_root::<sha2::Sha256>(original_leaves::<sha2::Sha256>(&TRANSACTIONS)));
+}
+
+#[test]
+fn every_prefix_of_the_block_matches_the_original_functions() {
+    for n in 1..=TRANSACTIONS.len() {
+        let transactions = &TRANSACTIONS[..n];
+
+        let sha256 = MerkleTree::<Sha256>::from_data(transactions).root().unwrap();
+        let original = original_root::<sha2::Sha256>(original_leaves::<sha2::Sha256>(transactions));
+        assert_eq!(sha256.to_vec(), original, "sha256, {} transactions", n);
+
+        let keccak = MerkleTree::<Keccak256>::from_data(transactions).root().unwrap();
+        let original = original_root::<sha3::Keccak256>(original_leaves::<sha3::Keccak256>(transactions));
+        assert_eq!(keccak.to_vec(), original, "keccak256, {} transactions", n);
+    }
+    assert!(original_root::<sha2::Sha256>(vec![]).is_empty());
+    assert_eq!(MerkleTree::<Sha256>::from_data(Vec::<&str>::new()).root(), None);
+}
@@ -0,0 +1,173 @@
+//! Merkle mountain range roots and proofs, old sizes included.
+
+use merkle_root::hasher::{Hash, Hasher, Sha256};
+use merkle_root::mmr::{MerkleMountainRange, MmrProof, MAX_LEAF_COUNT};
+use merkle_root::tree::MerkleTree;
+
+fn leaf(i: u64) -> Hash {
+    Sha256::hash(&[i as u8])
+}
+
+fn range(count: u64) -> MerkleMountainRange<Sha256> {
+    let mut mmr = MerkleMountainRange::new();
+    for i in 0..count {
+        assert_eq!(mmr.push(leaf(i)), i);
+    }
+    mmr
+}
+
+/// Roots and node counts printed by `tests/reference/mmr.py`.
+#[test]
+fn matches_reference_roots() {
+    let cases = [
+        (1, 1, "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d"),
+        (2, 3, "30e1867424e66e8b6d159246db94e3486778136f7e386ff5f001859d6b8484ab"),
+        (3, 4, "773a93ac37ea78b3f14ac31872c83886b0a0f1fec562c4e848e023c889c2ce9f"),
+        (4, 7, "9675e04b4ba9dc81b06e81731e2d21caa2c95557a85dcfa3fff70c9ff0f30b2e"),
+        (5, 8, "5174b138f822e56503c04bce38e368672593b4a2694466c2e60f1216caf234be"),
+        (7, 11, "7269be49c490af17ec87be84f3dc791c5f9923b4c557fefd83204f0c0f40b5ae"),
+        (8, 15, "0727b310f87099c1ba2ec0ba408def82c308237c8577f0bdfd2643e9cc6b7578"),
+        (11, 19, "4ad5d2ded1c519bd3e2701cc82f21df48ae8d15aed7ab8e9f78017543d368d86"),
+        (19, 35, "c6822aa31757aede4c9354229ccfc6bc6238c769c8af7d673df2558ddec30fde"),
+    ];
+
+    let mmr = range(19);
+    for (count, size, root) in cases {
+        assert_eq!(hex::encode(mmr.root_at(count).unwrap()), root, "{} leaves", count);
+        assert_eq!(range(count).size(), size, "{} leaves", count);
+    }
+    assert_eq!(mmr.root(), mmr.root_at(19));
+}
+
+#[test]
+fn empty_range_has_no_root() {
+    let mmr = range(0);
+
+    assert!(mmr.is_empty());
+    assert_eq!(mmr.root(), None);
+    assert!(mmr.peaks().is_empty());
+    assert_eq!(mmr.proof(0), None);
+}
+
+/// With a power of two leaves there is a single mountain, a perfect tree.
+#[test]
+fn single_mountain_is_a_merkle_tree() {
+    for count in [1, 2, 4, 8, 16, 32] {
+        let mmr = range(count);
+        let tree = MerkleTree::<Sha256>::from_leaves((0..count).map(leaf).collect());
+
+        assert_eq!(mmr.peaks().len(), 1);
+        assert_eq!(mmr.root(), tree.root(), "{} leaves", count);
+    }
+}
+
+#[test]
+fn one_peak_per_set_bit() {
+    let mmr = range(45);
+    for count in 0..=45u64 {
+        assert_eq!(range(count).peaks().len(), count.count_ones() as usize);
+        assert_eq!(range(count).root(), mmr.root_at(count), "{} leaves", count);
+    }
+    assert_eq!(mmr.root_at(46), None);
+}
+
+#[test]
+fn every_proof_verifies_at_every_size() {
+    let mmr = range(40);
+    for count in 1..=40 {
+        let root = mmr.root_at(count).unwrap();
+        for index in 0..count {
+            let proof = mmr.proof_at(index, count).unwrap();
+            assert_eq!(proof.leaf, leaf(index));
+            assert!(proof.verify::<Sha256>(&root), "leaf {} of {}", index, count);
+        }
+        assert_eq!(mmr.proof_at(count, count), None);
+    }
+}
+
+/// A proof handed out early keeps verifying against the root it was made for,
+/// and the grown range reproduces it exactly.
+#[test]
+fn old_proofs_stay_valid_as_the_range_grows() {
+    let mut mmr = range(5);
+    let old_root = mmr.root().unwrap();
+    let old_proof = mmr.proof(2).unwrap();
+
+    for i in 5..100 {
+        mmr.push(leaf(i));
+    }
+
+    assert_ne!(mmr.root().unwrap(), old_root);
+    assert_eq!(mmr.root_at(5), Some(old_root));
+    assert!(old_proof.verify::<Sha256>(&old_root));
+    assert_eq!(mmr.proof_at(2, 5), Some(old_proof.clone()));
+    assert!(!old_proof.verify::<Sha256>(&mmr.root().unwrap()));
+    assert!(mmr.proof(2).unwrap().verify::<Sha256>(&mmr.root().unwrap()));
+}
+
+#[test]
+fn rejects_tampered_proofs() {
+    let mmr = range(13);
+    let root = mmr.root().unwrap();
+    let proof = mmr.proof(9).unwrap();
+
+    let mut wrong_leaf = proof.clone();
+    wrong_leaf.leaf = leaf(10);
+    assert!(!wrong_leaf.verify::<Sha256>(&root));
+
+    let mut wrong_index = proof.clone();
+    wrong_index.leaf_index = 8;
+    assert!(!wrong_index.verify::<Sha256>(&root));
+
+    let mut wrong_count = proof.clone();
+    wrong_count.leaf_count = 16;
+    assert_eq!(wrong_count.compute_root::<Sha256>(), None);
+
+    let mut out_of_range = proof.clone();
+    out_of_range.leaf_index = 13;
+    assert_eq!(out_of_range.compute_root::<Sha256>(), None);
+
+    let mut missing_peak = proof;
+    missing_peak.peaks.pop();
+    assert_eq!(missing_peak.compute_root::<Sha256>(), None);
+}
+
+#[test]
+fn json_round_trip() {
+    let mmr = range(21);
+    let proof = mmr.proof(17).unwrap();
+
+    let decoded = MmrProof::from_json(&proof.to_json()).unwrap();
+    assert_eq!(decoded, proof);
+    assert!(decoded.verify::<Sha256>(&mmr.root().unwrap()));
+}
+
+/// Leaf counts of 2^63 and above have a mountain too large to number its
+/// nodes; proofs claiming them are rejected instead of overflowing.
+#[test]
+fn rejects_proofs_for_unaddressable_sizes() {
+    let root = range(3).root().unwrap();
+    let hostile = format!(
+        r#"{{"leafIndex": {}, "leafCount": {}, "leaf": "0x{}", "siblings": [], "peaks": ["0x{}"]}}"#,
+        1u64 << 63,
+        (1u64 << 63) + 1,
+        hex::encode(leaf(0)),
+        hex::encode(leaf(1)),
+    );
+    let proof = MmrProof::from_json(&hostile).unwrap();
+    assert_eq!(proof.compute_root::<Sha256>(), None);
+    assert!(!proof.verify::<Sha256>(&root));
+
+    let mut largest = proof.clone();
+    largest.leaf_count = u64::MAX;
+    largest.leaf_index = u64::MAX - 1;
+    assert!(!largest.verify::<Sha256>(&root));
+
+    // The largest size allowed has 63 mountains; its last leaf is a peak of its own.
+    let mut allowed = proof;
+    allowed.leaf_count = MAX_LEAF_COUNT;
+    allowed.leaf_index = MAX_LEAF_COUNT - 1;
+    allowed.peaks = vec![leaf(1); 62];
+    assert!(allowed.compute_root::<Sha256>().is_some());
+    assert!(!allowed.verify::<Sha256>(&root));
+}
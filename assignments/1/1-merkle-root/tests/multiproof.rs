@@ -0,0 +1,150 @@
+//! Multiproofs as `@openzeppelin/merkle-tree` generates them and
+//! `MerkleProof.multiProofVerify` consumes them.
+
+use merkle_root::hasher::Hash;
+use merkle_root::multiproof::{MultiProof, MultiProofError};
+use merkle_root::standard::{verify_multi_proof, StandardMerkleTree, StandardMultiProof, StandardTreeError};
+
+const ENCODING: [&str; 2] = ["address", "uint256"];
+
+fn values() -> Vec<[String; 2]> {
+    (1..=7u64)
+        .map(|i| [format!("0x{:040x}", 0x1000 * i), format!("{}00000000000000000", i)])
+        .collect()
+}
+
+fn tree() -> StandardMerkleTree {
+    StandardMerkleTree::of(&values(), &ENCODING).unwrap()
+}
+
+fn hash(value: &str) -> Hash {
+    hex::decode(value).unwrap().try_into().unwrap()
+}
+
+#[test]
+fn matches_reference_vectors() {
+    let tree = tree();
+    assert_eq!(tree.root(), hash("aad4c458a6b8835af71137d40851ff442dfd42f56f5c5defe58324de39766f29"));
+
+    let values = values();
+    let cases = [
+        (
+            vec![0, 3, 5],
+            vec![3, 0, 5],
+            vec![
+                "328999c8d6b5a5c112175287ad3391cc00fbfc90149c0883a5d8d0e6647f7d02",
+                "82ac5de0284dbf6fdeda27ef567f4a58fcf91f0d291158d8804c245be6390de4",
+                "860834484fa0da23a8eca9df6520a257e33c9a2fa90fcbbb6c4c5f020d31796e",
+                "f69ed1eb486f618c25ff378b71a5ddd4e1e2efd7901bdac8a53aa8c03316354a",
+            ],
+            vec![false, false, false, false, true, true],
+        ),
+        (
+            vec![1, 2],
+            vec![1, 2],
+            vec![
+                "415f5f7a039294b6b8347197169c7ab076b538919187b22a6a3c806ae8695f47",
+                "4871f7bcb33f7d76b46c6e252df4a6980dd013e04cfb8af35f001896bc46e318",
+                "f69ed1eb486f618c25ff378b71a5ddd4e1e2efd7901bdac8a53aa8c03316354a",
+                "f6f5e536876c8813b7cc6b5c46afba4199f2b8413e6848010ff9b0e7c8396c5c",
+            ],
+            vec![false, false, false, false, true],
+        ),
+        (
+            vec![0, 1, 2, 3, 4, 5, 6],
+            vec![1, 3, 0, 2, 4, 5, 6],
+            vec![],
+            vec![true; 6],
+        ),
+    ];
+
+    for (indices, leaf_order, proof, flags) in cases {
+        let multi = tree.multi_proof(&indices).unwrap();
+        let leaves: Vec<Vec<String>> = leaf_order.iter().map(|i| values[*i].to_vec()).collect();
+        assert_eq!(multi.leaves, leaves, "leaves for {:?}", indices);
+        assert_eq!(multi.proof, proof.iter().map(|h| hash(h)).collect::<Vec<_>>(), "proof for {:?}", indices);
+        assert_eq!(multi.proof_flags, flags, "flags for {:?}", indices);
+        assert!(verify_multi_proof(&tree.root(), &ENCODING, &multi).unwrap());
+    }
+}
+
+#[test]
+fn single_leaf_multiproof_is_the_ordinary_proof() {
+    let tree = tree();
+    let multi = tree.multi_proof(&[6]).unwrap();
+
+    assert_eq!(multi.proof, tree.proof(6).unwrap());
+    assert!(multi.proof_flags.iter().all(|flag| !flag));
+}
+
+#[test]
+fn proving_nothing_needs_only_the_root() {
+    let tree = tree();
+    let multi = tree.multi_proof(&[]).unwrap();
+
+    assert!(multi.leaves.is_empty());
+    assert_eq!(multi.proof, vec![tree.root()]);
+    assert!(verify_multi_proof(&tree.root(), &ENCODING, &multi).unwrap());
+}
+
+#[test]
+fn every_subset_verifies_and_is_shorter_than_single_proofs() {
+    let tree = tree();
+    for subset in 1u32..(1 << tree.len()) {
+        let indices: Vec<usize> = (0..tree.len()).filter(|i| subset & (1 << i) != 0).collect();
+        let multi = tree.multi_proof(&indices).unwrap();
+        let single: usize = indices.iter().map(|i| tree.proof(*i).unwrap().len()).sum();
+
+        assert!(verify_multi_proof(&tree.root(), &ENCODING, &multi).unwrap(), "{:?}", indices);
+        assert!(multi.proof.len() <= single, "{:?}", indices);
+    }
+}
+
+#[test]
+fn rejects_a_changed_value() {
+    let tree = tree();
+    let mut multi = tree.multi_proof(&[1, 2]).unwrap();
+    multi.leaves[0][1] = String::from("900000000000000000");
+
+    assert!(!verify_multi_proof(&tree.root(), &ENCODING, &multi).unwrap());
+}
+
+#[test]
+fn rejects_flags_that_do_not_fit() {
+    let tree = tree();
+    let mut multi = tree.multi_proof(&[0, 3, 5]).unwrap();
+    multi.proof_flags.push(true);
+
+    assert!(matches!(
+        verify_multi_proof(&tree.root(), &ENCODING, &multi),
+        Err(StandardTreeError::MultiProof(MultiProofError::InvalidLength { leaves: 3, proof: 4, flags: 7 }))
+    ));
+}
+
+#[test]
+fn rejects_bad_indices() {
+    let tree = tree();
+
+    assert_eq!(tree.multi_proof(&[1, 7]).unwrap_err(), StandardTreeError::InvalidIndex(7));
+    assert!(matches!(
+        tree.multi_proof(&[2, 2]),
+        Err(StandardTreeError::MultiProof(MultiProofError::DuplicateIndex(_)))
+    ));
+    assert_eq!(MultiProof::generate(tree.nodes(), &[0]).unwrap_err(), MultiProofError::NotALeaf(0));
+}
+
+#[test]
+fn rejects_an_empty_tree() {
+    assert_eq!(MultiProof::generate(&[], &[]).unwrap_err(), MultiProofError::EmptyTree);
+    assert_eq!(MultiProof::generate(&[], &[0]).unwrap_err(), MultiProofError::EmptyTree);
+}
+
+#[test]
+fn json_round_trips() {
+    let multi = tree().multi_proof(&[0, 3, 5]).unwrap();
+    let json = serde_json::to_string(&multi).unwrap();
+
+    assert!(json.starts_with(r#"{"leaves":[["0x0000000000000000000000000000000000004000","400000000000000000"]"#));
+    assert!(json.contains(r#""proofFlags":[false,false,false,false,true,true]"#));
+    assert_eq!(serde_json::from_str::<StandardMultiProof>(&json).unwrap(), multi);
+}
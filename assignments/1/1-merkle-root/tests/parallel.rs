@@ -0,0 +1,56 @@
+//! The parallel path against `MerkleTree`, which it must always agree with.
+
+use merkle_root::hasher::{DoubleSha256, Hash, Hasher, Keccak256, Sha256};
+use merkle_root::parallel;
+use merkle_root::tree::{MerkleTree, Policy};
+
+fn leaves<H: Hasher>(count: usize) -> Vec<Hash> {
+    (0..count as u64).map(|i| H::hash(&i.to_le_bytes())).collect()
+}
+
+fn same_roots<H: Hasher>(count: usize) {
+    let leaves = leaves::<H>(count);
+    for policy in Policy::ALL {
+        let sequential = MerkleTree::<H>::from_leaves_with(leaves.clone(), policy).root();
+        assert_eq!(parallel::root::<H>(&leaves, policy), sequential, "{} {} leaves {}", H::NAME, count, policy);
+    }
+}
+
+#[test]
+fn matches_sequential_for_small_trees() {
+    for count in 0..=70 {
+        same_roots::<Sha256>(count);
+        same_roots::<Keccak256>(count);
+        same_roots::<DoubleSha256>(count);
+    }
+}
+
+/// Sizes that split levels across many tasks and leave odd nodes on the way up.
+#[test]
+fn matches_sequential_for_large_trees() {
+    for count in [(1 << 13) + 1, 20_003] {
+        same_roots::<Sha256>(count);
+    }
+}
+
+#[test]
+fn hashes_raw_data_like_from_data() {
+    let items: Vec<String> = (0..5000).map(|i| format!("tx {} sent {} eth", i, i % 9)).collect();
+    for policy in Policy::ALL {
+        assert_eq!(
+            parallel::root_from_data::<Keccak256, _>(&items, policy),
+            MerkleTree::<Keccak256>::from_data_with(&items, policy).root()
+        );
+    }
+}
+
+#[test]
+fn program_transactions() {
+    let data = ["Alice sent 2 eth", "Jacks sent 1 eth", "Mike sent 8 eth", "Richard sent 2 eth", "Key sent 2 eth"];
+
+    assert_eq!(
+        parallel::root_from_data::<Sha256, _>(&data, Policy::Promote).map(hex::encode),
+        MerkleTree::<Sha256>::from_data(data).root().map(hex::encode)
+    );
+    assert_eq!(parallel::root::<Sha256>(&[], Policy::Promote), None);
+}
@@ -0,0 +1,196 @@
+//! The Patricia trie against the ethereum/tests trie fixtures.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use merkle_root::hasher::{Hasher, Keccak256};
+use merkle_root::patricia::{hex_prefix, verify_proof, PatriciaProofError, PatriciaTrie, EMPTY_TRIE_ROOT};
+use serde_json::Value;
+
+/// A fixture key or value: hex after `0x`, otherwise the string's bytes.
+fn bytes(value: &Value) -> Vec<u8> {
+    match value.as_str() {
+        None => Vec::new(),
+        Some(s) => match s.strip_prefix("0x") {
+            Some(digits) => hex::decode(digits).unwrap(),
+            None => s.as_bytes().to_vec(),
+        },
+    }
+}
+
+/// `(key, value)` pairs of a case, in order. An empty value deletes.
+fn entries(case: &Value) -> Vec<(Vec<u8>, Vec<u8>)> {
+    match &case["in"] {
+        Value::Object(map) => map.iter().map(|(k, v)| (bytes(&Value::from(k.as_str())), bytes(v))).collect(),
+        Value::Array(pairs) => pairs.iter().map(|pair| (bytes(&pair[0]), bytes(&pair[1]))).collect(),
+        other => panic!("unexpected input {}", other),
+    }
+}
+
+fn build(entries: &[(Vec<u8>, Vec<u8>)], secure: bool) -> PatriciaTrie {
+    let mut trie = PatriciaTrie::new();
+    for (key, value) in entries {
+        if secure {
+            trie.insert(&Keccak256::hash(key), value);
+        } else {
+            trie.insert(key, value);
+        }
+    }
+    trie
+}
+
+#[test]
+fn passes_ethereum_trie_fixtures() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/TrieTests");
+    let mut cases = BTreeMap::new();
+    for file in fs::read_dir(dir).unwrap() {
+        let path = file.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let fixtures: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+
+        for (case, fixture) in fixtures.as_object().unwrap() {
+            let mut entries = entries(fixture);
+            let secure = name.contains("secureTrie");
+            let root = format!("0x{}", hex::encode(build(&entries, secure).root()));
+            assert_eq!(root, fixture["root"].as_str().unwrap(), "{} {}", name, case);
+
+            if name.starts_with("trieanyorder") {
+                entries.reverse();
+                assert_eq!(format!("0x{}", hex::encode(build(&entries, secure).root())), root, "{} {} reversed", name, case);
+            }
+            *cases.entry(name.clone()).or_insert(0) += 1;
+        }
+    }
+
+    let expected = [
+        ("trieanyorder.json", 7),
+        ("trieanyorder_secureTrie.json", 3),
+        ("trietest.json", 3),
+        ("trietest_secureTrie.json", 1),
+    ];
+    assert_eq!(cases, expected.map(|(name, count)| (name.to_string(), count)).into());
+}
+
+#[test]
+fn empty_trie_root() {
+    let trie = PatriciaTrie::new();
+
+    assert_eq!(trie.root(), EMPTY_TRIE_ROOT);
+    assert_eq!(EMPTY_TRIE_ROOT, Keccak256::hash(&[0x80]));
+    assert!(trie.is_empty());
+}
+
+/// The examples of the Yellow Paper's appendix C.
+#[test]
+fn hex_prefix_encoding() {
+    assert_eq!(hex_prefix(&[1, 2, 3, 4, 5], false), [0x11, 0x23, 0x45]);
+    assert_eq!(hex_prefix(&[0, 1, 2, 3, 4, 5], false), [0x00, 0x01, 0x23, 0x45]);
+    assert_eq!(hex_prefix(&[0, 0xf, 1, 0xc, 0xb, 8], true), [0x20, 0x0f, 0x1c, 0xb8]);
+    assert_eq!(hex_prefix(&[0xf, 1, 0xc, 0xb, 8], true), [0x3f, 0x1c, 0xb8]);
+    assert_eq!(hex_prefix(&[], true), [0x20]);
+}
+
+#[test]
+fn get_insert_and_remove() {
+    let mut trie = PatriciaTrie::new();
+    for (key, value) in [("do", "verb"), ("dog", "puppy"), ("doge", "coin"), ("horse", "stallion")] {
+        trie.insert(key.as_bytes(), value.as_bytes());
+    }
+
+    assert_eq!(trie.len(), 4);
+    assert_eq!(trie.get(b"dog"), Some(&b"puppy"[..]));
+    assert_eq!(trie.get(b"do"), Some(&b"verb"[..]));
+    assert_eq!(trie.get(b"d"), None);
+    assert_eq!(trie.get(b"doges"), None);
+
+    trie.insert(b"dog", b"hound");
+    assert_eq!(trie.len(), 4);
+    assert_eq!(trie.get(b"dog"), Some(&b"hound"[..]));
+
+    assert!(trie.remove(b"dog"));
+    assert!(!trie.remove(b"dog"));
+    trie.insert(b"doge", b"");
+    assert_eq!(trie.len(), 2);
+    assert_eq!(trie.get(b"doge"), None);
+    assert_eq!(trie.get(b"do"), Some(&b"verb"[..]));
+
+    trie.remove(b"do");
+    trie.remove(b"horse");
+    assert_eq!(trie.root(), EMPTY_TRIE_ROOT);
+}
+
+/// Roots printed by `tests/reference/patricia.py`, which builds the trie from
+/// the final key set; removing keys must leave the same shape as never adding them.
+#[test]
+fn matches_reference_roots_after_removals() {
+    let key = |i: u16| Keccak256::hash(&i.to_be_bytes())[..8].to_vec();
+    let value = |i: u16| i.to_string().repeat(i as usize % 7 + 1).into_bytes();
+
+    let mut trie = PatriciaTrie::new();
+    for i in 0..100 {
+        trie.insert(&key(i), &value(i));
+    }
+    assert_eq!(hex::encode(trie.root()), "2875941fa78e5bca5785a8a3f3cf85cebfa49589afc9712464c5a883698f22f6");
+
+    for i in (0..100).step_by(3) {
+        assert!(trie.remove(&key(i)));
+    }
+    assert_eq!(trie.len(), 66);
+    assert_eq!(hex::encode(trie.root()), "fec22c5fbe0f12ff980e7df7fa85782f477eb5f72d16d85709a3cf1da1332985");
+
+    let mut fresh = PatriciaTrie::new();
+    for i in (0..100).filter(|i| i % 3 != 0).rev() {
+        fresh.insert(&key(i), &value(i));
+    }
+    assert_eq!(fresh.root(), trie.root());
+}
+
+#[test]
+fn proofs_show_presence_and_absence() {
+    let mut trie = PatriciaTrie::new();
+    for (key, value) in [("do", "verb"), ("dog", "puppy"), ("doge", "coin"), ("horse", "stallion"), ("be", "e")] {
+        trie.insert(key.as_bytes(), value.as_bytes());
+    }
+    let root = trie.root();
+
+    for (key, value) in [("do", "verb"), ("dog", "puppy"), ("doge", "coin"), ("horse", "stallion"), ("be", "e")] {
+        let proof = trie.prove(key.as_bytes());
+        assert_eq!(verify_proof(&root, key.as_bytes(), &proof), Ok(Some(value.as_bytes().to_vec())), "{}", key);
+    }
+    for key in ["d", "dogs", "cat", "hors", "bed", ""] {
+        let proof = trie.prove(key.as_bytes());
+        assert_eq!(verify_proof(&root, key.as_bytes(), &proof), Ok(None), "{}", key);
+    }
+
+    let proof = trie.prove(b"dog");
+    assert_eq!(verify_proof(&root, b"horse", &proof), Err(PatriciaProofError::UnusedNodes));
+    assert_eq!(verify_proof(&Keccak256::hash(b"other"), b"dog", &proof), Err(PatriciaProofError::HashMismatch { index: 0 }));
+
+    let mut padded = proof.clone();
+    padded.push(proof[0].clone());
+    assert_eq!(verify_proof(&root, b"dog", &padded), Err(PatriciaProofError::UnusedNodes));
+}
+
+#[test]
+fn proofs_in_a_large_trie() {
+    let mut trie = PatriciaTrie::new();
+    for i in 0..200u32 {
+        trie.insert(&Keccak256::hash(&i.to_be_bytes()), &i.to_be_bytes());
+    }
+    let root = trie.root();
+
+    for i in (0..400u32).step_by(7) {
+        let key = Keccak256::hash(&i.to_be_bytes());
+        let expected = (i < 200).then(|| i.to_be_bytes().to_vec());
+        assert_eq!(verify_proof(&root, &key, &trie.prove(&key)), Ok(expected), "{}", i);
+    }
+
+    let key = Keccak256::hash(&3u32.to_be_bytes());
+    let mut truncated = trie.prove(&key);
+    truncated.pop();
+    assert_eq!(verify_proof(&root, &key, &truncated), Err(PatriciaProofError::MissingNode));
+}
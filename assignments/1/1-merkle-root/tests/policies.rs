@@ -0,0 +1,152 @@
+//! Roots under every pairing policy. The vectors come from
+//! `tests/reference/policies.py`, except the Bitcoin block which is on chain.
+
+use merkle_root::hasher::{DoubleSha256, Hash, Hasher, Keccak256, Sha256};
+use merkle_root::proof::{verify_proof, Side};
+use merkle_root::tree::{MerkleTree, Policy};
+
+const TRANSACTIONS: [&str; 5] = [
+    "Alice sent 2 eth",
+    "Jacks sent 1 eth",
+    "Mike sent 8 eth",
+    "Richard sent 2 eth",
+    "Key sent 2 eth",
+];
+
+type RootFn = fn(usize, Policy) -> String;
+
+fn root_hex<H: Hasher>(leaf_count: usize, policy: Policy) -> String {
+    let tree = MerkleTree::<H>::from_data_with(&TRANSACTIONS[..leaf_count], policy);
+    hex::encode(tree.root().expect("at least one leaf"))
+}
+
+fn hash(value: &str) -> Hash {
+    hex::decode(value).unwrap().try_into().unwrap()
+}
+
+/// Policies only differ once a level has an odd number of nodes.
+#[test]
+fn policies_agree_on_one_and_two_leaves() {
+    for policy in Policy::ALL {
+        assert_eq!(
+            root_hex::<Sha256>(1, policy),
+            "db8451d24f029295d9a9ae8d60a2e61378ee5883f22fa9458c8020c1b7558f7b"
+        );
+    }
+    assert_eq!(
+        root_hex::<Sha256>(2, Policy::Promote),
+        "70f9ea271082c95693b070e9101065aaec36509038215088fb56718abf14ecf6"
+    );
+    assert_eq!(root_hex::<Sha256>(2, Policy::Duplicate), root_hex::<Sha256>(2, Policy::Promote));
+}
+
+#[test]
+fn promote_vectors() {
+    let cases: [(usize, &str, RootFn); 6] = [
+        (3, "55f811cf61f84c0aea3102e4788c66f5dbb1b2eda27f69c232dc5d6d9431f8a8", root_hex::<Sha256>),
+        (5, "d295a7a74dea664d488ae948c55bbc56337b55a631df73dc1e23264bf8f90386", root_hex::<Sha256>),
+        (3, "368702e44a166109e0174bf7f39dc1de46e5baf8c22c9376034f6f49c8160cb2", root_hex::<Keccak256>),
+        (5, "1e980298660df7139ddd389b5dfe31aa60cd06040ddf0b3ea15c8a746df3f372", root_hex::<Keccak256>),
+        (3, "837de2946692500ce01768a525ed24baa71601f1c9278d2688c0e3335bca612a", root_hex::<DoubleSha256>),
+        (5, "464a708576c207f0976f5fc058f72ba0f8fd700d71dbc7e7f0215b52dfbc1e28", root_hex::<DoubleSha256>),
+    ];
+    for (leaf_count, expected, root) in cases {
+        assert_eq!(root(leaf_count, Policy::Promote), expected, "{} leaves", leaf_count);
+    }
+}
+
+#[test]
+fn duplicate_vectors() {
+    let cases: [(usize, &str, RootFn); 6] = [
+        (3, "6d8e29d634c583c2ed6689be8fdb40a470c4e10d98c56b01df6eaa6b0884c574", root_hex::<Sha256>),
+        (5, "eb20fb5e81d3e8c9dc0833bf44d6be4c107328c471c522a4e09aca16310ddac9", root_hex::<Sha256>),
+        (3, "1316d33da510bd75321908ad682912b1ecb58f2d92396c23e8a8ea8400871e48", root_hex::<Keccak256>),
+        (5, "7062a30d161e5e2968bd8348a4374df8d8c60974208b82efc649d89ef6441462", root_hex::<Keccak256>),
+        (3, "004ae250d7c559a423f8dfe447c121a65b8acbe200e96891f57d9773897cf62e", root_hex::<DoubleSha256>),
+        (5, "63a65b9d804c3589436202c7e2055e672f7789a718ec8d84e9c5cfb99952edbe", root_hex::<DoubleSha256>),
+    ];
+    for (leaf_count, expected, root) in cases {
+        assert_eq!(root(leaf_count, Policy::Duplicate), expected, "{} leaves", leaf_count);
+    }
+}
+
+#[test]
+fn sorted_pairs_vectors() {
+    let cases: [(usize, &str, RootFn); 9] = [
+        (2, "df5377ffcb13596256820662b43771afde11a1fc3f10a7adbca5f904a7b6e947", root_hex::<Sha256>),
+        (3, "2759e8ddcdf723e06e1c5913ed71173af228bc33d89d25edb3c326c3fec9eeae", root_hex::<Sha256>),
+        (5, "ba5cafb467522a41ef413cfdfff2815342f5af24eb1619a96d134b8ccfeb63a9", root_hex::<Sha256>),
+        (2, "95f03a92c830f8d6e2015a925a5ffd249fbf6cbac1870b6d220baf1958ca8c03", root_hex::<Keccak256>),
+        (3, "de70cc195c6bf0599565d74e6bec3b7345c5cab8971473e86162627a764998b1", root_hex::<Keccak256>),
+        (5, "f7931f498928261b94918eed284a9a6d8aa7a79d1a847ce080c32b66d1891cf6", root_hex::<Keccak256>),
+        (2, "683d781ad939facf23aa2a0a84680df60cfccb3514fe57ecbb4d89e9aee89f80", root_hex::<DoubleSha256>),
+        (3, "266f910cb356931f282496728e25e538f4a113a7a2d5550b457cb67ca2c0bfd9", root_hex::<DoubleSha256>),
+        (5, "78e4fa345e54789b3962266fdeb08c9c49660d110d8ef83134257c745b89a575", root_hex::<DoubleSha256>),
+    ];
+    for (leaf_count, expected, root) in cases {
+        assert_eq!(root(leaf_count, Policy::SortedPairs), expected, "{} leaves", leaf_count);
+    }
+}
+
+/// Bitcoin block 100000. Explorers show txids and the root byte-reversed.
+#[test]
+fn duplicate_matches_bitcoin_block_100000() {
+    let txids = [
+        "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+        "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+        "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+        "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d",
+    ];
+    let reversed = |value: &str| {
+        let mut hash = hash(value);
+        hash.reverse();
+        hash
+    };
+
+    let tree = MerkleTree::<DoubleSha256>::from_leaves_with(txids.iter().map(|id| reversed(id)).collect(), Policy::Duplicate);
+    assert_eq!(
+        tree.root(),
+        Some(reversed("f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766"))
+    );
+}
+
+#[test]
+fn every_proof_verifies() {
+    for policy in Policy::ALL {
+        for leaf_count in 1..=TRANSACTIONS.len() {
+            let tree = MerkleTree::<Keccak256>::from_data_with(&TRANSACTIONS[..leaf_count], policy);
+            let root = tree.root().unwrap();
+            for index in 0..leaf_count {
+                let proof = tree.proof(index).unwrap();
+                let leaf = tree.leaf(index).unwrap();
+                let result = verify_proof::<Keccak256>(&proof, leaf, index as u64, leaf_count as u64, &root, policy);
+                assert_eq!(result, Ok(()), "{} leaf {} of {}", policy, index, leaf_count);
+            }
+        }
+    }
+}
+
+#[test]
+fn duplicate_proof_pairs_last_node_with_itself() {
+    let tree = MerkleTree::<Sha256>::from_data_with(TRANSACTIONS, Policy::Duplicate);
+    let proof = tree.proof(4).unwrap();
+
+    assert_eq!(proof.siblings.len(), tree.depth());
+    assert_eq!(proof.siblings[0].hash, proof.leaf);
+    assert_eq!(proof.siblings[0].position, Side::Right);
+}
+
+#[test]
+fn sorted_pairs_proof_ignores_positions() {
+    let tree = MerkleTree::<Keccak256>::from_data_with(TRANSACTIONS, Policy::SortedPairs);
+    let mut proof = tree.proof(2).unwrap();
+    for step in &mut proof.siblings {
+        step.position = match step.position {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        };
+    }
+
+    let root = tree.root().unwrap();
+    assert!(verify_proof::<Keccak256>(&proof, tree.leaf(2).unwrap(), 2, 5, &root, Policy::SortedPairs).is_ok());
+}
@@ -0,0 +1,162 @@
+//! Inclusion proofs through their JSON, hex and binary encodings, and
+//! `verify_proof` rejecting proofs that were tampered with.
+
+use merkle_root::hasher::{Hash, Hasher, Keccak256, Sha256};
+use merkle_root::proof::{verify_proof, MerkleProof, ProofError, Side, VerifyError};
+use merkle_root::tree::{MerkleTree, Policy};
+
+const TRANSACTIONS: [&str; 5] = [
+    "Alice sent 2 eth",
+    "Jacks sent 1 eth",
+    "Mike sent 8 eth",
+    "Richard sent 2 eth",
+    "Key sent 2 eth",
+];
+
+/// Proof of "Mike sent 8 eth" in the five-transaction block, with its root.
+fn mike<H: Hasher>(policy: Policy) -> (MerkleProof, Hash) {
+    let tree = MerkleTree::<H>::from_data_with(TRANSACTIONS, policy);
+    (tree.proof(2).unwrap(), tree.root().unwrap())
+}
+
+fn verify(proof: &MerkleProof, root: &Hash) -> Result<(), VerifyError> {
+    verify_proof::<Sha256>(proof, &Sha256::hash(TRANSACTIONS[2].as_bytes()), 2, 5, root, Policy::Promote)
+}
+
+#[test]
+fn every_encoding_round_trips() {
+    for policy in Policy::ALL {
+        for leaf_count in 1..=TRANSACTIONS.len() {
+            let tree = MerkleTree::<Keccak256>::from_data_with(&TRANSACTIONS[..leaf_count], policy);
+            for index in 0..leaf_count {
+                let proof = tree.proof(index).unwrap();
+                assert_eq!(MerkleProof::from_json(&proof.to_json()).unwrap(), proof);
+                assert_eq!(MerkleProof::from_hex(&proof.to_hex()).unwrap(), proof);
+                assert_eq!(MerkleProof::from_bytes(&proof.to_bytes()).unwrap(), proof);
+            }
+        }
+    }
+}
+
+#[test]
+fn encodings_have_the_documented_layout() {
+    let (proof, _) = mike::<Sha256>(Policy::Promote);
+    let bytes = proof.to_bytes();
+
+    assert_eq!(bytes.len(), 40 + 33 * proof.siblings.len());
+    assert_eq!(bytes[..8], 2u64.to_be_bytes());
+    assert_eq!(bytes[8..40], proof.leaf);
+    assert_eq!(proof.to_hex(), format!("0x{}", hex::encode(&bytes)));
+    // Mike's sibling on the first level is Richard, to the right.
+    assert_eq!(bytes[40], 1);
+    assert!(proof.to_json().contains("\"leafIndex\": 2"));
+}
+
+#[test]
+fn malformed_encodings_are_rejected() {
+    let (proof, _) = mike::<Sha256>(Policy::Promote);
+    let mut bytes = proof.to_bytes();
+
+    assert_eq!(MerkleProof::from_bytes(&bytes[..39]), Err(ProofError::InvalidLength(39)));
+    assert_eq!(MerkleProof::from_bytes(&bytes[..41]), Err(ProofError::InvalidLength(41)));
+    bytes[40] = 2;
+    assert_eq!(MerkleProof::from_bytes(&bytes), Err(ProofError::InvalidSide(2)));
+    assert!(matches!(MerkleProof::from_hex("0xzz"), Err(ProofError::InvalidHex(_))));
+    assert!(matches!(MerkleProof::from_json("{\"leafIndex\": 2}"), Err(ProofError::InvalidJson(_))));
+}
+
+#[test]
+fn untampered_proof_verifies() {
+    let (proof, root) = mike::<Sha256>(Policy::Promote);
+    assert_eq!(verify(&proof, &root), Ok(()));
+}
+
+#[test]
+fn proof_for_other_data_is_rejected() {
+    let (proof, root) = mike::<Sha256>(Policy::Promote);
+    let other = Sha256::hash(TRANSACTIONS[3].as_bytes());
+
+    assert_eq!(verify_proof::<Sha256>(&proof, &other, 2, 5, &root, Policy::Promote), Err(VerifyError::WrongLeaf));
+}
+
+#[test]
+fn proof_for_another_index_is_rejected() {
+    let (proof, root) = mike::<Sha256>(Policy::Promote);
+    let leaf = proof.leaf;
+
+    assert_eq!(
+        verify_proof::<Sha256>(&proof, &leaf, 3, 5, &root, Policy::Promote),
+        Err(VerifyError::WrongIndex { expected: 3, found: 2 })
+    );
+    assert_eq!(
+        verify_proof::<Sha256>(&proof, &leaf, 5, 5, &root, Policy::Promote),
+        Err(VerifyError::IndexOutOfRange { index: 5, leaf_count: 5 })
+    );
+
+    // Relabelling the proof does not help: the sides no longer fit the index.
+    let mut relabelled = proof.clone();
+    relabelled.leaf_index = 3;
+    assert_eq!(
+        verify_proof::<Sha256>(&relabelled, &leaf, 3, 5, &root, Policy::Promote),
+        Err(VerifyError::WrongSide { step: 0 })
+    );
+}
+
+#[test]
+fn wrong_leaf_count_is_rejected() {
+    let (proof, root) = mike::<Sha256>(Policy::Promote);
+
+    // Leaf 2 of five has a three-step path: its last level pairs it with the
+    // promoted fifth leaf. In smaller trees that level does not exist.
+    for (leaf_count, expected) in [(3, 1), (4, 2)] {
+        let result = verify_proof::<Sha256>(&proof, &proof.leaf, 2, leaf_count, &root, Policy::Promote);
+        assert_eq!(result, Err(VerifyError::WrongLength { expected, found: 3 }), "{} leaves", leaf_count);
+    }
+}
+
+#[test]
+fn flipped_side_is_rejected() {
+    let (mut proof, root) = mike::<Sha256>(Policy::Promote);
+    proof.siblings[1].position = Side::Right;
+
+    assert_eq!(verify(&proof, &root), Err(VerifyError::WrongSide { step: 1 }));
+}
+
+#[test]
+fn extra_or_missing_step_is_rejected() {
+    let (proof, root) = mike::<Sha256>(Policy::Promote);
+
+    let mut extra = proof.clone();
+    extra.siblings.push(proof.siblings[0]);
+    assert_eq!(verify(&extra, &root), Err(VerifyError::WrongLength { expected: 3, found: 4 }));
+
+    let mut missing = proof.clone();
+    missing.siblings.pop();
+    assert_eq!(verify(&missing, &root), Err(VerifyError::WrongLength { expected: 3, found: 2 }));
+}
+
+#[test]
+fn altered_sibling_or_root_is_rejected() {
+    let (mut proof, root) = mike::<Sha256>(Policy::Promote);
+
+    let mut other_root = root;
+    other_root[0] ^= 1;
+    assert_eq!(verify(&proof, &other_root), Err(VerifyError::WrongRoot));
+
+    proof.siblings[0].hash[31] ^= 1;
+    assert_eq!(verify(&proof, &root), Err(VerifyError::WrongRoot));
+}
+
+#[test]
+fn duplicate_step_must_repeat_the_node() {
+    let tree = MerkleTree::<Sha256>::from_data_with(TRANSACTIONS, Policy::Duplicate);
+    let mut proof = tree.proof(4).unwrap();
+    let (leaf, root) = (proof.leaf, tree.root().unwrap());
+    assert_eq!(verify_proof::<Sha256>(&proof, &leaf, 4, 5, &root, Policy::Duplicate), Ok(()));
+
+    proof.siblings[0].hash = *tree.leaf(3).unwrap();
+    assert_eq!(
+        verify_proof::<Sha256>(&proof, &leaf, 4, 5, &root, Policy::Duplicate),
+        Err(VerifyError::NotDuplicate { step: 0 })
+    );
+}
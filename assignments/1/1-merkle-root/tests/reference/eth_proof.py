@@ -0,0 +1,163 @@
+"""Holesky genesis state root, header and `eth_getProof` responses for
+tests/eth_proof.rs.
+
+Builds the state trie from the genesis allocation with patricia.py, encodes the
+genesis header around that state root, and prints the header's hash, which
+must be the published Holesky genesis hash. Given an output directory it
+writes the header and the responses there.
+
+    python3 eth_proof.py ../fixtures/eth_getProof/holesky_genesis.json [outdir]
+"""
+
+import json
+import os
+import sys
+
+from keccak import keccak256
+from patricia import nibbles, node, rlp
+
+EMPTY_ROOT = keccak256(b"\x80")
+EMPTY_CODE_HASH = keccak256(b"")
+EMPTY_OMMERS_HASH = keccak256(b"\xc0")
+
+
+def uint(value):
+    """Minimal big-endian bytes of a hex quantity, empty for zero."""
+    n = int(value, 16) if isinstance(value, str) else value
+    return n.to_bytes((n.bit_length() + 7) // 8, "big")
+
+
+def sorted_items(mapping):
+    return sorted((nibbles(key), value) for key, value in mapping.items() if value)
+
+
+def trie_root(mapping):
+    return keccak256(node(sorted_items(mapping), 0))
+
+
+def prove(mapping, key):
+    """Nodes on the path to `key`, root first, as `eth_getProof` lists them:
+    nodes under 32 bytes sit inside their parent and are not listed."""
+    items = sorted_items(mapping)
+    path = nibbles(key)
+    proof = []
+    if not items:
+        return proof
+
+    def walk(items, depth, inline):
+        encoded = node(items, depth)
+        if not inline:
+            proof.append(encoded)
+        if len(items) == 1:
+            return
+        first = items[0][0]
+        end = depth
+        while all(len(p) > end and p[end] == first[end] for p, _ in items):
+            end += 1
+        if end > depth:
+            if path[depth:end] == first[depth:end]:
+                walk(items, end, len(node(items, end)) < 32)
+            return
+        if len(path) == depth:
+            return
+        children = [(p, v) for p, v in items if len(p) > depth and p[depth] == path[depth]]
+        if children:
+            walk(children, depth + 1, len(node(children, depth + 1)) < 32)
+
+    walk(items, 0, False)
+    return proof
+
+
+def slot_key(slot):
+    return int(slot, 16).to_bytes(32, "big")
+
+
+def build_state(alloc):
+    state, storages = {}, {}
+    for address, account in alloc.items():
+        address = bytes.fromhex(address.removeprefix("0x"))
+        storage = {
+            keccak256(slot_key(slot)): rlp(uint(value))
+            for slot, value in account.get("storage", {}).items()
+            if int(value, 16)
+        }
+        code = bytes.fromhex(account.get("code", "0x")[2:])
+        fields = [uint(account.get("nonce", "0x0")), uint(account["balance"]), trie_root(storage), keccak256(code)]
+        values = {int(slot, 16): int(value, 16) for slot, value in account.get("storage", {}).items()}
+        state[keccak256(address)] = rlp(fields)
+        storages[address] = (fields, storage, values)
+    return state, storages
+
+
+def header(genesis, state_root):
+    fields = [
+        bytes(32),
+        EMPTY_OMMERS_HASH,
+        bytes.fromhex(genesis["coinbase"][2:]),
+        state_root,
+        EMPTY_ROOT,
+        EMPTY_ROOT,
+        bytes(256),
+        uint(genesis["difficulty"]),
+        uint(genesis["number"]),
+        uint(genesis["gasLimit"]),
+        uint(genesis["gasUsed"]),
+        uint(genesis["timestamp"]),
+        bytes.fromhex(genesis["extraData"][2:]),
+        bytes.fromhex(genesis["mixHash"][2:]),
+        int(genesis["nonce"], 16).to_bytes(8, "big"),
+        # London from genesis: the initial base fee of EIP-1559.
+        uint(1_000_000_000),
+    ]
+    return rlp(fields)
+
+
+def quantity(data):
+    return hex(int.from_bytes(data, "big"))
+
+
+def get_proof(state, storages, address, slots):
+    address = bytes.fromhex(address[2:])
+    fields, storage, values = storages.get(address, ([b"", b"", EMPTY_ROOT, EMPTY_CODE_HASH], {}, {}))
+    return {
+        "address": "0x" + address.hex(),
+        "accountProof": ["0x" + n.hex() for n in prove(state, keccak256(address))],
+        "balance": quantity(fields[1]),
+        "codeHash": "0x" + fields[3].hex(),
+        "nonce": quantity(fields[0]),
+        "storageHash": "0x" + fields[2].hex(),
+        "storageProof": [
+            {
+                "key": "0x" + slot_key(slot).hex(),
+                "value": hex(values.get(int(slot, 16), 0)),
+                "proof": ["0x" + n.hex() for n in prove(storage, keccak256(slot_key(slot)))],
+            }
+            for slot in slots
+        ],
+    }
+
+
+# Accounts to prove: the deposit contract with a set and an unset slot, an
+# account holding ether, and one that does not exist.
+REQUESTS = {
+    "holesky_deposit_contract.json": ("0x4242424242424242424242424242424242424242", ["0x22", "0x0"]),
+    "holesky_funded_account.json": ("0x0000000000000000000000000000000000000001", []),
+    "holesky_missing_account.json": ("0x000000000000000000000000000000000000dead", ["0x0"]),
+}
+
+if __name__ == "__main__":
+    genesis = json.load(open(sys.argv[1]))
+    state, storages = build_state(genesis["alloc"])
+    state_root = trie_root(state)
+    encoded = header(genesis, state_root)
+    print("state root", state_root.hex())
+    print("block hash", keccak256(encoded).hex())
+
+    if len(sys.argv) > 2:
+        outdir = sys.argv[2]
+        with open(os.path.join(outdir, "holesky_genesis_header.hex"), "w") as out:
+            out.write(encoded.hex() + "\n")
+        for name, (address, slots) in REQUESTS.items():
+            with open(os.path.join(outdir, name), "w") as out:
+                json.dump(get_proof(state, storages, address, slots), out, indent=2)
+                out.write("\n")
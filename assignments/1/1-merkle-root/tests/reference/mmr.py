@@ -0,0 +1,61 @@
+"""Merkle mountain range roots and node counts for tests/mmr.rs.
+
+Position-based, as in the MMR papers: nodes are appended in post-order, the
+height of a position comes from its binary form, and a parent is appended
+whenever the node just added completes a pair. The root bags the peaks from
+the right: the last peak is hashed under each earlier one in turn.
+
+    python3 tests/reference/mmr.py
+"""
+
+import hashlib
+
+
+def sha256(data):
+    return hashlib.sha256(data).digest()
+
+
+def height(position):
+    """Height of the node at 0-based `position`."""
+    position += 1
+    while (position + 1) & position:  # not all ones: jump to the left sibling
+        position -= (1 << (position.bit_length() - 1)) - 1
+    return position.bit_length() - 1
+
+
+def build(leaf_count):
+    nodes = []
+    for i in range(leaf_count):
+        nodes.append(sha256(bytes([i])))
+        h = 0
+        while height(len(nodes)) > h:
+            last = len(nodes)
+            nodes.append(sha256(nodes[last - (2 << h)] + nodes[last - 1]))
+            h += 1
+    return nodes
+
+
+def peaks(size):
+    """Positions of the peaks of an MMR with `size` nodes, left to right."""
+    positions, offset = [], 0
+    while size:
+        h = size.bit_length()
+        while (1 << h) - 1 > size:
+            h -= 1
+        offset += (1 << h) - 1
+        positions.append(offset - 1)
+        size -= (1 << h) - 1
+    return positions
+
+
+def root(nodes):
+    tops = [nodes[p] for p in peaks(len(nodes))]
+    bagged = tops[-1]
+    for top in reversed(tops[:-1]):
+        bagged = sha256(top + bagged)
+    return bagged
+
+
+for leaf_count in [1, 2, 3, 4, 5, 7, 8, 11, 19]:
+    nodes = build(leaf_count)
+    print(leaf_count, len(nodes), root(nodes).hex())
@@ -0,0 +1,119 @@
+"""Patricia trie roots for tests/patricia.rs.
+
+Builds the trie in one pass from the final key set, following the Yellow
+Paper's node definitions, so it knows nothing of insertion order or removals.
+Run without arguments it prints the roots of the removal test; given
+ethereum/tests TrieTests files it checks their cases too.
+
+    python3 tests/reference/patricia.py [../fixtures/TrieTests/*.json]
+"""
+
+import json
+import sys
+
+from keccak import keccak256
+
+
+class Raw(bytes):
+    """An already encoded node embedded in its parent."""
+
+
+def rlp(item):
+    if isinstance(item, bytes):
+        if len(item) == 1 and item[0] < 0x80:
+            return item
+        return rlp_length(len(item), 0x80) + item
+    body = b"".join(child if isinstance(child, Raw) else rlp(child) for child in item)
+    return rlp_length(len(body), 0xC0) + body
+
+
+def rlp_length(length, offset):
+    if length < 56:
+        return bytes([offset + length])
+    encoded = length.to_bytes((length.bit_length() + 7) // 8, "big")
+    return bytes([offset + 55 + len(encoded)]) + encoded
+
+
+def nibbles(key):
+    return [n for byte in key for n in (byte >> 4, byte & 15)]
+
+
+def hex_prefix(path, leaf):
+    flag = 2 if leaf else 0
+    path = [flag + 1] + path if len(path) % 2 else [flag, 0] + path
+    return bytes(path[i] * 16 + path[i + 1] for i in range(0, len(path), 2))
+
+
+def reference(encoded):
+    """How a parent refers to a child: inline under 32 bytes, else by hash."""
+    return Raw(encoded) if len(encoded) < 32 else keccak256(encoded)
+
+
+def node(items, depth):
+    """Encoding of the node holding `items`, (nibbles, value) pairs sharing
+    their first `depth` nibbles."""
+    if not items:
+        return b"\x80"
+    if len(items) == 1:
+        path, value = items[0]
+        return rlp([hex_prefix(path[depth:], True), value])
+
+    first = items[0][0]
+    end = depth
+    while all(len(path) > end and path[end] == first[end] for path, _ in items):
+        end += 1
+    if end > depth:
+        return rlp([hex_prefix(first[depth:end], False), reference(node(items, end))])
+
+    branch = []
+    for n in range(16):
+        children = [(path, value) for path, value in items if len(path) > depth and path[depth] == n]
+        branch.append(reference(node(children, depth + 1)) if children else b"")
+    values = [value for path, value in items if len(path) == depth]
+    branch.append(values[0] if values else b"")
+    return rlp(branch)
+
+
+def root(mapping):
+    items = sorted((nibbles(key), value) for key, value in mapping.items() if value)
+    return keccak256(node(items, 0))
+
+
+def fixture_bytes(value):
+    if value is None:
+        return b""
+    return bytes.fromhex(value[2:]) if value.startswith("0x") else value.encode()
+
+
+def check_fixtures(path):
+    secure = "secureTrie" in path
+    for name, case in json.load(open(path)).items():
+        pairs = case["in"].items() if isinstance(case["in"], dict) else case["in"]
+        mapping = {}
+        for key, value in pairs:
+            key, value = fixture_bytes(key), fixture_bytes(value)
+            if secure:
+                key = keccak256(key)
+            if value:
+                mapping[key] = value
+            else:
+                mapping.pop(key, None)
+        computed = "0x" + root(mapping).hex()
+        print(path, name, "ok" if computed == case["root"] else "MISMATCH " + computed)
+
+
+def key(i):
+    return keccak256(i.to_bytes(2, "big"))[:8]
+
+
+def value(i):
+    return (str(i) * (i % 7 + 1)).encode()
+
+
+if __name__ == "__main__":
+    if sys.argv[1:]:
+        for path in sys.argv[1:]:
+            check_fixtures(path)
+    else:
+        print("100 keys", root({key(i): value(i) for i in range(100)}).hex())
+        print("every third removed", root({key(i): value(i) for i in range(100) if i % 3}).hex())
@@ -0,0 +1,48 @@
+"""Roots of the demo block under each pairing policy, for tests/policies.rs.
+
+Written from the policy descriptions alone, not from src/tree.rs: a level is
+paired left to right and an unpaired last node is promoted, paired with
+itself (Bitcoin), or the pair is sorted before hashing (OpenZeppelin).
+
+    python3 tests/reference/policies.py
+"""
+
+import hashlib
+
+from keccak import keccak256
+
+TRANSACTIONS = [b"Alice sent 2 eth", b"Jacks sent 1 eth", b"Mike sent 8 eth", b"Richard sent 2 eth", b"Key sent 2 eth"]
+
+
+def sha256(data):
+    return hashlib.sha256(data).digest()
+
+
+def double_sha256(data):
+    return sha256(sha256(data))
+
+
+def root(leaves, hash_fn, policy):
+    level = list(leaves)
+    while len(level) > 1:
+        parents = []
+        for i in range(0, len(level), 2):
+            if i + 1 < len(level):
+                left, right = level[i], level[i + 1]
+            elif policy == "duplicate":
+                left, right = level[i], level[i]
+            else:
+                parents.append(level[i])
+                continue
+            if policy == "sorted-pairs" and right < left:
+                left, right = right, left
+            parents.append(hash_fn(left + right))
+        level = parents
+    return level[0]
+
+
+for policy in ["promote", "duplicate", "sorted-pairs"]:
+    for name, hash_fn in [("sha256", sha256), ("keccak256", keccak256), ("double-sha256", double_sha256)]:
+        for leaf_count in [1, 2, 3, 5]:
+            leaves = [hash_fn(tx) for tx in TRANSACTIONS[:leaf_count]]
+            print(policy, name, leaf_count, root(leaves, hash_fn, policy).hex())
@@ -0,0 +1,49 @@
+"""Sparse Merkle tree roots for tests/sparse.rs.
+
+Recursive over the key bits instead of storing nodes: a subtree holding no
+non-empty leaf is the default hash of its height, a single level-256 node is
+the leaf value itself, and anything else hashes its two halves.
+
+    python3 tests/reference/sparse.py
+"""
+
+import hashlib
+
+from keccak import keccak256
+
+DEPTH = 256
+EMPTY_LEAF = bytes(32)
+
+
+def sha256(data):
+    return hashlib.sha256(data).digest()
+
+
+def root(hash_fn, items):
+    defaults = [EMPTY_LEAF]
+    for _ in range(DEPTH):
+        defaults.append(hash_fn(defaults[-1] + defaults[-1]))
+
+    def bit(key, depth):
+        return (int.from_bytes(key, "big") >> (DEPTH - 1 - depth)) & 1
+
+    def subtree(items, depth):
+        if not items:
+            return defaults[DEPTH - depth]
+        if depth == DEPTH:
+            return items[0][1]
+        left = [item for item in items if bit(item[0], depth) == 0]
+        right = [item for item in items if bit(item[0], depth) == 1]
+        return hash_fn(subtree(left, depth + 1) + subtree(right, depth + 1))
+
+    return subtree([item for item in items if item[1] != EMPTY_LEAF], 0)
+
+
+print("empty sha256", root(sha256, []).hex())
+print("empty keccak256", root(keccak256, []).hex())
+
+# Three blocked addresses 0xdead0000..0xdead0002, keyed by keccak256(address).
+addresses = [(0xDEAD0000 + i).to_bytes(20, "big") for i in range(3)]
+print("blocklist", root(keccak256, [(keccak256(a), keccak256(b"blocked")) for a in addresses]).hex())
+
+print("sha256 five", root(sha256, [(sha256(bytes([i])), sha256(bytes([i, i]))) for i in range(5)]).hex())
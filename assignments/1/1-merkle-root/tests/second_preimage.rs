@@ -0,0 +1,130 @@
+//! The second-preimage attack on plain Merkle trees, and RFC 6962 domain
+//! separation stopping it.
+
+use merkle_root::hasher::{DoubleSha256, Hash, Hasher, Keccak256, Rfc6962, Sha256};
+use merkle_root::proof::{verify_proof, MerkleProof, ProofStep, Side};
+use merkle_root::tree::{MerkleTree, Policy};
+
+const TRANSACTIONS: [&str; 4] = ["Alice sent 2 eth", "Jacks sent 1 eth", "Mike sent 8 eth", "Richard sent 2 eth"];
+
+fn concat(left: &Hash, right: &Hash) -> Vec<u8> {
+    [left.as_slice(), right.as_slice()].concat()
+}
+
+/// Claims the first internal node of `tree` is a 64-byte leaf, the first of a
+/// two-leaf tree, proven by its sibling one level up. Returns the forged data
+/// and its proof.
+fn forge<H: Hasher>(tree: &MerkleTree<H>) -> (Vec<u8>, MerkleProof) {
+    let data = concat(tree.leaf(0).unwrap(), tree.leaf(1).unwrap());
+    let proof = MerkleProof {
+        leaf_index: 0,
+        leaf: H::hash(&data),
+        siblings: vec![ProofStep {
+            hash: *tree.node(1, 1).unwrap(),
+            position: Side::Right,
+        }],
+    };
+    (data, proof)
+}
+
+/// What a verifier does with claimed data: hash it itself, then check the path
+/// for the claimed position.
+fn accepts<H: Hasher>(data: &[u8], proof: &MerkleProof, leaf_count: u64, root: &Hash) -> bool {
+    verify_proof::<H>(proof, &H::hash(data), proof.leaf_index, leaf_count, root, Policy::Promote).is_ok()
+}
+
+/// The attack against a verifier that takes the tree size from the prover.
+fn attack_succeeds<H: Hasher>() -> bool {
+    let tree = MerkleTree::<H>::from_data(TRANSACTIONS);
+    let (data, proof) = forge(&tree);
+
+    assert!(!TRANSACTIONS.iter().any(|tx| tx.as_bytes() == data.as_slice()));
+    accepts::<H>(&data, &proof, 2, &tree.root().unwrap())
+}
+
+#[test]
+fn legacy_mode_accepts_an_internal_node_as_a_leaf() {
+    assert!(attack_succeeds::<Sha256>());
+    assert!(attack_succeeds::<Keccak256>());
+}
+
+#[test]
+fn knowing_the_leaf_count_rejects_the_forgery() {
+    let tree = MerkleTree::<Sha256>::from_data(TRANSACTIONS);
+    let (data, proof) = forge(&tree);
+
+    assert!(!accepts::<Sha256>(&data, &proof, TRANSACTIONS.len() as u64, &tree.root().unwrap()));
+}
+
+#[test]
+fn legacy_mode_gives_a_forged_tree_the_same_root() {
+    let tree = MerkleTree::<Sha256>::from_data(TRANSACTIONS);
+    let forged = [
+        concat(tree.leaf(0).unwrap(), tree.leaf(1).unwrap()),
+        concat(tree.leaf(2).unwrap(), tree.leaf(3).unwrap()),
+    ];
+
+    assert_eq!(MerkleTree::<Sha256>::from_data(forged).root(), tree.root());
+}
+
+#[test]
+fn hardened_mode_rejects_an_internal_node_as_a_leaf() {
+    assert!(!attack_succeeds::<Rfc6962<Sha256>>());
+    assert!(!attack_succeeds::<Rfc6962<Keccak256>>());
+}
+
+#[test]
+fn hardened_mode_gives_a_forged_tree_another_root() {
+    let tree = MerkleTree::<Rfc6962<Sha256>>::from_data(TRANSACTIONS);
+    let forged = [
+        concat(tree.leaf(0).unwrap(), tree.leaf(1).unwrap()),
+        concat(tree.leaf(2).unwrap(), tree.leaf(3).unwrap()),
+    ];
+
+    assert_ne!(MerkleTree::<Rfc6962<Sha256>>::from_data(forged).root(), tree.root());
+}
+
+#[test]
+fn hardened_mode_still_proves_real_leaves() {
+    let tree = MerkleTree::<Rfc6962<Sha256>>::from_data(TRANSACTIONS);
+    let root = tree.root().unwrap();
+
+    for (index, tx) in TRANSACTIONS.iter().enumerate() {
+        let proof = tree.proof(index).unwrap();
+        assert!(accepts::<Rfc6962<Sha256>>(tx.as_bytes(), &proof, TRANSACTIONS.len() as u64, &root));
+    }
+}
+
+/// Roots from the Certificate Transparency reference test data.
+#[test]
+fn matches_rfc6962_reference_roots() {
+    let leaves = ["", "00", "10", "2021", "3031", "40414243", "5051525354555657", "606162636465666768696a6b6c6d6e6f"];
+    let roots = [
+        "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+        "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+        "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+        "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+        "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+        "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+        "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+        "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+    ];
+    let data: Vec<Vec<u8>> = leaves.iter().map(|leaf| hex::decode(leaf).unwrap()).collect();
+
+    for (size, expected) in roots.iter().enumerate().map(|(i, root)| (i + 1, root)) {
+        let tree = MerkleTree::<Rfc6962<Sha256>>::from_data(&data[..size]);
+        assert_eq!(hex::encode(tree.root().unwrap()), *expected, "{} leaves", size);
+    }
+}
+
+/// The wrapped hashers give different roots, so their names differ too.
+#[test]
+fn rfc6962_names_its_hasher() {
+    assert_eq!(Rfc6962::<Sha256>::NAME, "rfc6962-sha256");
+    assert_eq!(Rfc6962::<Keccak256>::NAME, "rfc6962-keccak256");
+    assert_eq!(Rfc6962::<DoubleSha256>::NAME, "rfc6962-double-sha256");
+    assert_ne!(
+        MerkleTree::<Rfc6962<Sha256>>::from_data(TRANSACTIONS).root(),
+        MerkleTree::<Rfc6962<Keccak256>>::from_data(TRANSACTIONS).root()
+    );
+}
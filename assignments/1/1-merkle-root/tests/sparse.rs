@@ -0,0 +1,179 @@
+//! Sparse Merkle tree roots and proofs. The roots come from
+//! `tests/reference/sparse.py`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use merkle_root::hasher::{Hash, Hasher, Keccak256, Sha256};
+use merkle_root::sparse::{SparseMerkleTree, SparseProof, SparseProofError, EMPTY_LEAF};
+
+fn hash(value: &str) -> Hash {
+    hex::decode(value).unwrap().try_into().unwrap()
+}
+
+/// Blocklist keyed by the keccak hash of each address.
+fn blocklist() -> SparseMerkleTree<Keccak256> {
+    let mut tree = SparseMerkleTree::new();
+    tree.update((0..3u64).map(|i| (address_key(0xdead0000 + i), Keccak256::hash(b"blocked"))));
+    tree
+}
+
+fn address_key(address: u64) -> Hash {
+    let mut bytes = [0u8; 20];
+    bytes[12..].copy_from_slice(&address.to_be_bytes());
+    Keccak256::hash(&bytes)
+}
+
+#[test]
+fn empty_roots() {
+    assert_eq!(
+        SparseMerkleTree::<Sha256>::new().root(),
+        hash("b178c245c947ea7e21ecede07728941a6ab1b706143c06873baff8ebd6de6308")
+    );
+    assert_eq!(
+        SparseMerkleTree::<Keccak256>::new().root(),
+        hash("a7ff9e28ffd3def443d324547688c2c4eb98edf7da757d6bfa22bff55b9ce24a")
+    );
+}
+
+/// Empty subtrees hash like the deposit contract's zero hashes, which its
+/// storage in the Holesky genesis holds at slot `0x21 + height`.
+#[test]
+fn default_hashes_match_the_deposit_contract() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/deposit_contract");
+    let path = dir.join("holesky_genesis_storage.json");
+    let storage: HashMap<String, String> = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    let tree = SparseMerkleTree::<Sha256>::new();
+
+    assert_eq!(tree.default_hashes()[0], EMPTY_LEAF);
+    for height in 1..32 {
+        let slot = format!("0x{:064x}", 0x21 + height);
+        assert_eq!(storage[&slot], format!("0x{}", hex::encode(tree.default_hashes()[height])), "height {}", height);
+    }
+}
+
+#[test]
+fn matches_reference_roots() {
+    assert_eq!(blocklist().root(), hash("62e751bcd66314ded45dc207242f89f2d170bb8909094ceb9f8ff9b0a7231bc9"));
+
+    let mut tree = SparseMerkleTree::<Sha256>::new();
+    for i in 0..5u8 {
+        tree.insert(Sha256::hash(&[i]), Sha256::hash(&[i, i]));
+    }
+    assert_eq!(tree.root(), hash("acf30d161f42e22751673eff5d2cf36059a1b6cc13a6dee72bdd1f89322f989b"));
+    assert_eq!(tree.len(), 5);
+}
+
+#[test]
+fn proves_a_blocked_address() {
+    let tree = blocklist();
+    let proof = tree.prove(&address_key(0xdead0001));
+
+    assert!(proof.is_inclusion());
+    assert_eq!(proof.value, Keccak256::hash(b"blocked"));
+    assert!(proof.verify::<Keccak256>(&tree.root()));
+}
+
+#[test]
+fn proves_an_address_is_not_blocked() {
+    let tree = blocklist();
+    let proof = tree.prove(&address_key(0xbeef));
+
+    assert!(!proof.is_inclusion());
+    assert!(proof.verify::<Keccak256>(&tree.root()));
+
+    // Claiming the address is blocked does not fit the same path.
+    let forged = SparseProof { value: Keccak256::hash(b"blocked"), ..proof };
+    assert!(!forged.verify::<Keccak256>(&tree.root()));
+}
+
+#[test]
+fn exclusion_cannot_be_proven_for_a_member() {
+    let tree = blocklist();
+    let proof = tree.prove(&address_key(0xdead0002));
+    let denied = SparseProof { value: EMPTY_LEAF, ..proof };
+
+    assert!(!denied.verify::<Keccak256>(&tree.root()));
+}
+
+#[test]
+fn neighbouring_keys() {
+    let mut tree = SparseMerkleTree::<Sha256>::new();
+    let left = [0u8; 32];
+    let mut right = [0u8; 32];
+    right[31] = 1;
+    tree.insert(left, Sha256::hash(b"left"));
+    tree.insert(right, Sha256::hash(b"right"));
+
+    // The two leaves are siblings, so each proof carries exactly one hash.
+    for key in [left, right] {
+        let proof = tree.prove(&key);
+        assert_eq!(proof.siblings.len(), 1);
+        assert!(proof.verify::<Sha256>(&tree.root()));
+    }
+
+    let mut far = [0u8; 32];
+    far[0] = 0x80;
+    let proof = tree.prove(&far);
+    assert_eq!(proof.siblings.len(), 1);
+    assert!(!proof.is_inclusion());
+    assert!(proof.verify::<Sha256>(&tree.root()));
+}
+
+#[test]
+fn batch_matches_one_at_a_time_in_any_order() {
+    let entries: Vec<(Hash, Hash)> = (0..50u8).map(|i| (Sha256::hash(&[i]), Sha256::hash(&[i, 1]))).collect();
+
+    let mut batched = SparseMerkleTree::<Sha256>::new();
+    batched.update(entries.iter().copied());
+
+    let mut single = SparseMerkleTree::<Sha256>::new();
+    for (key, value) in entries.iter().rev() {
+        single.insert(*key, *value);
+    }
+
+    assert_eq!(batched.root(), single.root());
+    assert_eq!(batched.len(), 50);
+}
+
+#[test]
+fn removing_every_key_empties_the_tree() {
+    let mut tree = blocklist();
+    tree.update((0..3u64).map(|i| (address_key(0xdead0000 + i), EMPTY_LEAF)));
+
+    assert!(tree.is_empty());
+    assert_eq!(tree.root(), SparseMerkleTree::<Keccak256>::new().root());
+}
+
+#[test]
+fn updating_a_value_changes_the_root_back_and_forth() {
+    let mut tree = blocklist();
+    let before = tree.root();
+    let key = address_key(0xdead0000);
+
+    tree.insert(key, Keccak256::hash(b"cleared on appeal"));
+    assert_ne!(tree.root(), before);
+    assert_eq!(tree.len(), 3);
+
+    tree.insert(key, Keccak256::hash(b"blocked"));
+    assert_eq!(tree.root(), before);
+}
+
+#[test]
+fn malformed_proofs_are_rejected() {
+    let tree = blocklist();
+    let mut proof = tree.prove(&address_key(0xdead0000));
+    proof.siblings.pop();
+
+    assert!(matches!(proof.compute_root::<Keccak256>(), Err(SparseProofError::SiblingCount { .. })));
+    assert!(!proof.verify::<Keccak256>(&tree.root()));
+}
+
+#[test]
+fn json_round_trips() {
+    let proof = blocklist().prove(&address_key(0xbeef));
+    let json = serde_json::to_string(&proof).unwrap();
+
+    assert_eq!(serde_json::from_str::<SparseProof>(&json).unwrap(), proof);
+}
@@ -0,0 +1,136 @@
+//! `StandardMerkleTree` output from `@openzeppelin/merkle-tree` that ours has
+//! to reproduce byte for byte.
+
+use merkle_root::hasher::Hash;
+use merkle_root::standard::{leaf_hash, process_proof, verify, StandardMerkleTree, StandardTreeError};
+
+const ENCODING: [&str; 2] = ["address", "uint256"];
+
+/// Allowlist of escrow buyers and the most each may deposit.
+const ALLOWLIST: [[&str; 2]; 3] = [
+    ["0x70997970C51812dc3A010C7d01b50e0d17dc79C8", "1000000000000000000"],
+    ["0x3C44CdDdB6a900fa2b585dd299e03d12FA4293BC", "2500000000000000000"],
+    ["0x90F79bf6EB2c4f870365E785982E1f101E93b906", "500000000000000000"],
+];
+
+const ALLOWLIST_DUMP: &str = r#"{"format":"standard-v1","tree":["0x8cfc4b8e63242e113381f1c9d9f758de36ac04ac2681f7f680fa4324fea04e29","0xa68135865881453ab8107aa43a4b0a7fe13d6df1422bc0ddb54947c8d09aae6b","0xb661aa6a38fe3ed2b37c1b85a2b1033bb006af05c7342f27772aad4de7468d52","0x9574e60e39d6310b20a0c3f928e702daf13ed8bc40496dbd055c026c3047ad81","0x48b89d46ac36bc91cff52fda8f367663966eeb47da22a76a3f52c1ed976504ee"],"values":[{"value":["0x70997970C51812dc3A010C7d01b50e0d17dc79C8","1000000000000000000"],"treeIndex":4},{"value":["0x3C44CdDdB6a900fa2b585dd299e03d12FA4293BC","2500000000000000000"],"treeIndex":3},{"value":["0x90F79bf6EB2c4f870365E785982E1f101E93b906","500000000000000000"],"treeIndex":2}],"leafEncoding":["address","uint256"]}"#;
+
+fn hash(value: &str) -> Hash {
+    hex::decode(value.trim_start_matches("0x")).unwrap().try_into().unwrap()
+}
+
+/// The example from the `@openzeppelin/merkle-tree` README.
+#[test]
+fn matches_readme_example() {
+    let values = [
+        ["0x1111111111111111111111111111111111111111", "5000000000000000000"],
+        ["0x2222222222222222222222222222222222222222", "2500000000000000000"],
+    ];
+    let tree = StandardMerkleTree::of(&values, &ENCODING).unwrap();
+
+    assert_eq!(tree.root(), hash("0xd4dee0beab2d53f2cc83e567171bd2820e49898130a22622b10ead383e90bd77"));
+}
+
+#[test]
+fn dump_is_byte_identical() {
+    let tree = StandardMerkleTree::of(&ALLOWLIST, &ENCODING).unwrap();
+
+    assert_eq!(tree.to_json(), ALLOWLIST_DUMP);
+}
+
+#[test]
+fn proofs_match_and_verify() {
+    let tree = StandardMerkleTree::of(&ALLOWLIST, &ENCODING).unwrap();
+    let expected = [
+        vec![
+            hash("9574e60e39d6310b20a0c3f928e702daf13ed8bc40496dbd055c026c3047ad81"),
+            hash("b661aa6a38fe3ed2b37c1b85a2b1033bb006af05c7342f27772aad4de7468d52"),
+        ],
+        vec![
+            hash("48b89d46ac36bc91cff52fda8f367663966eeb47da22a76a3f52c1ed976504ee"),
+            hash("b661aa6a38fe3ed2b37c1b85a2b1033bb006af05c7342f27772aad4de7468d52"),
+        ],
+        vec![hash("a68135865881453ab8107aa43a4b0a7fe13d6df1422bc0ddb54947c8d09aae6b")],
+    ];
+
+    for (index, value) in ALLOWLIST.iter().enumerate() {
+        let proof = tree.proof(index).unwrap();
+        assert_eq!(proof, expected[index], "proof {}", index);
+        assert!(verify(&tree.root(), &ENCODING, value, &proof).unwrap());
+    }
+    assert_eq!(tree.proof(3), None);
+}
+
+#[test]
+fn rejects_a_changed_value() {
+    let tree = StandardMerkleTree::of(&ALLOWLIST, &ENCODING).unwrap();
+    let proof = tree.proof(0).unwrap();
+    let raised = ["0x70997970C51812dc3A010C7d01b50e0d17dc79C8", "2000000000000000000"];
+
+    assert!(!verify(&tree.root(), &ENCODING, &raised, &proof).unwrap());
+}
+
+/// An internal node is 64 bytes of input, like an (address, uint256) leaf,
+/// but leaves are hashed twice so one cannot stand in for the other.
+#[test]
+fn internal_node_is_not_a_leaf() {
+    let tree = StandardMerkleTree::of(&ALLOWLIST, &ENCODING).unwrap();
+    let node = tree.nodes()[1];
+
+    assert_eq!(process_proof(&node, &[tree.nodes()[2]]), tree.root());
+    for value in tree.values() {
+        assert_ne!(tree.nodes()[value.tree_index], node);
+    }
+}
+
+#[test]
+fn index_of_ignores_address_case() {
+    let tree = StandardMerkleTree::of(&ALLOWLIST, &ENCODING).unwrap();
+    let lowercase = ["0x3c44cdddb6a900fa2b585dd299e03d12fa4293bc", "2500000000000000000"];
+
+    assert_eq!(tree.index_of(&lowercase).unwrap(), Some(1));
+    assert_eq!(tree.index_of(&["0x3c44cdddb6a900fa2b585dd299e03d12fa4293bc", "1"]).unwrap(), None);
+}
+
+#[test]
+fn loads_a_dump() {
+    let tree = StandardMerkleTree::from_json(ALLOWLIST_DUMP).unwrap();
+
+    assert_eq!(tree.root(), StandardMerkleTree::of(&ALLOWLIST, &ENCODING).unwrap().root());
+    assert_eq!(tree.to_json(), ALLOWLIST_DUMP);
+
+    // JS keeps numbers the caller passed unquoted.
+    let unquoted = ALLOWLIST_DUMP.replace("\"500000000000000000\"", "500000000000000000");
+    assert!(StandardMerkleTree::from_json(&unquoted).is_ok());
+}
+
+#[test]
+fn rejects_a_tampered_dump() {
+    let tampered = ALLOWLIST_DUMP.replace("2500000000000000000", "9500000000000000000");
+
+    assert!(matches!(StandardMerkleTree::from_json(&tampered), Err(StandardTreeError::InvalidDump(_))));
+}
+
+#[test]
+fn rejects_values_that_do_not_encode() {
+    let values = [["0x1234", "1"]];
+
+    assert!(matches!(StandardMerkleTree::of(&values, &ENCODING), Err(StandardTreeError::InvalidValue { .. })));
+    assert!(matches!(StandardMerkleTree::of::<[&str; 2], &str>(&[], &ENCODING), Err(StandardTreeError::NoValues)));
+    assert_eq!(
+        leaf_hash(&["uint8"], &["256"]),
+        Err(StandardTreeError::InvalidValue {
+            ty: "uint8".to_string(),
+            value: "256".to_string(),
+        })
+    );
+    assert_eq!(leaf_hash(&["int256"], &["1"]), Err(StandardTreeError::UnsupportedType("int256".to_string())));
+    assert_eq!(leaf_hash(&ENCODING, &["1"]), Err(StandardTreeError::ValueCount { expected: 2, got: 1 }));
+}
+
+/// A dynamic value is encoded after an offset word, then its length.
+#[test]
+fn string_leaves_encode_with_an_offset() {
+    let leaf = leaf_hash(&["string"], &["Alice sent 2 eth"]).unwrap();
+    assert_eq!(hex::encode(leaf), "e9a38478795d8ccbd911a14b4436dd35ba7ef5ff1f6db476716f9d177d79173b");
+}
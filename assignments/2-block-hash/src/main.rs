@@ -1,8 +1,10 @@
 use rlp::RlpStream;
 use ethereum_types::{H256, U256, Address};
 use tiny_keccak::{Hasher, Keccak};
-use sha2::{Sha256, Digest};
 use colored::*;
+use gas::units::{Ether, UnitError};
+use merkle_root::hasher::{Hasher as _, Sha256};
+use merkle_root::tree::MerkleTree;
 
 fn keccak256(data: &[u8]) -> [u8; 32] {
     let mut hasher = Keccak::v256();
@@ -23,9 +25,15 @@ impl Transaction {
             tx: String::from(metadata) 
         }
     }
+
+    /// Amount moved by a transaction written as "<name> sent <amount>".
+    fn value(&self) -> Result<Ether, UnitError> {
+        let amount = self.tx.split_once(" sent ").map_or("", |(_, amount)| amount);
+        amount.parse()
+    }
 }
 
-fn ethereum_block_hash() -> H256 {
+fn ethereum_block_hash() -> (H256, Ether) {
       let tx1 = Transaction::new("Alice sent 2 eth");
     let tx2 = Transaction::new("Jacks sent 1 eth");
     let tx3 = Transaction::new("Mike sent 8 eth");
@@ -34,12 +42,13 @@ fn ethereum_block_hash() -> H256 {
 
     let mut txns = vec![tx1, tx2, tx3, tx4, tx5];
     let mut hashed_trxn = Vec::new();
+    let mut block_value = Ether::zero();
 
     for i in txns.iter_mut(){
-        let mut hash = Sha256::new();
-        hash.update(i.tx.clone());
-        let result = hash.finalize().to_vec();
-        hashed_trxn.push(result);
+        hashed_trxn.push(Sha256::hash(i.tx.as_bytes()));
+
+        let value = i.value().unwrap_or_else(|e| panic!("`{}`: {}", i.tx, e));
+        block_value = block_value.checked_add(value).expect("block value overflows");
     }
 
     let mut stream = RlpStream::new_list(6);
@@ -50,7 +59,11 @@ fn ethereum_block_hash() -> H256 {
     let timestamp: u32 = 178393938;
     let bits: u32 = 783883893;
     let nonce: u32 = 23838373;
-    let merkle_root = get_merkle_root(hashed_trxn.clone()); 
+    let tree = MerkleTree::<Sha256>::from_leaves(hashed_trxn);
+    println!("");
+    println!("Getting Merkle Root");
+    print!("{}", tree);
+    let merkle_root = tree.root().map(|root| root.to_vec()).unwrap_or_default();
 
 
     stream.append(&block_version);             
@@ -62,66 +75,17 @@ fn ethereum_block_hash() -> H256 {
     stream.append(&merkle_root);     
 
     let rlp_bytes = stream.out();
-    H256::from(keccak256(&rlp_bytes))
+    (H256::from(keccak256(&rlp_bytes)), block_value)
 }
 
 fn main() {
-    let block_hash = ethereum_block_hash();
+    let (block_hash, block_value) = ethereum_block_hash();
     
     println!("");
     // println!("Block hash: 0x{:x}", block_hash);
 
   println!("Block hash: {}", format!("0x{:x}", block_hash).green().bold());
+  println!("Block value: {}", block_value.to_string().green().bold());
 
 }
 
-
-fn get_merkle_root(mut hashes: Vec<Vec<u8>>) -> Vec<u8> {
-
-    println!("");
-    println!("Getting Merkle Root");
-    if hashes.is_empty(){
-        return vec![]
-    }
-    
-    
-    while hashes.len() > 1{
-        let mut next_level = Vec::new();
-        
-        let mut i = 0;
-
-        println!("");
-        println!("CURRENT LEVEL");
-        for (i, h) in hashes.iter().enumerate(){
-            println!("Transaction {}: {}", i, hex::encode(h))
-        }
-
-        while i < hashes.len() {
-
-            if i + 1 < hashes.len() {
-
-            println!("");
-            println!("Hashing ==[{}
-            || {}]==", hex::encode(hashes[i].clone()), hex::encode(hashes[i + 1].clone()));
-
-                let mut hasher = Sha256::new();
-                hasher.update(&hashes[i]);
-                hasher.update(&hashes[i + 1]);
-                let result: Vec<u8> = hasher.finalize().to_vec();
-                println!("");
-                println!("Result: {}", hex::encode(&result));
-                next_level.push(result);
-            }else {
-                println!("Unlinked hash: {}", hex::encode(hashes[i].clone()));
-                next_level.push(hashes[i].clone());
-            }
-            
-            i += 2;
-        }
-        hashes = next_level;
-    }
-
-    hashes.pop().unwrap()
-
-
-}
\ No newline at end of file
@@ -10,8 +10,19 @@ use k256::elliptic_curve::FieldBytes;
 
 use sha3::{Digest, Keccak256};
 
+use gas::units::Ether;
+
 
 fn main() {
+    // Optional amount to fund each wallet with, e.g. `cargo run -- "0.05 ether"`.
+    let funding: Option<Ether> = std::env::args().nth(1).map(|amount| {
+        amount.parse().unwrap_or_else(|e| {
+            eprintln!("error: invalid funding amount `{}`: {}", amount, e);
+            std::process::exit(1);
+        })
+    });
+    let mut total_funding = Ether::zero();
+
     let mut rng = OsRng;  
     
     let mnemonic = Mnemonic::generate_in_with(&mut rng, Language::English, 15).expect("failed to generate mnemonic");
@@ -49,9 +60,17 @@ fn main() {
         println!("Private key in hex(before signing key): {}", hex::encode(raw_prv));
         println!("Public Key in hex: {}", hex::encode(pubkey));
         println!("Address: 0x{}", hex::encode(address));
+        if let Some(amount) = funding {
+            println!("Funding: {}", amount);
+            total_funding = total_funding.checked_add(amount).expect("total funding overflows");
+        }
         println!("");    
     }
 
+    if funding.is_some() {
+        println!("Total funding: {}", total_funding);
+    }
+
 
 }
 